@@ -0,0 +1,57 @@
+use super::validate::IqIssue;
+use std::fmt;
+
+/// 一个描述 IQ 文件读写及校验错误的类型。
+#[derive(Debug)]
+pub enum IqError {
+    /// 读写文件失败。
+    Io(std::io::Error),
+    /// JSON 解析或分段转换失败。
+    Json(serde_json::Error),
+    /// 无法识别文件对应的 ISP 版本。
+    UnknownIspVersion,
+    /// 缺少必需的字段，内容为 JSON Pointer 路径。
+    Missing(String),
+    /// 校验未通过。
+    Invalid(Vec<IqIssue>),
+}
+
+impl fmt::Display for IqError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IqError::Io(err) => write!(f, "{}", err),
+            IqError::Json(err) => write!(f, "{}", err),
+            IqError::UnknownIspVersion => write!(f, "Unknown ISP version"),
+            IqError::Missing(path) => write!(f, "Missing field: {}", path),
+            IqError::Invalid(issues) => {
+                write!(f, "Invalid IQ file")?;
+                for issue in issues {
+                    write!(f, "\n  {}", issue)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for IqError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IqError::Io(err) => Some(err),
+            IqError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for IqError {
+    fn from(err: std::io::Error) -> Self {
+        IqError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for IqError {
+    fn from(err: serde_json::Error) -> Self {
+        IqError::Json(err)
+    }
+}
@@ -0,0 +1,464 @@
+//! Rockchip RKAIQ IQ 标定文件解析与编辑
+//!
+//! 本库解析 RKAIQ v2 的 JSON 格式 IQ 标定文件，不依赖 librkaiq，可直接在主机上运行，
+//! 用于离线检查、批量修改或在下发前校验 IQ 文件。
+//!
+//! 文件整体以 JSON 树保存，未建模的字段在写回时原样保留；类型化的分段视图
+//! （[`SensorCalib`]、[`AeCalib`]、[`AwbCalib`]、[`LscCalib`]、[`CcmCalib`]、
+//! [`GammaCalib`] 及各降噪分段）通过 [`IqFile::section`] 读取，
+//! 修改后通过 [`IqFile::set_section`] 写回，写回时仅改动实际变化的值。
+//!
+//! ```no_run
+//! use rkaiq_iq::{GammaCalib, IqFile, SceneRef};
+//!
+//! let mut iq = IqFile::open("/etc/iqfiles/imx415_CMK-OT2022-PX1_IR0147-50IRC-8M-F20.json")?;
+//! let scene = SceneRef::default();
+//! let mut gamma: GammaCalib = iq.section(&scene)?;
+//! gamma.tuning.offset = Some(0);
+//! iq.set_section(&scene, &gamma)?;
+//! iq.check()?;
+//! iq.save("/tmp/imx415.json")?;
+//! # Ok::<(), rkaiq_iq::IqError>(())
+//! ```
+mod error;
+mod sections;
+mod validate;
+
+pub use error::IqError;
+pub use sections::*;
+pub use validate::IqIssue;
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+use std::str::FromStr;
+
+/// 一个描述 IQ 文件所属 ISP 版本的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IspVersion {
+    /// ISP_HW V20 (RV1126, RV1109)
+    V20,
+    /// ISP_HW V21 (RK356X)
+    V21,
+    /// ISP_HW V30 (RK3588)
+    V30,
+    /// ISP_HW V32 (RV1106, RK3562)
+    V32,
+}
+
+impl IspVersion {
+    /// 全部版本。
+    pub const ALL: [IspVersion; 4] = [
+        IspVersion::V20,
+        IspVersion::V21,
+        IspVersion::V30,
+        IspVersion::V32,
+    ];
+
+    /// 返回子场景中标定数据的键名，如 `scene_isp21`。
+    pub fn scene_key(self) -> &'static str {
+        match self {
+            IspVersion::V20 => "scene_isp20",
+            IspVersion::V21 => "scene_isp21",
+            IspVersion::V30 => "scene_isp30",
+            IspVersion::V32 => "scene_isp32",
+        }
+    }
+
+    /// 返回 Gamma 曲线的节点数量。
+    pub fn gamma_curve_knots(self) -> usize {
+        match self {
+            IspVersion::V20 | IspVersion::V21 => 45,
+            IspVersion::V30 | IspVersion::V32 => 49,
+        }
+    }
+
+    fn from_scene_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.scene_key() == key)
+    }
+}
+
+/// 一个代表场景位置的类型，由主场景名称及子场景名称组成。
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SceneRef {
+    /// 主场景名称，如 `normal`、`hdr`。
+    pub main: String,
+    /// 子场景名称，如 `day`、`night`。
+    pub sub: String,
+}
+
+impl SceneRef {
+    pub fn new<M: Into<String>, S: Into<String>>(main: M, sub: S) -> Self {
+        Self {
+            main: main.into(),
+            sub: sub.into(),
+        }
+    }
+}
+
+impl Default for SceneRef {
+    fn default() -> Self {
+        Self::new("normal", "day")
+    }
+}
+
+/// 一个代表 IQ 标定文件的类型。
+#[derive(Clone, Debug, PartialEq)]
+pub struct IqFile {
+    root: Value,
+    isp: IspVersion,
+}
+
+impl IqFile {
+    /// 从 JSON 文本解析，ISP 版本由场景键名自动识别。
+    pub fn parse(s: &str) -> Result<Self, IqError> {
+        Self::from_value(serde_json::from_str(s)?)
+    }
+
+    /// 从 JSON 文本解析并指定 ISP 版本。
+    pub fn parse_with(s: &str, isp: IspVersion) -> Result<Self, IqError> {
+        Ok(Self {
+            root: serde_json::from_str(s)?,
+            isp,
+        })
+    }
+
+    /// 从 JSON 树创建，ISP 版本由场景键名自动识别。
+    pub fn from_value(root: Value) -> Result<Self, IqError> {
+        let isp = detect_isp_version(&root).ok_or(IqError::UnknownIspVersion)?;
+        Ok(Self { root, isp })
+    }
+
+    /// 从文件加载。
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, IqError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// 返回文件所属的 ISP 版本。
+    pub fn isp_version(&self) -> IspVersion {
+        self.isp
+    }
+
+    /// 返回原始 JSON 树。
+    pub fn value(&self) -> &Value {
+        &self.root
+    }
+
+    /// 返回可修改的原始 JSON 树。
+    pub fn value_mut(&mut self) -> &mut Value {
+        &mut self.root
+    }
+
+    /// 返回全部场景位置。
+    pub fn scenes(&self) -> Vec<SceneRef> {
+        let mut scenes = Vec::new();
+        for main in self.main_scenes() {
+            let main_name = name_of(main);
+            let subs = main.get("sub_scene").and_then(Value::as_array);
+            for sub in subs.into_iter().flatten() {
+                scenes.push(SceneRef::new(main_name, name_of(sub)));
+            }
+        }
+        scenes
+    }
+
+    /// 读取传感器标定信息。
+    pub fn sensor(&self) -> Result<SensorCalib, IqError> {
+        let val = self
+            .root
+            .get("sensor_calib")
+            .ok_or_else(|| IqError::Missing("/sensor_calib".into()))?;
+        Ok(SensorCalib::deserialize(val)?)
+    }
+
+    /// 写回传感器标定信息。
+    pub fn set_sensor(&mut self, sensor: &SensorCalib) -> Result<(), IqError> {
+        let val = serde_json::to_value(sensor)?;
+        let dst = self
+            .root
+            .get_mut("sensor_calib")
+            .ok_or_else(|| IqError::Missing("/sensor_calib".into()))?;
+        merge(dst, val);
+        Ok(())
+    }
+
+    /// 读取指定场景中的分段。
+    pub fn section<S: Section>(&self, scene: &SceneRef) -> Result<S, IqError> {
+        let key = S::key(self.isp).ok_or(IqError::UnknownIspVersion)?;
+        let val = self
+            .scene(scene)?
+            .get(key)
+            .ok_or_else(|| IqError::Missing(self.scene_path(scene, key)))?;
+        Ok(S::deserialize(val)?)
+    }
+
+    /// 将分段写回指定场景，原有分段不存在时新建。
+    pub fn set_section<S: Section>(
+        &mut self,
+        scene: &SceneRef,
+        section: &S,
+    ) -> Result<(), IqError> {
+        let key = S::key(self.isp).ok_or(IqError::UnknownIspVersion)?;
+        let val = serde_json::to_value(section)?;
+        let path = self.scene_path(scene, "");
+        let obj = self
+            .scene_mut(scene)?
+            .as_object_mut()
+            .ok_or(IqError::Missing(path))?;
+        match obj.get_mut(key) {
+            Some(dst) => merge(dst, val),
+            None => {
+                obj.insert(key.to_string(), val);
+            }
+        }
+        Ok(())
+    }
+
+    /// 校验必需字段及曲线、矩阵、校正表的长度与取值，返回发现的全部问题。
+    pub fn validate(&self) -> Vec<IqIssue> {
+        validate::validate(self)
+    }
+
+    /// 校验文件，存在问题时返回 [`IqError::Invalid`]。
+    pub fn check(&self) -> Result<(), IqError> {
+        let issues = self.validate();
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(IqError::Invalid(issues))
+        }
+    }
+
+    /// 以 4 空格缩进格式化为 JSON 文本。
+    pub fn to_string_pretty(&self) -> String {
+        use serde::Serialize;
+        let mut buf = Vec::new();
+        let fmt = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+        let mut ser = serde_json::Serializer::with_formatter(&mut buf, fmt);
+        // 序列化 Value 到内存不会失败，且输出必为 UTF-8。
+        self.root.serialize(&mut ser).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// 保存到文件。
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), IqError> {
+        Ok(std::fs::write(path, self.to_string_pretty())?)
+    }
+
+    fn main_scenes(&self) -> impl Iterator<Item = &Value> {
+        self.root
+            .get("main_scene")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+    }
+
+    fn scene_indices(&self, scene: &SceneRef) -> Option<(usize, usize)> {
+        let (i, main) = self
+            .main_scenes()
+            .enumerate()
+            .find(|(_, v)| name_of(v) == scene.main)?;
+        let j = main
+            .get("sub_scene")?
+            .as_array()?
+            .iter()
+            .position(|v| name_of(v) == scene.sub)?;
+        Some((i, j))
+    }
+
+    fn scene_path(&self, scene: &SceneRef, key: &str) -> String {
+        match self.scene_indices(scene) {
+            Some((i, j)) => format!(
+                "/main_scene/{}/sub_scene/{}/{}/{}",
+                i,
+                j,
+                self.isp.scene_key(),
+                key
+            ),
+            None => format!("/main_scene[{}]/sub_scene[{}]", scene.main, scene.sub),
+        }
+    }
+
+    fn scene(&self, scene: &SceneRef) -> Result<&Value, IqError> {
+        let (i, j) = self
+            .scene_indices(scene)
+            .ok_or_else(|| IqError::Missing(self.scene_path(scene, "")))?;
+        self.root["main_scene"][i]["sub_scene"][j]
+            .get(self.isp.scene_key())
+            .ok_or_else(|| IqError::Missing(self.scene_path(scene, "")))
+    }
+
+    fn scene_mut(&mut self, scene: &SceneRef) -> Result<&mut Value, IqError> {
+        let path = self.scene_path(scene, "");
+        let (i, j) = self
+            .scene_indices(scene)
+            .ok_or_else(|| IqError::Missing(path.clone()))?;
+        self.root["main_scene"][i]["sub_scene"][j]
+            .get_mut(self.isp.scene_key())
+            .ok_or(IqError::Missing(path))
+    }
+}
+
+impl FromStr for IqFile {
+    type Err = IqError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+fn name_of(val: &Value) -> &str {
+    val.get("name").and_then(Value::as_str).unwrap_or_default()
+}
+
+fn detect_isp_version(root: &Value) -> Option<IspVersion> {
+    let main = root.get("main_scene")?.as_array()?.first()?;
+    let sub = main.get("sub_scene")?.as_array()?.first()?;
+    sub.as_object()?
+        .keys()
+        .find_map(|k| IspVersion::from_scene_key(k))
+}
+
+/// 将 `src` 合并到 `dst`，仅改动实际变化的值。
+///
+/// 对象按键合并并删除 `src` 中不存在的键；等长数组逐项合并；
+/// 数值相等时保留原有写法（如 `10` 不会被改写为 `10.0`）。
+fn merge(dst: &mut Value, src: Value) {
+    match (dst, src) {
+        (Value::Object(dst), Value::Object(mut src)) => {
+            dst.retain(|k, _| src.contains_key(k));
+            for (k, v) in dst.iter_mut() {
+                if let Some(s) = src.remove(k) {
+                    merge(v, s);
+                }
+            }
+            dst.extend(src);
+        }
+        (Value::Array(dst), Value::Array(src)) if dst.len() == src.len() => {
+            for (d, s) in dst.iter_mut().zip(src) {
+                merge(d, s);
+            }
+        }
+        (Value::Number(d), Value::Number(s)) if d.as_f64() == s.as_f64() => {}
+        (dst, src) => *dst = src,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        let curve: Vec<u32> = (0..45).map(|i| i * 4095 / 44).collect();
+        let coeff = vec![1024; LSC_SAMPLES];
+        let samples = json!({ "uCoeff": coeff });
+        json!({
+            "sensor_calib": {
+                "resolution": { "width": 2688, "height": 1520 },
+                "CISMinFps": 10,
+                "CISFlip": 0
+            },
+            "module_calib": {},
+            "main_scene": [{
+                "name": "normal",
+                "sub_scene": [{
+                    "name": "day",
+                    "scene_isp21": {
+                        "ae_calib": { "CommCtrl": { "Enable": 1, "AecRunInterval": 0 } },
+                        "wb_v21": { "control": { "byPass": 0, "mode": "CALIB_WB_MODE_AUTO" } },
+                        "lsc_v2": {
+                            "common": { "enable": true },
+                            "tbl": { "tableAll": [{
+                                "name": "2688x1520_A_70",
+                                "resolution": "2688x1520",
+                                "illumination": "A",
+                                "vignetting": 70,
+                                "lsc_samples_red": samples,
+                                "lsc_samples_greenR": samples,
+                                "lsc_samples_greenB": samples,
+                                "lsc_samples_blue": samples
+                            }]}
+                        },
+                        "ccm_calib": {
+                            "control": { "enable": 1 },
+                            "matrixAll": [{
+                                "name": "A_100",
+                                "illumination": "A",
+                                "saturation": 100,
+                                "ccMatrix": [1.5, -0.3, -0.2, -0.2, 1.4, -0.2, 0, -0.5, 1.5],
+                                "ccOffsets": [0, 0, 0]
+                            }]
+                        },
+                        "agamma_calib": { "GammaTuningPara": {
+                            "Gamma_en": 1,
+                            "Gamma_out_segnum": "GAMMATYPE_LOG",
+                            "Gamma_out_offset": 0,
+                            "Gamma_curve": curve
+                        }},
+                        "bayernr_v2": { "Version": "V2" },
+                        "ynr_v2": { "Version": "V2" },
+                        "cnr_v1": { "Version": "V1" },
+                        "sharp_v3": { "Version": "V3" }
+                    }
+                }]
+            }]
+        })
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        let iq = IqFile::parse(&sample().to_string()).unwrap();
+        assert_eq!(iq.isp_version(), IspVersion::V21);
+        assert_eq!(iq.scenes(), vec![SceneRef::default()]);
+        assert!(iq.validate().is_empty(), "{:?}", iq.validate());
+        let sensor = iq.sensor().unwrap();
+        assert_eq!(sensor.resolution.unwrap().width, 2688);
+        let awb: AwbCalib = iq.section(&SceneRef::default()).unwrap();
+        assert!(!awb.control.unwrap().bypass.unwrap().is_on());
+    }
+
+    #[test]
+    fn test_edit_round_trip() {
+        let mut iq = IqFile::from_value(sample()).unwrap();
+        let scene = SceneRef::default();
+        let mut gamma: GammaCalib = iq.section(&scene).unwrap();
+        gamma.tuning.curve[1] = 200;
+        gamma.tuning.enable.as_mut().unwrap().set(false);
+        iq.set_section(&scene, &gamma).unwrap();
+        let mut sensor = iq.sensor().unwrap();
+        sensor.min_fps = Some(10.0);
+        iq.set_sensor(&sensor).unwrap();
+
+        let text = iq.to_string_pretty();
+        assert!(text.contains("\"CISMinFps\": 10,"));
+        let iq = IqFile::parse(&text).unwrap();
+        let gamma: GammaCalib = iq.section(&scene).unwrap();
+        assert_eq!(gamma.tuning.curve[1], 200);
+        assert_eq!(gamma.tuning.enable, Some(Flag::Int(0)));
+        let orig = sample();
+        let path = "/main_scene/0/sub_scene/0/scene_isp21/lsc_v2";
+        assert_eq!(iq.value().pointer(path), orig.pointer(path));
+    }
+
+    #[test]
+    fn test_validate_issues() {
+        let mut root = sample();
+        let scene = &mut root["main_scene"][0]["sub_scene"][0]["scene_isp21"];
+        scene.as_object_mut().unwrap().remove("sharp_v3");
+        scene["agamma_calib"]["GammaTuningPara"]["Gamma_curve"][3] = json!(0);
+        scene["ccm_calib"]["matrixAll"][0]["ccOffsets"] = json!([0, 0]);
+        let iq = IqFile::from_value(root).unwrap();
+        let paths: Vec<_> = iq.validate().into_iter().map(|i| i.path).collect();
+        let base = "/main_scene/0/sub_scene/0/scene_isp21";
+        assert_eq!(
+            paths,
+            vec![
+                format!("{}/sharp_v3", base),
+                format!("{}/agamma_calib/GammaTuningPara/Gamma_curve/3", base),
+                format!("{}/ccm_calib/matrixAll/0/ccOffsets", base),
+            ]
+        );
+        assert!(matches!(iq.check(), Err(IqError::Invalid(_))));
+    }
+}
@@ -0,0 +1,423 @@
+use super::IspVersion;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// LSC 每个通道的采样点数量（17x17）。
+pub const LSC_SAMPLES: usize = 289;
+/// CCM 矩阵元素数量。
+pub const CCM_MATRIX_LEN: usize = 9;
+/// CCM 偏移元素数量。
+pub const CCM_OFFSETS_LEN: usize = 3;
+/// Gamma 曲线最大输出值。
+pub const GAMMA_CURVE_MAX: u16 = 4095;
+
+/// 一个代表场景内标定分段的特型。
+///
+/// 分段在不同 ISP 版本中的键名不同，例如 AWB 在 ISP20 上为 `wb_v20`，
+/// 在 ISP21 上为 `wb_v21`。
+pub trait Section: Serialize + DeserializeOwned {
+    /// 返回该分段在指定 ISP 版本场景中的键名，`None` 表示该版本没有此分段。
+    fn key(isp: IspVersion) -> Option<&'static str>;
+}
+
+/// 一个代表开关字段的类型。
+///
+/// IQ 文件中的开关既有写作 `true`/`false` 的，也有写作 `1`/`0` 的，
+/// 此类型保留原有写法以便原样写回。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Flag {
+    Bool(bool),
+    Int(i64),
+}
+
+impl Flag {
+    /// 返回是否开启。
+    pub fn is_on(self) -> bool {
+        match self {
+            Flag::Bool(v) => v,
+            Flag::Int(v) => v != 0,
+        }
+    }
+
+    /// 设置开关，保持原有写法。
+    pub fn set(&mut self, on: bool) {
+        match self {
+            Flag::Bool(v) => *v = on,
+            Flag::Int(v) => *v = on as i64,
+        }
+    }
+}
+
+impl From<bool> for Flag {
+    fn from(val: bool) -> Self {
+        Flag::Bool(val)
+    }
+}
+
+/// 一个代表传感器标定信息（`sensor_calib`）的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SensorCalib {
+    /// 标定分辨率。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+    /// 最低帧率。
+    #[serde(rename = "CISMinFps", default, skip_serializing_if = "Option::is_none")]
+    pub min_fps: Option<f64>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表分辨率的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表自动曝光标定（`ae_calib`）的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AeCalib {
+    /// 公共控制参数。
+    #[serde(rename = "CommCtrl", default, skip_serializing_if = "Option::is_none")]
+    pub comm_ctrl: Option<AeCommCtrl>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表自动曝光公共控制参数的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AeCommCtrl {
+    /// 是否开启。
+    #[serde(rename = "Enable", default, skip_serializing_if = "Option::is_none")]
+    pub enable: Option<Flag>,
+    /// 运行间隔帧数。
+    #[serde(
+        rename = "AecRunInterval",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub run_interval: Option<u32>,
+    /// 运行模式，如 `RK_AIQ_OP_MODE_AUTO`。
+    #[serde(rename = "AecOpType", default, skip_serializing_if = "Option::is_none")]
+    pub op_type: Option<String>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Section for AeCalib {
+    fn key(_isp: IspVersion) -> Option<&'static str> {
+        Some("ae_calib")
+    }
+}
+
+/// 一个代表自动白平衡标定（`wb_vXX`）的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AwbCalib {
+    /// 控制参数。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control: Option<AwbControl>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表自动白平衡控制参数的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AwbControl {
+    /// 是否旁路。
+    #[serde(rename = "byPass", default, skip_serializing_if = "Option::is_none")]
+    pub bypass: Option<Flag>,
+    /// 工作模式，如 `CALIB_WB_MODE_AUTO`。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Section for AwbCalib {
+    fn key(isp: IspVersion) -> Option<&'static str> {
+        Some(match isp {
+            IspVersion::V20 => "wb_v20",
+            IspVersion::V21 | IspVersion::V30 => "wb_v21",
+            IspVersion::V32 => "wb_v32",
+        })
+    }
+}
+
+/// 一个代表镜头阴影校正标定（`lsc_v2`）的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LscCalib {
+    /// 公共参数。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub common: Option<LscCommon>,
+    /// 校正表。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tbl: Option<LscTableSet>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表镜头阴影校正公共参数的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LscCommon {
+    /// 是否开启。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable: Option<Flag>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表镜头阴影校正表集合的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LscTableSet {
+    /// 按分辨率及光源划分的校正表。
+    #[serde(rename = "tableAll")]
+    pub tables: Vec<LscTable>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表镜头阴影校正表的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LscTable {
+    /// 表名称。
+    pub name: String,
+    /// 适用分辨率，如 `2688x1520`。
+    pub resolution: String,
+    /// 适用光源，如 `D65`。
+    pub illumination: String,
+    /// 暗角校正比例。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vignetting: Option<f64>,
+    #[serde(rename = "lsc_samples_red")]
+    pub red: LscSamples,
+    #[serde(rename = "lsc_samples_greenR")]
+    pub green_r: LscSamples,
+    #[serde(rename = "lsc_samples_greenB")]
+    pub green_b: LscSamples,
+    #[serde(rename = "lsc_samples_blue")]
+    pub blue: LscSamples,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表镜头阴影校正单通道采样的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LscSamples {
+    /// 采样系数，共 [`LSC_SAMPLES`] 个。
+    #[serde(rename = "uCoeff")]
+    pub coeff: Vec<u16>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Section for LscCalib {
+    fn key(_isp: IspVersion) -> Option<&'static str> {
+        Some("lsc_v2")
+    }
+}
+
+/// 一个代表颜色校正矩阵标定（`ccm_calib`）的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CcmCalib {
+    /// 控制参数。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control: Option<CcmControl>,
+    /// 按光源及饱和度划分的矩阵。
+    #[serde(rename = "matrixAll", default, skip_serializing_if = "Option::is_none")]
+    pub matrices: Option<Vec<CcmMatrix>>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表颜色校正控制参数的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CcmControl {
+    /// 是否开启。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable: Option<Flag>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表颜色校正矩阵的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CcmMatrix {
+    /// 矩阵名称，如 `A_100`。
+    pub name: String,
+    /// 适用光源。
+    pub illumination: String,
+    /// 饱和度。
+    pub saturation: f64,
+    /// 3x3 矩阵，共 [`CCM_MATRIX_LEN`] 个元素。
+    #[serde(rename = "ccMatrix")]
+    pub matrix: Vec<f64>,
+    /// 偏移，共 [`CCM_OFFSETS_LEN`] 个元素。
+    #[serde(rename = "ccOffsets")]
+    pub offsets: Vec<f64>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Section for CcmCalib {
+    fn key(isp: IspVersion) -> Option<&'static str> {
+        Some(match isp {
+            IspVersion::V32 => "ccm_calib_v2",
+            _ => "ccm_calib",
+        })
+    }
+}
+
+/// 一个代表 Gamma 标定（`agamma_calib`）的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GammaCalib {
+    /// 调试参数。
+    #[serde(rename = "GammaTuningPara")]
+    pub tuning: GammaTuning,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// 一个代表 Gamma 调试参数的类型。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GammaTuning {
+    /// 是否开启。
+    #[serde(rename = "Gamma_en", default, skip_serializing_if = "Option::is_none")]
+    pub enable: Option<Flag>,
+    /// 分段方式，如 `GAMMATYPE_LOG`。
+    #[serde(
+        rename = "Gamma_out_segnum",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub segment: Option<String>,
+    /// 输出偏移。
+    #[serde(
+        rename = "Gamma_out_offset",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub offset: Option<i32>,
+    /// 曲线，长度由 [`IspVersion::gamma_curve_knots`] 决定。
+    #[serde(rename = "Gamma_curve")]
+    pub curve: Vec<u16>,
+    /// 未建模的字段。
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Section for GammaCalib {
+    fn key(_isp: IspVersion) -> Option<&'static str> {
+        Some("agamma_calib")
+    }
+}
+
+macro_rules! versioned_section {
+    ($(#[$meta:meta])* $name:ident { $($isp:ident => $key:expr),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+        pub struct $name {
+            /// 算法版本，如 `V2`。
+            #[serde(rename = "Version", default, skip_serializing_if = "Option::is_none")]
+            pub version: Option<String>,
+            /// 未建模的字段。
+            #[serde(flatten)]
+            pub other: Map<String, Value>,
+        }
+
+        impl Section for $name {
+            fn key(isp: IspVersion) -> Option<&'static str> {
+                match isp {
+                    $(IspVersion::$isp => $key,)*
+                }
+            }
+        }
+    };
+}
+
+versioned_section! {
+    /// 一个代表 Bayer 域降噪标定的类型。
+    BayerNrCalib {
+        V20 => Some("bayernr_v1"),
+        V21 => Some("bayernr_v2"),
+        V30 => Some("bayer2dnr_v2"),
+        V32 => Some("bayer2dnr_v23"),
+    }
+}
+
+versioned_section! {
+    /// 一个代表 Bayer 域时域降噪标定的类型。
+    BayerTnrCalib {
+        V20 => None,
+        V21 => None,
+        V30 => Some("bayertnr_v2"),
+        V32 => Some("bayertnr_v23"),
+    }
+}
+
+versioned_section! {
+    /// 一个代表亮度降噪标定的类型。
+    YnrCalib {
+        V20 => Some("ynr_v1"),
+        V21 => Some("ynr_v2"),
+        V30 => Some("ynr_v3"),
+        V32 => Some("ynr_v22"),
+    }
+}
+
+versioned_section! {
+    /// 一个代表色度降噪标定的类型。
+    CnrCalib {
+        V20 => Some("uvnr_v1"),
+        V21 => Some("cnr_v1"),
+        V30 => Some("cnr_v2"),
+        V32 => Some("cnr_v30"),
+    }
+}
+
+versioned_section! {
+    /// 一个代表锐化标定的类型。
+    SharpCalib {
+        V20 => Some("sharp_v1"),
+        V21 => Some("sharp_v3"),
+        V30 => Some("sharp_v4"),
+        V32 => Some("sharp_v33"),
+    }
+}
+
+/// 返回指定 ISP 版本场景中必需的分段键名。
+pub fn required_sections(isp: IspVersion) -> Vec<&'static str> {
+    [
+        AeCalib::key(isp),
+        AwbCalib::key(isp),
+        LscCalib::key(isp),
+        CcmCalib::key(isp),
+        GammaCalib::key(isp),
+        BayerNrCalib::key(isp),
+        BayerTnrCalib::key(isp),
+        YnrCalib::key(isp),
+        CnrCalib::key(isp),
+        SharpCalib::key(isp),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
@@ -0,0 +1,189 @@
+use super::sections::{
+    required_sections, CcmCalib, GammaCalib, LscCalib, Section, CCM_MATRIX_LEN, CCM_OFFSETS_LEN,
+    GAMMA_CURVE_MAX, LSC_SAMPLES,
+};
+use super::{IqFile, IspVersion};
+use serde_json::Value;
+use std::fmt;
+
+/// 一个代表校验问题的类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IqIssue {
+    /// 出现问题的字段，JSON Pointer 路径。
+    pub path: String,
+    /// 问题描述。
+    pub message: String,
+}
+
+impl IqIssue {
+    fn new<P: Into<String>, M: Into<String>>(path: P, message: M) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for IqIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+pub(crate) fn validate(iq: &IqFile) -> Vec<IqIssue> {
+    let mut issues = Vec::new();
+    let root = iq.value();
+    let isp = iq.isp_version();
+
+    check_sensor(root, &mut issues);
+
+    let scenes = match root.get("main_scene").and_then(Value::as_array) {
+        Some(scenes) if !scenes.is_empty() => scenes,
+        _ => {
+            issues.push(IqIssue::new("/main_scene", "missing or empty"));
+            return issues;
+        }
+    };
+    for (i, main) in scenes.iter().enumerate() {
+        let subs = match main.get("sub_scene").and_then(Value::as_array) {
+            Some(subs) if !subs.is_empty() => subs,
+            _ => {
+                let path = format!("/main_scene/{}/sub_scene", i);
+                issues.push(IqIssue::new(path, "missing or empty"));
+                continue;
+            }
+        };
+        for (j, sub) in subs.iter().enumerate() {
+            let path = format!("/main_scene/{}/sub_scene/{}/{}", i, j, isp.scene_key());
+            match sub.get(isp.scene_key()) {
+                Some(scene) if scene.is_object() => check_scene(scene, isp, &path, &mut issues),
+                _ => issues.push(IqIssue::new(path, "missing scene")),
+            }
+        }
+    }
+    issues
+}
+
+fn check_sensor(root: &Value, issues: &mut Vec<IqIssue>) {
+    let sensor = match root.get("sensor_calib") {
+        Some(sensor) if sensor.is_object() => sensor,
+        _ => {
+            issues.push(IqIssue::new("/sensor_calib", "missing"));
+            return;
+        }
+    };
+    for key in ["width", "height"] {
+        let ok = sensor
+            .pointer(&format!("/resolution/{}", key))
+            .and_then(Value::as_u64)
+            .is_some_and(|v| v > 0);
+        if !ok {
+            let path = format!("/sensor_calib/resolution/{}", key);
+            issues.push(IqIssue::new(path, "must be a positive integer"));
+        }
+    }
+}
+
+fn check_scene(scene: &Value, isp: IspVersion, path: &str, issues: &mut Vec<IqIssue>) {
+    for key in required_sections(isp) {
+        if !scene.get(key).is_some_and(Value::is_object) {
+            issues.push(IqIssue::new(format!("{}/{}", path, key), "missing section"));
+        }
+    }
+    let section = |key: Option<&'static str>| {
+        key.and_then(|k| scene.get(k).map(|v| (v, format!("{}/{}", path, k))))
+    };
+    if let Some((gamma, path)) = section(GammaCalib::key(isp)) {
+        check_gamma(gamma, isp, &path, issues);
+    }
+    if let Some((ccm, path)) = section(CcmCalib::key(isp)) {
+        check_ccm(ccm, &path, issues);
+    }
+    if let Some((lsc, path)) = section(LscCalib::key(isp)) {
+        check_lsc(lsc, &path, issues);
+    }
+}
+
+fn check_gamma(gamma: &Value, isp: IspVersion, path: &str, issues: &mut Vec<IqIssue>) {
+    let path = format!("{}/GammaTuningPara/Gamma_curve", path);
+    let curve = match gamma
+        .pointer("/GammaTuningPara/Gamma_curve")
+        .and_then(Value::as_array)
+    {
+        Some(curve) => curve,
+        None => {
+            issues.push(IqIssue::new(path, "missing"));
+            return;
+        }
+    };
+    let knots = isp.gamma_curve_knots();
+    if curve.len() != knots {
+        let msg = format!("expected {} knots, found {}", knots, curve.len());
+        issues.push(IqIssue::new(path, msg));
+        return;
+    }
+    let mut last = 0;
+    for (i, v) in curve.iter().enumerate() {
+        match v.as_u64() {
+            Some(v) if v > GAMMA_CURVE_MAX as u64 => {
+                let msg = format!("exceeds {}", GAMMA_CURVE_MAX);
+                issues.push(IqIssue::new(format!("{}/{}", path, i), msg));
+            }
+            Some(v) if v < last => {
+                issues.push(IqIssue::new(format!("{}/{}", path, i), "not monotonic"));
+            }
+            Some(v) => last = v,
+            None => issues.push(IqIssue::new(format!("{}/{}", path, i), "not an integer")),
+        }
+    }
+}
+
+fn check_ccm(ccm: &Value, path: &str, issues: &mut Vec<IqIssue>) {
+    let matrices = match ccm.get("matrixAll").and_then(Value::as_array) {
+        Some(matrices) => matrices,
+        None => return,
+    };
+    for (i, m) in matrices.iter().enumerate() {
+        for (key, len) in [("ccMatrix", CCM_MATRIX_LEN), ("ccOffsets", CCM_OFFSETS_LEN)] {
+            check_len(
+                m.get(key),
+                len,
+                &format!("{}/matrixAll/{}/{}", path, i, key),
+                issues,
+            );
+        }
+    }
+}
+
+fn check_lsc(lsc: &Value, path: &str, issues: &mut Vec<IqIssue>) {
+    let tables = match lsc.pointer("/tbl/tableAll").and_then(Value::as_array) {
+        Some(tables) => tables,
+        None => {
+            issues.push(IqIssue::new(format!("{}/tbl/tableAll", path), "missing"));
+            return;
+        }
+    };
+    for (i, t) in tables.iter().enumerate() {
+        for ch in [
+            "lsc_samples_red",
+            "lsc_samples_greenR",
+            "lsc_samples_greenB",
+            "lsc_samples_blue",
+        ] {
+            let p = format!("{}/tbl/tableAll/{}/{}/uCoeff", path, i, ch);
+            let coeff = t.get(ch).and_then(|s| s.get("uCoeff"));
+            check_len(coeff, LSC_SAMPLES, &p, issues);
+        }
+    }
+}
+
+fn check_len(val: Option<&Value>, len: usize, path: &str, issues: &mut Vec<IqIssue>) {
+    match val.and_then(Value::as_array) {
+        Some(arr) if arr.len() == len => {}
+        Some(arr) => {
+            let msg = format!("expected {} elements, found {}", len, arr.len());
+            issues.push(IqIssue::new(path, msg));
+        }
+        None => issues.push(IqIssue::new(path, "missing")),
+    }
+}
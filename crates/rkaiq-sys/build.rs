@@ -68,6 +68,8 @@ fn main() {
     writeln!(wrapper, "#include <rk_aiq_user_api2_amerge.h>").unwrap();
     writeln!(wrapper, "#include <rk_aiq_user_api2_anr.h>").unwrap();
     writeln!(wrapper, "#include <rk_aiq_user_api2_asharp_v3.h>").unwrap();
+    #[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+    writeln!(wrapper, "#include <rk_aiq_user_api2_asharp_v4.h>").unwrap();
     writeln!(wrapper, "#include <rk_aiq_user_api2_atmo.h>").unwrap();
     writeln!(wrapper, "#include <rk_aiq_user_api2_awb.h>").unwrap();
     writeln!(wrapper, "#include <rk_aiq_user_api2_aynr_v2.h>").unwrap();
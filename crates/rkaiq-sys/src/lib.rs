@@ -87,7 +87,7 @@ impl fmt::Debug for rk_aiq_static_info_t {
             .field("fl_strth_adj_sup", &self.fl_strth_adj_sup)
             .field("has_irc", &self.has_irc)
             .field("fl_ir_strth_adj_sup", &self.fl_ir_strth_adj_sup);
-        #[cfg(feature = "v3_0")]
+        #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
         ds.field("_is_1608_sensor", &self._is_1608_sensor)
             .field("is_multi_isp_mode", &self.is_multi_isp_mode)
             .field("multi_isp_extended_pixel", &self.multi_isp_extended_pixel)
@@ -1,40 +1,212 @@
+//! 色彩处理
+//!
+//! ACP 模块用于调节图像的亮度、对比度、饱和度及色调。
 use super::context::Context;
 use super::error::XCamError;
 use super::ffi;
-use super::types::XCamResult;
+use super::types::{AcpAttr, XCamResult};
+use std::convert::TryFrom;
+
+/// 一个代表色彩处理等级的类型，范围：[0,255]，默认值 128。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct AcpLevel(u8);
+
+impl AcpLevel {
+    /// 最小等级。
+    pub const MIN: Self = Self(0);
+    /// 最大等级。
+    pub const MAX: Self = Self(255);
+    /// 中性等级，此时对图像无影响。
+    pub const NEUTRAL: Self = Self(128);
+
+    /// 创建一个色彩处理等级。
+    pub const fn new(val: u8) -> Self {
+        Self(val)
+    }
+
+    /// 从相对中性等级的偏移量创建，偏移量范围：[-128,127]，超出范围时返回错误。
+    pub fn from_offset(offset: i16) -> XCamResult<Self> {
+        u8::try_from(offset as i32 + 128)
+            .map(Self)
+            .map_err(|_| out_of_range())
+    }
+
+    /// 返回等级值。
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+
+    /// 返回相对中性等级的偏移量。
+    pub const fn offset(self) -> i16 {
+        self.0 as i16 - 128
+    }
+}
+
+impl Default for AcpLevel {
+    fn default() -> Self {
+        Self::NEUTRAL
+    }
+}
+
+impl From<u8> for AcpLevel {
+    fn from(val: u8) -> Self {
+        Self(val)
+    }
+}
+
+impl From<AcpLevel> for u8 {
+    fn from(val: AcpLevel) -> Self {
+        val.0
+    }
+}
+
+impl TryFrom<u32> for AcpLevel {
+    type Error = XCamError;
+
+    fn try_from(val: u32) -> Result<Self, Self::Error> {
+        u8::try_from(val).map(Self).map_err(|_| out_of_range())
+    }
+}
+
+fn out_of_range() -> XCamError {
+    XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_OUTOFRANGE)
+}
+
+/// 一个代表全部色彩处理参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AcpLevels {
+    /// 亮度等级，其偏移量即为亮度偏移。
+    pub brightness: AcpLevel,
+    /// 对比度等级。
+    pub contrast: AcpLevel,
+    /// 饱和度等级。
+    pub saturation: AcpLevel,
+    /// 色调等级，其偏移量即为色调旋转量。
+    pub hue: AcpLevel,
+}
+
+impl From<&AcpAttr> for AcpLevels {
+    fn from(val: &AcpAttr) -> Self {
+        Self {
+            brightness: AcpLevel(val.brightness),
+            contrast: AcpLevel(val.contrast),
+            saturation: AcpLevel(val.saturation),
+            hue: AcpLevel(val.hue),
+        }
+    }
+}
 
 pub trait AutoColorManagment {
+    /// 获取色彩处理属性。
+    fn get_acp_attrib(&self) -> XCamResult<AcpAttr>;
+
+    /// 设置色彩处理属性。
+    fn set_acp_attrib<T: Into<AcpAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 开启色彩处理模块。
+    fn enable_acp(&self) -> XCamResult<()>;
+
+    /// 关闭色彩处理模块。
+    fn disable_acp(&self) -> XCamResult<()>;
+
+    /// 获取全部色彩处理等级。
+    fn get_acp_levels(&self) -> XCamResult<AcpLevels>;
+
+    /// 一次性设置全部色彩处理等级，仅触发一次 ISP 更新。
+    fn set_acp_levels(&self, levels: AcpLevels) -> XCamResult<()>;
+
     /// 获取亮度等级。
     fn get_brightness(&self) -> XCamResult<u32>;
 
-    /// 设置亮度等级，范围：[0,255]。
+    /// 设置亮度等级，范围：[0,255]，超出范围时返回错误。
     fn set_brightness(&self, val: u32) -> XCamResult<()>;
 
     /// 获取对比度等级。
     fn get_contrast(&self) -> XCamResult<u32>;
 
-    /// 设置对比度等级，范围：[0,255]。
+    /// 设置对比度等级，范围：[0,255]，超出范围时返回错误。
     fn set_contrast(&self, val: u32) -> XCamResult<()>;
 
     /// 获取饱和度等级。
     fn get_saturation(&self) -> XCamResult<u32>;
 
-    /// 设置饱和度等级，范围：[0,255]。
+    /// 设置饱和度等级，范围：[0,255]，超出范围时返回错误。
     fn set_saturation(&self, val: u32) -> XCamResult<()>;
 
     /// 获取色度等级。
     fn get_hue(&self) -> XCamResult<u32>;
 
-    /// 设置色度等级，范围：[0,255]。
+    /// 设置色度等级，范围：[0,255]，超出范围时返回错误。
     fn set_hue(&self, val: u32) -> XCamResult<()>;
 }
 
 impl AutoColorManagment for Context {
+    fn get_acp_attrib(&self) -> XCamResult<AcpAttr> {
+        let mut attr = AcpAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_acp_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    fn set_acp_attrib<T: Into<AcpAttr>>(&self, attr: T) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_acp_SetAttrib(
+                self.lock().as_ptr(),
+                attr.into(),
+            ))
+            .ok()
+        }
+    }
+
+    fn enable_acp(&self) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_sysctl_setModuleCtl(
+                self.lock().as_ptr(),
+                ffi::rk_aiq_module_id_t::RK_MODULE_CP,
+                true,
+            ))
+            .ok()
+        }
+    }
+
+    fn disable_acp(&self) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_sysctl_setModuleCtl(
+                self.lock().as_ptr(),
+                ffi::rk_aiq_module_id_t::RK_MODULE_CP,
+                false,
+            ))
+            .ok()
+        }
+    }
+
+    fn get_acp_levels(&self) -> XCamResult<AcpLevels> {
+        self.get_acp_attrib().map(|attr| AcpLevels::from(&attr))
+    }
+
+    fn set_acp_levels(&self, levels: AcpLevels) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_acp_attrib()?;
+        attr.brightness = levels.brightness.get();
+        attr.contrast = levels.contrast.get();
+        attr.saturation = levels.saturation.get();
+        attr.hue = levels.hue.get();
+        self.set_acp_attrib(attr)
+    }
+
     fn get_brightness(&self) -> XCamResult<u32> {
         let mut val: u32 = 0;
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getBrightness(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut val,
             ))
             .ok()
@@ -43,30 +215,29 @@ impl AutoColorManagment for Context {
     }
 
     fn set_brightness(&self, val: u32) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi_setBrightness(self.internal.as_ptr(), val)).ok() }
+        let val = AcpLevel::try_from(val)?.get() as u32;
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_setBrightness(self.lock().as_ptr(), val)).ok() }
     }
 
     fn get_contrast(&self) -> XCamResult<u32> {
         let mut val: u32 = 0;
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_getContrast(
-                self.internal.as_ptr(),
-                &mut val,
-            ))
-            .ok()
-            .map(|_| val)
+            XCamError::from(ffi::rk_aiq_uapi_getContrast(self.lock().as_ptr(), &mut val))
+                .ok()
+                .map(|_| val)
         }
     }
 
     fn set_contrast(&self, val: u32) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi_setContrast(self.internal.as_ptr(), val)).ok() }
+        let val = AcpLevel::try_from(val)?.get() as u32;
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_setContrast(self.lock().as_ptr(), val)).ok() }
     }
 
     fn get_saturation(&self) -> XCamResult<u32> {
         let mut val: u32 = 0;
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getSaturation(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut val,
             ))
             .ok()
@@ -75,19 +246,44 @@ impl AutoColorManagment for Context {
     }
 
     fn set_saturation(&self, val: u32) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi_setSaturation(self.internal.as_ptr(), val)).ok() }
+        let val = AcpLevel::try_from(val)?.get() as u32;
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_setSaturation(self.lock().as_ptr(), val)).ok() }
     }
 
     fn get_hue(&self) -> XCamResult<u32> {
         let mut val: u32 = 0;
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_getHue(self.internal.as_ptr(), &mut val))
+            XCamError::from(ffi::rk_aiq_uapi_getHue(self.lock().as_ptr(), &mut val))
                 .ok()
                 .map(|_| val)
         }
     }
 
     fn set_hue(&self, val: u32) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi_setHue(self.internal.as_ptr(), val)).ok() }
+        let val = AcpLevel::try_from(val)?.get() as u32;
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_setHue(self.lock().as_ptr(), val)).ok() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acp_level() {
+        assert_eq!(AcpLevel::default(), AcpLevel::NEUTRAL);
+        assert_eq!(AcpLevel::from_offset(0), Ok(AcpLevel::NEUTRAL));
+        assert_eq!(AcpLevel::from_offset(-128), Ok(AcpLevel::MIN));
+        assert_eq!(AcpLevel::from_offset(127), Ok(AcpLevel::MAX));
+        assert!(AcpLevel::from_offset(-129).is_err());
+        assert!(AcpLevel::from_offset(128).is_err());
+        assert!(AcpLevel::from_offset(i16::MAX).is_err());
+        assert!(AcpLevel::from_offset(i16::MIN).is_err());
+        assert_eq!(AcpLevel::new(100).offset(), -28);
+        assert_eq!(AcpLevel::MAX.offset(), 127);
+
+        assert_eq!(AcpLevel::try_from(255u32), Ok(AcpLevel::MAX));
+        assert!(AcpLevel::try_from(256u32).is_err());
+        assert_eq!(u8::from(AcpLevel::from(42)), 42);
     }
 }
@@ -4,6 +4,7 @@ use super::ffi;
 use super::types::{AntiFlickerMode, ExpPwrLineFreq, OpMode, PaRange, XCamResult};
 
 #[cfg(feature = "v1_0")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AeMode {
     Auto,
     IrisPrior,
@@ -35,6 +36,7 @@ impl From<AeMode> for ffi::aeMode_t {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AeMeasAreaType {
     Auto,
     Up,
@@ -72,6 +74,20 @@ impl From<AeMeasAreaType> for ffi::aeMeasAreaType_e {
     }
 }
 
+/// 一个代表当前曝光结果的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExpInfo {
+    /// 总增益，即模拟增益、数字增益及 ISP 增益之积。
+    pub gain: f32,
+    /// 曝光时间，单位：秒。
+    pub time: f32,
+    /// 平均亮度。
+    pub mean_luma: f32,
+    /// 是否已收敛。
+    pub converged: bool,
+}
+
 pub trait AutoExposure {
     #[cfg(feature = "v1_0")]
     fn get_ae_mode(&self) -> XCamResult<AeMode>;
@@ -92,6 +108,9 @@ pub trait AutoExposure {
     fn set_manual_exp_ms(&self, gain: f32, time_ms: usize) -> XCamResult<()>;
     fn set_manual_exp_us(&self, gain: f32, time_us: usize) -> XCamResult<()>;
 
+    /// 查询当前曝光结果（线性模式）。
+    fn query_exp_info(&self) -> XCamResult<ExpInfo>;
+
     fn set_blc_mode(&self, enabled: bool, mode: AeMeasAreaType) -> XCamResult<()>;
     fn set_blc_strength(&self, strength: i32) -> XCamResult<()>;
 
@@ -131,30 +150,24 @@ impl AutoExposure for Context {
     fn get_ae_mode(&self) -> XCamResult<AeMode> {
         unsafe {
             let mut mode = ffi::aeMode_t::default();
-            XCamError::from(ffi::rk_aiq_uapi_getAeMode(
-                self.internal.as_ptr(),
-                &mut mode,
-            ))
-            .ok()
-            .map(|_| mode)
+            XCamError::from(ffi::rk_aiq_uapi_getAeMode(self.lock().as_ptr(), &mut mode))
+                .ok()
+                .map(|_| mode)
         }
     }
 
     #[cfg(feature = "v1_0")]
     fn set_ae_mode(&self, mode: AeMode) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi_getAeMode(self.internal.as_ptr(), &mode)).ok() }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_getAeMode(self.lock().as_ptr(), &mode)).ok() }
     }
 
     #[cfg(any(feature = "v1_0", feature = "v2_0", feature = "v3_0"))]
     fn get_exp_mode(&self) -> XCamResult<OpMode> {
         unsafe {
             let mut mode = ffi::opMode_t::OP_INVAL;
-            XCamError::from(ffi::rk_aiq_uapi_getExpMode(
-                self.internal.as_ptr(),
-                &mut mode,
-            ))
-            .ok()
-            .map(|_| mode.into())
+            XCamError::from(ffi::rk_aiq_uapi_getExpMode(self.lock().as_ptr(), &mut mode))
+                .ok()
+                .map(|_| mode.into())
         }
     }
 
@@ -163,7 +176,7 @@ impl AutoExposure for Context {
         unsafe {
             let mut mode = ffi::opMode_t::OP_INVAL;
             XCamError::from(ffi::rk_aiq_uapi2_getExpMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut mode,
             ))
             .ok()
@@ -175,7 +188,7 @@ impl AutoExposure for Context {
     fn set_exp_mode(&self, mode: OpMode) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setExpMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
@@ -186,7 +199,7 @@ impl AutoExposure for Context {
     fn set_exp_mode(&self, mode: OpMode) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setExpMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
@@ -198,7 +211,7 @@ impl AutoExposure for Context {
         unsafe {
             let mut range = ffi::paRange_t::default();
             XCamError::from(ffi::rk_aiq_uapi_getExpGainRange(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut range,
             ))
             .ok()
@@ -211,7 +224,7 @@ impl AutoExposure for Context {
         unsafe {
             let mut range = ffi::paRange_t::default();
             XCamError::from(ffi::rk_aiq_uapi2_getExpGainRange(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut range,
             ))
             .ok()
@@ -224,7 +237,7 @@ impl AutoExposure for Context {
         let mut range = ffi::paRange_t { min, max };
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setExpGainRange(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut range,
             ))
             .ok()
@@ -236,7 +249,7 @@ impl AutoExposure for Context {
         let mut range = ffi::paRange_t { min, max };
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setExpGainRange(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut range,
             ))
             .ok()
@@ -248,7 +261,7 @@ impl AutoExposure for Context {
         unsafe {
             let mut range = ffi::paRange_t::default();
             XCamError::from(ffi::rk_aiq_uapi_getExpTimeRange(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut range,
             ))
             .ok()
@@ -261,7 +274,7 @@ impl AutoExposure for Context {
         unsafe {
             let mut range = ffi::paRange_t::default();
             XCamError::from(ffi::rk_aiq_uapi2_getExpTimeRange(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut range,
             ))
             .ok()
@@ -274,7 +287,7 @@ impl AutoExposure for Context {
         let mut range = ffi::paRange_t { min, max };
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setExpTimeRange(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut range,
             ))
             .ok()
@@ -286,7 +299,7 @@ impl AutoExposure for Context {
         let mut range = ffi::paRange_t { min, max };
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setExpTimeRange(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut range,
             ))
             .ok()
@@ -297,7 +310,7 @@ impl AutoExposure for Context {
     fn set_manual_exp(&self, gain: f32, time: f32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setManualExp(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 gain,
                 time,
             ))
@@ -307,10 +320,11 @@ impl AutoExposure for Context {
 
     #[cfg(any(feature = "v4_0", feature = "v5_0"))]
     fn set_manual_exp(&self, gain: f32, time: f32) -> XCamResult<()> {
+        let _lock = self.lock();
         unsafe {
             let mut sw_attr = ffi::Uapi_ExpSwAttrV2_t::default();
             use crate::ffi::RKAiqOPMode_e::RK_AIQ_OP_MODE_MANUAL;
-            ffi::rk_aiq_user_api2_ae_getExpSwAttr(self.internal.as_ptr(), &mut sw_attr);
+            ffi::rk_aiq_user_api2_ae_getExpSwAttr(self.lock().as_ptr(), &mut sw_attr);
             sw_attr.Enable = 1;
             sw_attr.AecOpType = RK_AIQ_OP_MODE_MANUAL;
             // LinearAE
@@ -319,7 +333,7 @@ impl AutoExposure for Context {
             sw_attr.stManual.LinearAE.GainValue = gain;
             sw_attr.stManual.LinearAE.TimeValue = time;
             let r1 = XCamError::from(ffi::rk_aiq_user_api2_ae_setExpSwAttr(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 sw_attr,
             ));
             r1.ok()
@@ -341,10 +355,28 @@ impl AutoExposure for Context {
         self.set_manual_exp(gain, time)
     }
 
+    fn query_exp_info(&self) -> XCamResult<ExpInfo> {
+        let mut info = ffi::Uapi_ExpQueryInfo_t::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_ae_queryExpResInfo(
+                self.lock().as_ptr(),
+                &mut info,
+            ))
+            .ok()
+        }?;
+        let exp = &info.CurExpInfo.LinearExp.exp_real_params;
+        Ok(ExpInfo {
+            gain: exp.analog_gain * exp.digital_gain * exp.isp_dgain,
+            time: exp.integration_time,
+            mean_luma: info.MeanLuma,
+            converged: info.IsConverged,
+        })
+    }
+
     fn set_blc_mode(&self, enabled: bool, mode: AeMeasAreaType) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setBLCMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 enabled,
                 mode.into(),
             ))
@@ -355,7 +387,7 @@ impl AutoExposure for Context {
     fn set_blc_strength(&self, strength: i32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setBLCStrength(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 strength,
             ))
             .ok()
@@ -363,15 +395,13 @@ impl AutoExposure for Context {
     }
 
     fn set_hlc_mode(&self, enabled: bool) -> XCamResult<()> {
-        unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_setHLCMode(self.internal.as_ptr(), enabled)).ok()
-        }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_setHLCMode(self.lock().as_ptr(), enabled)).ok() }
     }
 
     fn set_hlc_strength(&self, strength: i32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setHLCStrength(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 strength,
             ))
             .ok()
@@ -383,7 +413,7 @@ impl AutoExposure for Context {
         #[cfg(feature = "v2_0")]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getDarkAreaBoostStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut level,
             ))
             .ok()
@@ -392,7 +422,7 @@ impl AutoExposure for Context {
         #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_getDarkAreaBoostStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut level,
             ))
             .ok()
@@ -404,7 +434,7 @@ impl AutoExposure for Context {
         #[cfg(feature = "v2_0")]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setDarkAreaBoostStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 level,
             ))
             .ok()
@@ -412,7 +442,7 @@ impl AutoExposure for Context {
         #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setDarkAreaBoostStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 level,
             ))
             .ok()
@@ -423,7 +453,7 @@ impl AutoExposure for Context {
         let mut mode: AntiFlickerMode = Default::default();
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getAntiFlickerMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut mode,
             ))
             .ok()
@@ -434,7 +464,7 @@ impl AutoExposure for Context {
     fn set_anti_flicker_mode<T: Into<AntiFlickerMode>>(&self, mode: T) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setAntiFlickerMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
@@ -445,7 +475,7 @@ impl AutoExposure for Context {
         let mut mode: ExpPwrLineFreq = Default::default();
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getExpPwrLineFreqMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut mode,
             ))
             .ok()
@@ -456,7 +486,7 @@ impl AutoExposure for Context {
     fn set_exp_pwr_line_freq_mode<T: Into<ExpPwrLineFreq>>(&self, mode: T) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setExpPwrLineFreqMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
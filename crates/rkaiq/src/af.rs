@@ -1,14 +1,310 @@
 //! 自动聚焦
 //!
 //! AF 模块的功能是指调整相机镜头，使被拍物成像清晰的过程。
+//!
+//! 除聚焦模式外，本模块还提供手动对焦、单次/连续触发、锁定、变倍位置与变倍跟焦曲线、
+//! 搜索状态及分窗口对比度统计等控制。对焦、变倍位置以 [`FocusPosition`]、[`ZoomPosition`]
+//! 表示，由 [`VcmCaps`] 依据马达能力校验后创建。
 use super::context::Context;
 use super::error::XCamError;
 use super::ffi;
-use super::types::{OpMode, XCamResult};
+use super::types::{AfAttr, OpMode, StaticInfo, XCamResult};
+use std::ops::RangeInclusive;
+
+/// 对比度统计的水平窗口数。
+pub const AF_WINDOW_COLS: usize = 15;
+
+/// 对比度统计的垂直窗口数。
+pub const AF_WINDOW_ROWS: usize = 15;
+
+/// 一个代表已校验对焦马达位置的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FocusPosition(i16);
+
+impl FocusPosition {
+    /// 返回马达码值。
+    pub fn code(self) -> i16 {
+        self.0
+    }
+}
+
+/// 一个代表已校验变倍马达位置的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZoomPosition(i32);
+
+impl ZoomPosition {
+    /// 返回马达码值。
+    pub fn code(self) -> i32 {
+        self.0
+    }
+}
 
+/// 一个代表镜头马达能力的类型。
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VcmCaps {
+    /// 对焦马达的可用码值范围，为 `None` 表示不支持对焦。
+    pub focus: Option<RangeInclusive<i32>>,
+    /// 变倍马达的可用码值范围，为 `None` 表示不支持变倍。
+    pub zoom: Option<RangeInclusive<i32>>,
+    /// 变倍马达的焦距范围（毫米）。
+    pub focal_length: Option<RangeInclusive<f32>>,
+    focus_limit: Option<RangeInclusive<i32>>,
+}
+
+impl VcmCaps {
+    /// 返回对焦位置的限位范围，未设置时为对焦马达的可用范围。
+    pub fn focus_limit(&self) -> Option<&RangeInclusive<i32>> {
+        self.focus_limit.as_ref().or(self.focus.as_ref())
+    }
+
+    /// 设置对焦位置的限位范围，此后 [`focus_position`](Self::focus_position) 仅接受该范围内的码值。
+    ///
+    /// AIQ 仅提供对焦范围的查询接口，限位在创建对焦位置时生效。
+    ///
+    /// # Parameters
+    /// * `limit` - 限位范围，须非空且位于对焦马达的可用范围内。
+    pub fn set_focus_limit(&mut self, limit: RangeInclusive<i32>) -> XCamResult<()> {
+        let range = self.focus.as_ref().ok_or_else(XCamError::unsupported)?;
+        if limit.is_empty() {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        if !range.contains(limit.start()) || !range.contains(limit.end()) {
+            return Err(XCamError::from(
+                ffi::XCamReturn::XCAM_RETURN_ERROR_OUTOFRANGE,
+            ));
+        }
+        self.focus_limit = Some(limit);
+        Ok(())
+    }
+
+    /// 清除对焦位置的限位范围，恢复为对焦马达的可用范围。
+    pub fn clear_focus_limit(&mut self) {
+        self.focus_limit = None;
+    }
+
+    /// 校验并创建对焦位置，码值须位于 [`focus_limit`](Self::focus_limit) 范围内。
+    pub fn focus_position(&self, code: i32) -> XCamResult<FocusPosition> {
+        match self.focus_limit() {
+            Some(range) if range.contains(&code) => i16::try_from(code)
+                .map(FocusPosition)
+                .map_err(|_| XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_OUTOFRANGE)),
+            Some(_) => Err(XCamError::from(
+                ffi::XCamReturn::XCAM_RETURN_ERROR_OUTOFRANGE,
+            )),
+            None => Err(XCamError::unsupported()),
+        }
+    }
+
+    /// 校验并创建变倍位置。
+    pub fn zoom_position(&self, code: i32) -> XCamResult<ZoomPosition> {
+        match self.zoom {
+            Some(ref range) if range.contains(&code) => Ok(ZoomPosition(code)),
+            Some(_) => Err(XCamError::from(
+                ffi::XCamReturn::XCAM_RETURN_ERROR_OUTOFRANGE,
+            )),
+            None => Err(XCamError::unsupported()),
+        }
+    }
+}
+
+/// 一个描述对焦触发方式的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FocusTrigger {
+    /// 单次对焦，搜索完成后保持在清晰位置。
+    OneShot,
+    /// 连续对焦，场景变化时重新搜索。
+    Continuous,
+    /// 在手动模式下触发一次搜索。
+    Manual,
+}
+
+/// 一个描述对焦搜索状态的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FocusSearchState {
+    /// 正在搜索。
+    Running,
+    /// 搜索完成。
+    Finished,
+    /// 搜索失败。
+    Failed,
+    /// 无效状态，如尚未触发搜索。
+    Invalid,
+}
+
+impl From<ffi::RKAIQ_AF_SEARCH_STATE> for FocusSearchState {
+    fn from(val: ffi::RKAIQ_AF_SEARCH_STATE) -> Self {
+        use ffi::RKAIQ_AF_SEARCH_STATE::*;
+        match val {
+            RKAIQ_AF_SEARCH_RUNNING => FocusSearchState::Running,
+            RKAIQ_AF_SEARCH_END => FocusSearchState::Finished,
+            RKAIQ_AF_SEARCH_FAILED => FocusSearchState::Failed,
+            _ => FocusSearchState::Invalid,
+        }
+    }
+}
+
+/// 一个代表对焦搜索结果的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FocusSearchResult {
+    /// 搜索状态。
+    pub state: FocusSearchState,
+    /// 搜索结束时的对焦马达码值。
+    pub final_position: i32,
+}
+
+/// 一个代表对焦搜索路径中单个采样点的类型。
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FocusSearchStep {
+    /// 对焦马达码值。
+    pub position: i32,
+    /// 该位置下的清晰度评价值。
+    pub sharpness: f32,
+}
+
+/// 一个代表分窗口对比度统计的类型。
+///
+/// 统计按行优先排列，共 [`AF_WINDOW_ROWS`] × [`AF_WINDOW_COLS`] 个窗口。
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AfWindowStats {
+    /// 各窗口的对比度（清晰度）评价值。
+    pub contrast: Vec<u64>,
+    /// 各窗口的平均亮度，硬件不提供时为空。
+    pub luma: Vec<u32>,
+}
+
+impl AfWindowStats {
+    /// 获取指定窗口的对比度。
+    pub fn contrast_at(&self, row: usize, col: usize) -> Option<u64> {
+        if row >= AF_WINDOW_ROWS || col >= AF_WINDOW_COLS {
+            return None;
+        }
+        self.contrast.get(row * AF_WINDOW_COLS + col).copied()
+    }
+}
+
+/// 一个代表变倍跟焦曲线的类型。
+///
+/// 曲线由若干 (变倍码值, 对焦码值) 节点组成，节点之间线性插值，通常取自镜头标定数据。
+/// 曲线至少包含一个节点。
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "Vec<(i32, i32)>", into = "Vec<(i32, i32)>")
+)]
+pub struct ZoomFocusCurve {
+    points: Vec<(i32, i32)>,
+}
+
+impl ZoomFocusCurve {
+    /// 创建一条变倍跟焦曲线，变倍码值须严格递增。
+    pub fn new(points: Vec<(i32, i32)>) -> XCamResult<Self> {
+        if points.is_empty() || points.windows(2).any(|w| w[1].0 <= w[0].0) {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        Ok(Self { points })
+    }
+
+    /// 返回曲线节点。
+    pub fn points(&self) -> &[(i32, i32)] {
+        &self.points
+    }
+
+    /// 计算指定变倍码值对应的对焦码值，超出曲线范围时取端点值。
+    pub fn focus_at(&self, zoom: i32) -> i32 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if zoom <= first.0 {
+            return first.1;
+        }
+        if zoom >= last.0 {
+            return last.1;
+        }
+        let i = self.points.partition_point(|p| p.0 <= zoom);
+        let (z0, f0) = self.points[i - 1];
+        let (z1, f1) = self.points[i];
+        let t = (zoom - z0) as f32 / (z1 - z0) as f32;
+        f0 + ((f1 - f0) as f32 * t).round() as i32
+    }
+}
+
+impl TryFrom<Vec<(i32, i32)>> for ZoomFocusCurve {
+    type Error = XCamError;
+
+    fn try_from(val: Vec<(i32, i32)>) -> Result<Self, Self::Error> {
+        Self::new(val)
+    }
+}
+
+impl From<ZoomFocusCurve> for Vec<(i32, i32)> {
+    fn from(val: ZoomFocusCurve) -> Self {
+        val.points
+    }
+}
+
+/// 一个描述自动聚焦控制的契定。
 pub trait AutoFocus {
     fn get_focus_mode(&self) -> XCamResult<OpMode>;
     fn set_focus_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 获取 AF 属性。
+    fn get_af_attrib(&self) -> XCamResult<AfAttr>;
+
+    /// 设置 AF 属性。
+    fn set_af_attrib<T: Into<AfAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 查询镜头马达能力。
+    ///
+    /// # Parameters
+    /// * `info` - 由 [`get_static_metas`](super::sysctl::get_static_metas) 获取的静态信息。
+    fn get_vcm_caps(&self, info: &StaticInfo) -> XCamResult<VcmCaps>;
+
+    /// 获取对焦马达的可用范围。
+    fn get_focus_range(&self) -> XCamResult<RangeInclusive<i32>>;
+
+    /// 获取变倍马达的可用范围。
+    fn get_zoom_range(&self) -> XCamResult<RangeInclusive<i32>>;
+
+    /// 获取当前对焦位置。
+    fn get_focus_position(&self) -> XCamResult<i16>;
+
+    /// 设置手动对焦位置，需先切换至手动模式。
+    fn set_focus_position(&self, pos: FocusPosition) -> XCamResult<()>;
+
+    /// 获取当前变倍位置。
+    fn get_zoom_position(&self) -> XCamResult<i32>;
+
+    /// 设置变倍位置。
+    fn set_zoom_position(&self, pos: ZoomPosition) -> XCamResult<()>;
+
+    /// 设置变倍位置，并按变倍跟焦曲线同步移动对焦马达。
+    fn set_zoom_position_with_curve(
+        &self,
+        caps: &VcmCaps,
+        pos: ZoomPosition,
+        curve: &ZoomFocusCurve,
+    ) -> XCamResult<()>;
+
+    /// 通知 AF 变倍已结束，以便重新对焦。
+    fn end_zoom_change(&self) -> XCamResult<()>;
+
+    /// 触发对焦。
+    fn trigger_focus(&self, trigger: FocusTrigger) -> XCamResult<()>;
+
+    /// 锁定对焦，保持当前马达位置。
+    fn lock_focus(&self) -> XCamResult<()>;
+
+    /// 解除对焦锁定。
+    fn unlock_focus(&self) -> XCamResult<()>;
+
+    /// 获取对焦搜索结果。
+    fn get_focus_search_result(&self) -> XCamResult<FocusSearchResult>;
+
+    /// 获取最近一次对焦搜索的路径。
+    fn get_focus_search_path(&self) -> XCamResult<(FocusSearchState, Vec<FocusSearchStep>)>;
+
+    /// 获取当前帧的分窗口对比度统计。
+    fn get_af_window_stats(&self) -> XCamResult<AfWindowStats>;
 }
 
 impl AutoFocus for Context {
@@ -16,7 +312,7 @@ impl AutoFocus for Context {
         let mut mode: ffi::opMode_t = ffi::opMode_t::OP_AUTO;
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_getFocusMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut mode,
             ))
             .ok()
@@ -27,10 +323,273 @@ impl AutoFocus for Context {
     fn set_focus_mode(&self, mode: OpMode) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setFocusMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
         }
     }
+
+    fn get_af_attrib(&self) -> XCamResult<AfAttr> {
+        let mut attr = AfAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_af_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    fn set_af_attrib<T: Into<AfAttr>>(&self, attr: T) -> XCamResult<()> {
+        let attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_af_SetAttrib(
+                self.lock().as_ptr(),
+                &attr,
+            ))
+            .ok()
+        }
+    }
+
+    fn get_vcm_caps(&self, info: &StaticInfo) -> XCamResult<VcmCaps> {
+        let mut caps = VcmCaps::default();
+        if !info.has_lens_vcm {
+            return Ok(caps);
+        }
+        if info.lens_info.focus_support {
+            caps.focus = Some(self.get_focus_range()?);
+        }
+        if info.lens_info.zoom_support {
+            let mut range = ffi::rk_aiq_af_zoomrange::default();
+            unsafe {
+                XCamError::from(ffi::rk_aiq_user_api2_af_GetZoomRange(
+                    self.lock().as_ptr(),
+                    &mut range,
+                ))
+                .ok()?;
+            }
+            caps.zoom = Some(range.min_pos..=range.max_pos);
+            caps.focal_length = Some(range.min_fl..=range.max_fl);
+        }
+        Ok(caps)
+    }
+
+    fn get_focus_range(&self) -> XCamResult<RangeInclusive<i32>> {
+        let mut range = ffi::rk_aiq_af_focusrange::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_af_GetFocusRange(
+                self.lock().as_ptr(),
+                &mut range,
+            ))
+            .ok()
+            .map(|_| range.min_pos..=range.max_pos)
+        }
+    }
+
+    fn get_zoom_range(&self) -> XCamResult<RangeInclusive<i32>> {
+        let mut range = ffi::rk_aiq_af_zoomrange::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_af_GetZoomRange(
+                self.lock().as_ptr(),
+                &mut range,
+            ))
+            .ok()
+            .map(|_| range.min_pos..=range.max_pos)
+        }
+    }
+
+    fn get_focus_position(&self) -> XCamResult<i16> {
+        let mut code: i16 = 0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_getFocusPosition(
+                self.lock().as_ptr(),
+                &mut code,
+            ))
+            .ok()
+            .map(|_| code)
+        }
+    }
+
+    fn set_focus_position(&self, pos: FocusPosition) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_setFocusPosition(
+                self.lock().as_ptr(),
+                pos.code(),
+            ))
+            .ok()
+        }
+    }
+
+    fn get_zoom_position(&self) -> XCamResult<i32> {
+        let mut code: i32 = 0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_getOpZoomPosition(
+                self.lock().as_ptr(),
+                &mut code,
+            ))
+            .ok()
+            .map(|_| code)
+        }
+    }
+
+    fn set_zoom_position(&self, pos: ZoomPosition) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_setOpZoomPosition(
+                self.lock().as_ptr(),
+                pos.code(),
+            ))
+            .ok()
+        }
+    }
+
+    fn set_zoom_position_with_curve(
+        &self,
+        caps: &VcmCaps,
+        pos: ZoomPosition,
+        curve: &ZoomFocusCurve,
+    ) -> XCamResult<()> {
+        let _lock = self.lock();
+        let focus = caps.focus_position(curve.focus_at(pos.code()))?;
+        self.set_zoom_position(pos)?;
+        self.set_focus_position(focus)
+    }
+
+    fn end_zoom_change(&self) -> XCamResult<()> {
+        unsafe { XCamError::from(ffi::rk_aiq_user_api2_af_EndZoomChg(self.lock().as_ptr())).ok() }
+    }
+
+    fn trigger_focus(&self, trigger: FocusTrigger) -> XCamResult<()> {
+        let guard = self.lock();
+        let ctx = guard.as_ptr();
+        unsafe {
+            XCamError::from(match trigger {
+                FocusTrigger::OneShot => ffi::rk_aiq_user_api2_af_Oneshot(ctx),
+                FocusTrigger::Continuous => ffi::rk_aiq_user_api2_af_Tracking(ctx),
+                FocusTrigger::Manual => ffi::rk_aiq_user_api2_af_ManualTriger(ctx),
+            })
+            .ok()
+        }
+    }
+
+    fn lock_focus(&self) -> XCamResult<()> {
+        unsafe { XCamError::from(ffi::rk_aiq_user_api2_af_Lock(self.lock().as_ptr())).ok() }
+    }
+
+    fn unlock_focus(&self) -> XCamResult<()> {
+        unsafe { XCamError::from(ffi::rk_aiq_user_api2_af_Unlock(self.lock().as_ptr())).ok() }
+    }
+
+    fn get_focus_search_result(&self) -> XCamResult<FocusSearchResult> {
+        let mut result = ffi::rk_aiq_af_result_t::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_af_GetSearchResult(
+                self.lock().as_ptr(),
+                &mut result,
+            ))
+            .ok()
+            .map(|_| FocusSearchResult {
+                state: result.stat.into(),
+                final_position: result.final_pos,
+            })
+        }
+    }
+
+    fn get_focus_search_path(&self) -> XCamResult<(FocusSearchState, Vec<FocusSearchStep>)> {
+        let mut path = ffi::rk_aiq_af_sec_path_t::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_af_GetSearchPath(
+                self.lock().as_ptr(),
+                &mut path,
+            ))
+            .ok()?;
+        }
+        let num = (path.search_num.max(0) as usize).min(path.pos.len());
+        let steps = path.pos[..num]
+            .iter()
+            .zip(path.var[..num].iter())
+            .map(|(&position, &sharpness)| FocusSearchStep {
+                position,
+                sharpness,
+            })
+            .collect();
+        Ok((path.stat.into(), steps))
+    }
+
+    fn get_af_window_stats(&self) -> XCamResult<AfWindowStats> {
+        let mut stats = ffi::rk_aiq_isp_stats_t::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_sysctl_get3AStats(
+                self.lock().as_ptr(),
+                &mut stats,
+            ))
+            .ok()?;
+        }
+        Ok(window_stats_from(&stats))
+    }
+}
+
+#[cfg(any(feature = "isp_hw_v20", feature = "isp_hw_v21"))]
+fn window_stats_from(stats: &ffi::rk_aiq_isp_stats_t) -> AfWindowStats {
+    let af = &stats.af_stats;
+    AfWindowStats {
+        contrast: af.global_sharpness.iter().map(|&x| x as u64).collect(),
+        luma: Vec::new(),
+    }
+}
+
+#[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+fn window_stats_from(stats: &ffi::rk_aiq_isp_stats_t) -> AfWindowStats {
+    let af = &stats.af_stats_v3x;
+    AfWindowStats {
+        contrast: af
+            .wnda_fv_h1
+            .iter()
+            .zip(af.wnda_fv_v1.iter())
+            .map(|(&h, &v)| h as u64 + v as u64)
+            .collect(),
+        luma: af.wnda_luma.iter().map(|&x| x as u32).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zoom_focus_curve() {
+        let curve = ZoomFocusCurve::new(vec![(0, 100), (100, 200), (200, 400)]).unwrap();
+        assert_eq!(curve.focus_at(-10), 100);
+        assert_eq!(curve.focus_at(50), 150);
+        assert_eq!(curve.focus_at(150), 300);
+        assert_eq!(curve.focus_at(300), 400);
+        assert!(ZoomFocusCurve::new(vec![(0, 0), (0, 1)]).is_err());
+    }
+
+    #[test]
+    fn test_vcm_caps() {
+        let mut caps = VcmCaps {
+            focus: Some(0..=64),
+            ..Default::default()
+        };
+        assert_eq!(caps.focus_position(32).map(FocusPosition::code), Ok(32));
+        assert!(caps.focus_position(65).is_err());
+        assert!(caps.zoom_position(0).unwrap_err().is_unsupported());
+
+        assert_eq!(caps.focus_limit(), Some(&(0..=64)));
+        assert!(caps.set_focus_limit(10..=70).is_err());
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 20..=10;
+        assert!(caps.set_focus_limit(empty).is_err());
+        assert!(caps.set_focus_limit(10..=40).is_ok());
+        assert!(caps.focus_position(8).is_err());
+        assert!(caps.focus_position(48).is_err());
+        assert_eq!(caps.focus_position(40).map(FocusPosition::code), Ok(40));
+        caps.clear_focus_limit();
+        assert_eq!(caps.focus_position(48).map(FocusPosition::code), Ok(48));
+
+        let mut caps = VcmCaps::default();
+        assert!(caps.set_focus_limit(0..=1).unwrap_err().is_unsupported());
+    }
 }
@@ -0,0 +1,265 @@
+//! 异步接口
+//!
+//! AIQ 的 uapi 调用均为同步调用，部分调用（如 `stop`、`update_iq` 及带跳帧的镜像翻转）
+//! 会阻塞数帧时间。[`AsyncContext`] 将 [`Context`] 移交给专用线程，所有调用在该线程上
+//! 依次执行，调用方以 future 的形式等待结果，不会阻塞 tokio 运行时。
+//!
+//! 元数据及错误回调通过 [`metas`] 及 [`errors`] 以 `Stream` 的形式提供；曝光及白平衡等
+//! 查询结果可通过 [`AsyncContext::watch`] 周期轮询。
+//!
+//! ```no_run
+//! use rkaiq::aio::AsyncContext;
+//! use rkaiq::prelude::*;
+//! use rkaiq::types::WorkingMode;
+//! use tokio_stream::StreamExt;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let ctx = AsyncContext::spawn(|| Context::new("m00_b_imx415 4-001a", "/etc/iqfiles")).await?;
+//! ctx.prepare(3840, 2160, WorkingMode::Normal).await?;
+//! ctx.start().await?;
+//! ctx.call(|ctx| ctx.set_sharpness(60)).await?;
+//!
+//! let mut exp = ctx.exp_info_stream(std::time::Duration::from_millis(500));
+//! while let Some(info) = exp.next().await {
+//!     println!("{:?}", info?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use super::ae::{AutoExposure, ExpInfo};
+use super::awb::{AutoWhiteBalance, WbInfo};
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::sysctl::SystemControl;
+use super::types::{WorkingMode, XCamResult};
+use std::io;
+use std::sync::{mpsc, Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot};
+use tokio::time::MissedTickBehavior;
+use tokio_stream::wrappers::{BroadcastStream, ReceiverStream};
+use tokio_stream::{Stream, StreamExt};
+
+/// 回调事件的缓冲数量，订阅者处理不及时时丢弃最早的事件。
+pub const EVENT_CAPACITY: usize = 64;
+
+type Job = Box<dyn FnOnce(&Context) + Send>;
+
+/// 一个代表每帧元数据的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Metas {
+    /// 帧序号。
+    pub frame_id: u32,
+}
+
+/// 一个代表 AIQ 错误回调的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ErrorEvent {
+    /// 错误代码。
+    pub error: XCamError,
+}
+
+/// 一个代表异步 AIQ 上下文的类型。
+///
+/// 克隆得到的句柄共享同一个工作线程，全部句柄释放后工作线程退出并释放上下文。
+#[derive(Clone)]
+pub struct AsyncContext {
+    tx: mpsc::Sender<Job>,
+}
+
+impl AsyncContext {
+    /// 将已创建的上下文移交给新的工作线程。
+    pub fn new(ctx: Context) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("rkaiq".into())
+            .spawn(move || serve(&ctx, rx))?;
+        Ok(Self { tx })
+    }
+
+    /// 在新的工作线程上创建上下文。
+    pub async fn spawn<F>(init: F) -> io::Result<Self>
+    where
+        F: FnOnce() -> io::Result<Context> + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let (job_tx, job_rx) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("rkaiq".into())
+            .spawn(move || match init() {
+                Ok(ctx) => {
+                    let _ = tx.send(Ok(()));
+                    serve(&ctx, job_rx);
+                }
+                Err(err) => {
+                    let _ = tx.send(Err(err));
+                }
+            })?;
+        rx.await
+            .map_err(|_| io::Error::other("rkaiq worker exited"))??;
+        Ok(Self { tx: job_tx })
+    }
+
+    /// 在工作线程上执行 `f` 并等待其结果。
+    ///
+    /// 工作线程已退出时返回 `XCAM_RETURN_ERROR_THREAD`。
+    pub async fn call<F, R>(&self, f: F) -> XCamResult<R>
+    where
+        F: FnOnce(&Context) -> XCamResult<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.tx
+            .send(Box::new(move |ctx| {
+                let _ = tx.send(f(ctx));
+            }))
+            .map_err(|_| thread_error())?;
+        rx.await.map_err(|_| thread_error())?
+    }
+
+    pub async fn prepare(&self, width: u32, height: u32, mode: WorkingMode) -> XCamResult<()> {
+        self.call(move |ctx| ctx.prepare(width, height, mode)).await
+    }
+
+    pub async fn start(&self) -> XCamResult<()> {
+        self.call(|ctx| ctx.start()).await
+    }
+
+    pub async fn stop(&self, keep_ext_hw_st: bool) -> XCamResult<()> {
+        self.call(move |ctx| ctx.stop(keep_ext_hw_st)).await
+    }
+
+    pub async fn update_iq<T: Into<Vec<u8>>>(&self, iq_file: T) -> XCamResult<()> {
+        let iq_file = iq_file.into();
+        self.call(move |ctx| ctx.update_iq(iq_file)).await
+    }
+
+    pub async fn query_exp_info(&self) -> XCamResult<ExpInfo> {
+        self.call(|ctx| ctx.query_exp_info()).await
+    }
+
+    pub async fn query_wb_info(&self) -> XCamResult<WbInfo> {
+        self.call(|ctx| ctx.query_wb_info()).await
+    }
+
+    /// 以 `period` 为周期在工作线程上执行 `f`，并以 `Stream` 返回每次的结果。
+    ///
+    /// 轮询在 tokio 任务中进行，`Stream` 释放后于下一周期结束。
+    ///
+    /// # Panics
+    ///
+    /// 不在 tokio 运行时中调用时 panic。
+    pub fn watch<F, R>(&self, period: Duration, f: F) -> ReceiverStream<XCamResult<R>>
+    where
+        F: Fn(&Context) -> XCamResult<R> + Send + Sync + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let handle = self.clone();
+        let f = Arc::new(f);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+                let f = f.clone();
+                let result = handle.call(move |ctx| f(ctx)).await;
+                if tx.send(result).await.is_err() {
+                    break;
+                }
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+
+    /// 周期查询曝光信息。
+    pub fn exp_info_stream(&self, period: Duration) -> ReceiverStream<XCamResult<ExpInfo>> {
+        self.watch(period, |ctx| ctx.query_exp_info())
+    }
+
+    /// 周期查询白平衡信息。
+    pub fn wb_info_stream(&self, period: Duration) -> ReceiverStream<XCamResult<WbInfo>> {
+        self.watch(period, |ctx| ctx.query_wb_info())
+    }
+}
+
+/// 订阅每帧元数据。
+///
+/// AIQ 的回调不区分上下文，多个上下文的元数据将合并到同一 `Stream` 中；
+/// 订阅者处理不及时时跳过被覆盖的事件。
+pub fn metas() -> impl Stream<Item = Metas> + Send + Unpin {
+    BroadcastStream::new(metas_sender().subscribe()).filter_map(Result::ok)
+}
+
+/// 订阅 AIQ 错误回调，说明同 [`metas`]。
+pub fn errors() -> impl Stream<Item = ErrorEvent> + Send + Unpin {
+    BroadcastStream::new(errors_sender().subscribe()).filter_map(Result::ok)
+}
+
+fn metas_sender() -> &'static broadcast::Sender<Metas> {
+    static SENDER: OnceLock<broadcast::Sender<Metas>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(EVENT_CAPACITY).0)
+}
+
+fn errors_sender() -> &'static broadcast::Sender<ErrorEvent> {
+    static SENDER: OnceLock<broadcast::Sender<ErrorEvent>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(EVENT_CAPACITY).0)
+}
+
+/// 由元数据回调调用。
+pub(crate) unsafe fn dispatch_metas(metas: *const ffi::rk_aiq_metas_t) {
+    if let Some(metas) = metas.as_ref() {
+        let _ = metas_sender().send(Metas {
+            frame_id: metas.frame_id,
+        });
+    }
+}
+
+/// 由错误回调调用。
+pub(crate) unsafe fn dispatch_error(err_msg: *const ffi::rk_aiq_err_msg_t) {
+    if let Some(err_msg) = err_msg.as_ref() {
+        let _ = errors_sender().send(ErrorEvent {
+            error: XCamError::from(err_msg.err_code),
+        });
+    }
+}
+
+fn serve(ctx: &Context, rx: mpsc::Receiver<Job>) {
+    for job in rx {
+        job(ctx);
+    }
+}
+
+fn thread_error() -> XCamError {
+    XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_THREAD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_async_context() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let ctx = AsyncContext::spawn(|| Context::new("m00_b_ov5695 4-0036-1", "/etc/iqfiles"))
+                .await
+                .unwrap();
+            let mut metas = metas();
+            assert_eq!(ctx.prepare(2592, 1944, WorkingMode::Normal).await, Ok(()));
+            assert_eq!(ctx.start().await, Ok(()));
+            assert!(metas.next().await.is_some());
+            let mut exp = ctx.exp_info_stream(Duration::from_millis(100));
+            assert!(exp.next().await.unwrap().is_ok());
+            assert_eq!(ctx.stop(false).await, Ok(()));
+        });
+    }
+}
@@ -17,7 +17,7 @@ impl AmbientDetection for Context {
         let mut attr: AsdAttrib = Default::default();
         unsafe {
             XCamError::from(ffi::rk_aiq_user_api_asd_GetAttrib(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut attr,
             ))
             .ok()
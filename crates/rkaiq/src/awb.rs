@@ -8,6 +8,17 @@ use super::error::XCamError;
 use super::ffi;
 use super::types::{OpMode, WbGain, WbScene, XCamResult};
 
+/// 一个代表当前白平衡结果的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WbInfo {
+    /// 当前增益。
+    pub gain: WbGain,
+    /// 全局色温。
+    pub cct: f32,
+    /// 是否已收敛。
+    pub converged: bool,
+}
+
 /// 一个描述自动白平衡的契定。
 pub trait AutoWhiteBalance {
     /// 获取白平衡工作模式。
@@ -39,18 +50,18 @@ pub trait AutoWhiteBalance {
 
     /// 设置白平衡色温参数。
     fn set_mwb_ct(&self, ct: u32) -> XCamResult<()>;
+
+    /// 查询当前白平衡结果。
+    fn query_wb_info(&self) -> XCamResult<WbInfo>;
 }
 
 impl AutoWhiteBalance for Context {
     fn get_wb_mode(&self) -> XCamResult<OpMode> {
         let mut mode = ffi::opMode_t::OP_INVAL;
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_getWBMode(
-                self.internal.as_ptr(),
-                &mut mode,
-            ))
-            .ok()
-            .map(|_| mode.into())
+            XCamError::from(ffi::rk_aiq_uapi_getWBMode(self.lock().as_ptr(), &mut mode))
+                .ok()
+                .map(|_| mode.into())
         }
     }
 
@@ -58,7 +69,7 @@ impl AutoWhiteBalance for Context {
     fn set_wb_mode(&self, mode: OpMode) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setWBMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
@@ -69,7 +80,7 @@ impl AutoWhiteBalance for Context {
     fn set_wb_mode(&self, mode: OpMode) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setWBMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
@@ -77,18 +88,18 @@ impl AutoWhiteBalance for Context {
     }
 
     fn lock_awb(&self) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi_lockAWB(self.internal.as_ptr())).ok() }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_lockAWB(self.lock().as_ptr())).ok() }
     }
 
     fn unlock_awb(&self) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi_unlockAWB(self.internal.as_ptr())).ok() }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_unlockAWB(self.lock().as_ptr())).ok() }
     }
 
     fn get_mwb_scene(&self) -> XCamResult<WbScene> {
         let mut scene: WbScene = Default::default();
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getMWBScene(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut scene,
             ))
             .ok()
@@ -99,7 +110,7 @@ impl AutoWhiteBalance for Context {
     fn set_mwb_scene<T: Into<WbScene>>(&self, scene: T) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setMWBScene(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 scene.into(),
             ))
             .ok()
@@ -109,19 +120,16 @@ impl AutoWhiteBalance for Context {
     fn get_mwb_gain(&self) -> XCamResult<WbGain> {
         let mut gain: WbGain = Default::default();
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_getMWBGain(
-                self.internal.as_ptr(),
-                &mut gain,
-            ))
-            .ok()
-            .map(|_| gain)
+            XCamError::from(ffi::rk_aiq_uapi_getMWBGain(self.lock().as_ptr(), &mut gain))
+                .ok()
+                .map(|_| gain)
         }
     }
 
     fn set_mwb_gain<T: Into<WbGain>>(&self, gain: T) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setMWBGain(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut gain.into(),
             ))
             .ok()
@@ -131,17 +139,34 @@ impl AutoWhiteBalance for Context {
     fn get_mwb_ct(&self) -> XCamResult<u32> {
         let mut ct: u32 = 0;
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_getMWBCT(self.internal.as_ptr(), &mut ct))
+            XCamError::from(ffi::rk_aiq_uapi_getMWBCT(self.lock().as_ptr(), &mut ct))
                 .ok()
                 .map(|_| ct)
         }
     }
 
     fn set_mwb_ct(&self, ct: u32) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi_setMWBCT(self.internal.as_ptr(), ct)).ok() }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_setMWBCT(self.lock().as_ptr(), ct)).ok() }
+    }
+
+    fn query_wb_info(&self) -> XCamResult<WbInfo> {
+        let mut info = ffi::rk_aiq_wb_querry_info_t::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_awb_QueryWBInfo(
+                self.lock().as_ptr(),
+                &mut info,
+            ))
+            .ok()
+        }?;
+        Ok(WbInfo {
+            gain: info.gain,
+            cct: info.cctGloabl.CCT,
+            converged: info.awbConverged,
+        })
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WbOpMode {
     #[cfg(feature = "v1_0")]
     Invalid,
@@ -0,0 +1,361 @@
+//! 批量设置
+//!
+//! 逐个调用 `enable_cnr`、`set_ynr_mode` 等接口时，每次调用都会独立读取并写回一次模块属性，
+//! 修改多个参数会引起多次 ISP 重新配置。[`Batch`] 先记录对各模块属性的修改，提交时在持锁
+//! 期间对每个涉及的模块只读取并写回一次；任一模块写回失败时，已写回的模块恢复为提交前的属性，
+//! 并报告失败的模块。
+//!
+//! ```no_run
+//! use rkaiq::prelude::*;
+//!
+//! let ctx = Context::new("m00_b_imx415 4-001a", "/etc/iqfiles").unwrap();
+//! ctx.batch(|b| {
+//!     b.enable_bayernr2d().disable_bayernr3d();
+//!     b.enable_cnr().enable_ynr();
+//! })
+//! .unwrap();
+//! ```
+use super::bayernr::BayerNoiseRemoval;
+use super::cnr::ChromaNoiseRemoval;
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::types::{BayerNrAttr, CnrAttr, OpMode, XCamResult, YnrAttr};
+use super::ynr::LumaNoiseRemoval;
+use std::fmt;
+
+/// 一个描述批量设置所涉及模块的枚举，按写回顺序排列。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BatchModule {
+    BayerNr,
+    Cnr,
+    Ynr,
+}
+
+impl fmt::Display for BatchModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BatchModule::BayerNr => "BayerNR",
+            BatchModule::Cnr => "CNR",
+            BatchModule::Ynr => "YNR",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 一个代表批量设置失败的错误类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchError {
+    /// 失败的模块。
+    pub module: BatchModule,
+    /// 失败原因。
+    pub error: XCamError,
+    /// 已写回的模块，已尝试回滚。
+    pub applied: Vec<BatchModule>,
+    /// 回滚失败的模块及原因，为空表示已完整恢复至提交前的状态。
+    pub rollback_failures: Vec<(BatchModule, XCamError)>,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Apply {} failed: {}", self.module, self.error)?;
+        if !self.rollback_failures.is_empty() {
+            write!(f, ", rollback failed on")?;
+            for (m, e) in &self.rollback_failures {
+                write!(f, " {}({})", m, e)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+impl From<BatchError> for XCamError {
+    fn from(err: BatchError) -> Self {
+        err.error
+    }
+}
+
+type Edit<'a, T> = Box<dyn FnOnce(&mut T) -> XCamResult<()> + 'a>;
+
+/// 一个代表待提交修改集合的类型，由 [`Batching::batch`] 创建。
+///
+/// 修改按添加顺序作用于同一份属性，提交前不会调用任何 uapi 接口。降噪开关修改的是该属性
+/// 当前工作模式下的开关，同一批次中先设置工作模式时修改新模式下的开关；设置不支持的工作模式，
+/// 或在既非自动也非手动的工作模式下修改开关，将使提交返回参数错误，且不写回任何模块。
+#[derive(Default)]
+pub struct Batch<'a> {
+    bayernr: Vec<Edit<'a, BayerNrAttr>>,
+    cnr: Vec<Edit<'a, CnrAttr>>,
+    ynr: Vec<Edit<'a, YnrAttr>>,
+}
+
+impl<'a> Batch<'a> {
+    /// 返回是否没有任何修改。
+    pub fn is_empty(&self) -> bool {
+        self.bayernr.is_empty() && self.cnr.is_empty() && self.ynr.is_empty()
+    }
+
+    /// 修改 BayerNR 属性。
+    pub fn bayernr<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut BayerNrAttr) -> XCamResult<()> + 'a,
+    {
+        self.bayernr.push(Box::new(f));
+        self
+    }
+
+    /// 修改 CNR 属性。
+    pub fn cnr<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut CnrAttr) -> XCamResult<()> + 'a,
+    {
+        self.cnr.push(Box::new(f));
+        self
+    }
+
+    /// 修改 YNR 属性。
+    pub fn ynr<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut YnrAttr) -> XCamResult<()> + 'a,
+    {
+        self.ynr.push(Box::new(f));
+        self
+    }
+
+    /// 设置 BayerNR 工作模式，仅支持 `Auto` 及 `Manual`。
+    pub fn set_bayernr_mode(&mut self, mode: OpMode) -> &mut Self {
+        self.bayernr(move |attr| {
+            use ffi::Abayernr_OPMode_t::*;
+            attr.eMode = match mode {
+                OpMode::Auto => ABAYERNR_OP_MODE_AUTO,
+                OpMode::Manual => ABAYERNR_OP_MODE_MANUAL,
+                _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+            };
+            Ok(())
+        })
+    }
+
+    /// 设置 CNR 工作模式，仅支持 `Auto` 及 `Manual`。
+    pub fn set_cnr_mode(&mut self, mode: OpMode) -> &mut Self {
+        self.cnr(move |attr| {
+            use ffi::Acnr_OPMode_t::*;
+            attr.eMode = match mode {
+                OpMode::Auto => ACNR_OP_MODE_AUTO,
+                OpMode::Manual => ACNR_OP_MODE_MANUAL,
+                _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+            };
+            Ok(())
+        })
+    }
+
+    /// 设置 YNR 工作模式，仅支持 `Auto` 及 `Manual`。
+    pub fn set_ynr_mode(&mut self, mode: OpMode) -> &mut Self {
+        self.ynr(move |attr| {
+            use ffi::Aynr_OPMode_t::*;
+            attr.eMode = match mode {
+                OpMode::Auto => AYNR_OP_MODE_AUTO,
+                OpMode::Manual => AYNR_OP_MODE_MANUAL,
+                _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+            };
+            Ok(())
+        })
+    }
+
+    /// 开启 Bayer 2D 降噪。
+    pub fn enable_bayernr2d(&mut self) -> &mut Self {
+        self.bayernr(|attr| set_bayernr2d_en(attr, 1))
+    }
+
+    /// 关闭 Bayer 2D 降噪。
+    pub fn disable_bayernr2d(&mut self) -> &mut Self {
+        self.bayernr(|attr| set_bayernr2d_en(attr, 0))
+    }
+
+    /// 开启 Bayer 3D 降噪。
+    pub fn enable_bayernr3d(&mut self) -> &mut Self {
+        self.bayernr(|attr| set_bayernr3d_en(attr, 1))
+    }
+
+    /// 关闭 Bayer 3D 降噪。
+    pub fn disable_bayernr3d(&mut self) -> &mut Self {
+        self.bayernr(|attr| set_bayernr3d_en(attr, 0))
+    }
+
+    /// 开启色度降噪。
+    pub fn enable_cnr(&mut self) -> &mut Self {
+        self.cnr(|attr| set_cnr_en(attr, 1))
+    }
+
+    /// 关闭色度降噪。
+    pub fn disable_cnr(&mut self) -> &mut Self {
+        self.cnr(|attr| set_cnr_en(attr, 0))
+    }
+
+    /// 开启亮度降噪。
+    pub fn enable_ynr(&mut self) -> &mut Self {
+        self.ynr(|attr| set_ynr_en(attr, 1))
+    }
+
+    /// 关闭亮度降噪。
+    pub fn disable_ynr(&mut self) -> &mut Self {
+        self.ynr(|attr| set_ynr_en(attr, 0))
+    }
+}
+
+fn set_bayernr2d_en(attr: &mut BayerNrAttr, en: u8) -> XCamResult<()> {
+    match attr.eMode {
+        ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_AUTO => attr.stAuto.bayernr2DEn = en as _,
+        ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_MANUAL => attr.stManual.bayernr2DEn = en as _,
+        _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+    Ok(())
+}
+
+fn set_bayernr3d_en(attr: &mut BayerNrAttr, en: u8) -> XCamResult<()> {
+    match attr.eMode {
+        ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_AUTO => attr.stAuto.bayernr3DEn = en as _,
+        ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_MANUAL => attr.stManual.bayernr3DEn = en as _,
+        _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+    Ok(())
+}
+
+fn set_cnr_en(attr: &mut CnrAttr, en: u8) -> XCamResult<()> {
+    match attr.eMode {
+        ffi::Acnr_OPMode_t::ACNR_OP_MODE_AUTO => attr.stAuto.cnrEn = en as _,
+        ffi::Acnr_OPMode_t::ACNR_OP_MODE_MANUAL => attr.stManual.cnrEn = en as _,
+        _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+    Ok(())
+}
+
+fn set_ynr_en(attr: &mut YnrAttr, en: u8) -> XCamResult<()> {
+    match attr.eMode {
+        ffi::Aynr_OPMode_t::AYNR_OP_MODE_AUTO => attr.stAuto.ynrEn = en as _,
+        ffi::Aynr_OPMode_t::AYNR_OP_MODE_MANUAL => attr.stManual.ynrEn = en as _,
+        _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+    Ok(())
+}
+
+/// 一个描述批量设置的契定。
+pub trait Batching {
+    /// 收集 `f` 中的修改并一次性提交。
+    ///
+    /// 提交在持有上下文锁期间进行：先读取全部涉及模块的属性并应用修改，任一修改出错时不写回
+    /// 任何模块；随后每个模块只写回一次，写回失败时按相反顺序恢复已写回的模块。
+    fn batch<'a, F>(&self, f: F) -> Result<(), BatchError>
+    where
+        F: FnOnce(&mut Batch<'a>);
+}
+
+impl Batching for Context {
+    fn batch<'a, F>(&self, f: F) -> Result<(), BatchError>
+    where
+        F: FnOnce(&mut Batch<'a>),
+    {
+        let mut batch = Batch::default();
+        f(&mut batch);
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let _lock = self.lock();
+        let bayernr = stage(BatchModule::BayerNr, batch.bayernr, || {
+            self.get_bayernr_attrib()
+        })?;
+        let cnr = stage(BatchModule::Cnr, batch.cnr, || self.get_cnr_attrib())?;
+        let ynr = stage(BatchModule::Ynr, batch.ynr, || self.get_ynr_attrib())?;
+
+        let mut applied: Vec<Restore> = Vec::new();
+        let result = (|| {
+            if let Some((orig, attr)) = bayernr {
+                self.set_bayernr_attrib(attr)
+                    .map_err(|e| (BatchModule::BayerNr, e))?;
+                applied.push(Restore::BayerNr(Box::new(orig)));
+            }
+            if let Some((orig, attr)) = cnr {
+                self.set_cnr_attrib(attr)
+                    .map_err(|e| (BatchModule::Cnr, e))?;
+                applied.push(Restore::Cnr(Box::new(orig)));
+            }
+            if let Some((orig, attr)) = ynr {
+                self.set_ynr_attrib(attr)
+                    .map_err(|e| (BatchModule::Ynr, e))?;
+                applied.push(Restore::Ynr(Box::new(orig)));
+            }
+            Ok(())
+        })();
+
+        result.map_err(|(module, error)| {
+            let modules = applied.iter().map(Restore::module).collect();
+            let rollback_failures = applied
+                .into_iter()
+                .rev()
+                .filter_map(|r| {
+                    let module = r.module();
+                    r.apply(self).err().map(|e| (module, e))
+                })
+                .collect();
+            BatchError {
+                module,
+                error,
+                applied: modules,
+                rollback_failures,
+            }
+        })
+    }
+}
+
+/// 读取模块属性并依次应用修改，返回原属性及修改后的属性，无修改时返回 `None`。
+fn stage<T, G>(
+    module: BatchModule,
+    edits: Vec<Edit<'_, T>>,
+    get: G,
+) -> Result<Option<(T, T)>, BatchError>
+where
+    T: Copy,
+    G: FnOnce() -> XCamResult<T>,
+{
+    if edits.is_empty() {
+        return Ok(None);
+    }
+    let err = |error| BatchError {
+        module,
+        error,
+        applied: Vec::new(),
+        rollback_failures: Vec::new(),
+    };
+    let orig = get().map_err(err)?;
+    let mut attr = orig;
+    for edit in edits {
+        edit(&mut attr).map_err(err)?;
+    }
+    Ok(Some((orig, attr)))
+}
+
+enum Restore {
+    BayerNr(Box<BayerNrAttr>),
+    Cnr(Box<CnrAttr>),
+    Ynr(Box<YnrAttr>),
+}
+
+impl Restore {
+    fn module(&self) -> BatchModule {
+        match self {
+            Restore::BayerNr(_) => BatchModule::BayerNr,
+            Restore::Cnr(_) => BatchModule::Cnr,
+            Restore::Ynr(_) => BatchModule::Ynr,
+        }
+    }
+
+    fn apply(self, ctx: &Context) -> XCamResult<()> {
+        match self {
+            Restore::BayerNr(attr) => ctx.set_bayernr_attrib(*attr),
+            Restore::Cnr(attr) => ctx.set_cnr_attrib(*attr),
+            Restore::Ynr(attr) => ctx.set_ynr_attrib(*attr),
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! Bayer 域降噪
+//!
+//! BayerNR V2 模块在 Bayer 域进行降噪，包括空域降噪（2DNR）及时域降噪（3DNR），
+//! 自动模式下的参数按 ISO 分档，运行时根据当前 ISO 插值选取。
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::nr::{validate_iso_table, validate_shared, IsoInterpolate, IsoParams};
+use super::types::{BayerNrAttr, OpMode, XCamResult};
+
+/// 亮度噪声曲线的节点个数。
+pub const BAYERNR_LUMA_POINTS: usize = 16;
+
+/// 自动参数表的 ISO 档位数。
+pub const BAYERNR_ISO_NUM: usize = 13;
+
+/// 一个代表 Bayer 2DNR 参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bayer2dnrParams {
+    /// 按亮度分段的噪声强度曲线横坐标，自动参数表中各 ISO 档位共用。
+    pub luma_point: [i32; BAYERNR_LUMA_POINTS],
+    /// 各亮度节点对应的噪声标准差。
+    pub sigma: [i32; BAYERNR_LUMA_POINTS],
+    /// 滤波强度。
+    pub filter_strength: f32,
+    /// 边缘柔化程度。
+    pub edge_softness: f32,
+    /// 滤波比例。
+    pub ratio: f32,
+    /// 降噪结果与原图的融合权重，取值范围 [0,1]。
+    pub weight: f32,
+    /// 是否使用高斯引导滤波。
+    pub gauss_guide: bool,
+}
+
+/// 一个代表 Bayer 3DNR 参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bayer3dnrParams {
+    /// 按亮度分段的噪声强度曲线横坐标，自动参数表中各 ISO 档位共用。
+    pub luma_point: [i32; BAYERNR_LUMA_POINTS],
+    /// 各亮度节点对应的噪声标准差。
+    pub sigma: [i32; BAYERNR_LUMA_POINTS],
+    /// 滤波强度。
+    pub filter_strength: f32,
+    /// 低频钳位权重。
+    pub lo_clip_weight: f32,
+    /// 高频钳位权重。
+    pub hi_clip_weight: f32,
+    /// 柔化权重。
+    pub soft_weight: f32,
+}
+
+impl IsoInterpolate for Bayer2dnrParams {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            luma_point: self.luma_point,
+            sigma: self.sigma.lerp(&other.sigma, t),
+            filter_strength: self.filter_strength.lerp(&other.filter_strength, t),
+            edge_softness: self.edge_softness.lerp(&other.edge_softness, t),
+            ratio: self.ratio.lerp(&other.ratio, t),
+            weight: self.weight.lerp(&other.weight, t),
+            gauss_guide: self.gauss_guide.lerp(&other.gauss_guide, t),
+        }
+    }
+}
+
+impl IsoInterpolate for Bayer3dnrParams {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            luma_point: self.luma_point,
+            sigma: self.sigma.lerp(&other.sigma, t),
+            filter_strength: self.filter_strength.lerp(&other.filter_strength, t),
+            lo_clip_weight: self.lo_clip_weight.lerp(&other.lo_clip_weight, t),
+            hi_clip_weight: self.hi_clip_weight.lerp(&other.hi_clip_weight, t),
+            soft_weight: self.soft_weight.lerp(&other.soft_weight, t),
+        }
+    }
+}
+
+/// 一个描述 Bayer 域降噪控制的契定。
+pub trait BayerNoiseRemoval {
+    /// 获取 BayerNR V2 属性。
+    fn get_bayernr_attrib(&self) -> XCamResult<BayerNrAttr>;
+
+    /// 设置 BayerNR V2 属性。
+    fn set_bayernr_attrib<T: Into<BayerNrAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 获取 BayerNR 工作模式。
+    fn get_bayernr_mode(&self) -> XCamResult<OpMode>;
+
+    /// 设置 BayerNR 工作模式，仅支持 `Auto` 及 `Manual`。
+    fn set_bayernr_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 获取自动模式下按 ISO 索引的 2DNR 参数表。
+    fn get_bayer2dnr_auto(&self) -> XCamResult<Vec<IsoParams<Bayer2dnrParams>>>;
+
+    /// 设置自动模式下按 ISO 索引的 2DNR 参数表，并切换至自动模式。
+    ///
+    /// # Parameters
+    /// * `table` - 参数表，须包含 [`BAYERNR_ISO_NUM`] 个档位且 ISO 单调递增，
+    ///   各档位的 `luma_point` 须一致。
+    fn set_bayer2dnr_auto(&self, table: &[IsoParams<Bayer2dnrParams>]) -> XCamResult<()>;
+
+    /// 获取自动模式下按 ISO 索引的 3DNR 参数表。
+    fn get_bayer3dnr_auto(&self) -> XCamResult<Vec<IsoParams<Bayer3dnrParams>>>;
+
+    /// 设置自动模式下按 ISO 索引的 3DNR 参数表，并切换至自动模式。
+    ///
+    /// # Parameters
+    /// * `table` - 参数表，须包含 [`BAYERNR_ISO_NUM`] 个档位且 ISO 单调递增，
+    ///   各档位的 `luma_point` 须一致。
+    fn set_bayer3dnr_auto(&self, table: &[IsoParams<Bayer3dnrParams>]) -> XCamResult<()>;
+
+    /// 获取手动模式下的 2DNR 参数。
+    fn get_bayer2dnr_manual(&self) -> XCamResult<Bayer2dnrParams>;
+
+    /// 设置手动模式下的 2DNR 参数，并切换至手动模式。
+    fn set_bayer2dnr_manual(&self, params: &Bayer2dnrParams) -> XCamResult<()>;
+
+    /// 获取手动模式下的 3DNR 参数。
+    fn get_bayer3dnr_manual(&self) -> XCamResult<Bayer3dnrParams>;
+
+    /// 设置手动模式下的 3DNR 参数，并切换至手动模式。
+    fn set_bayer3dnr_manual(&self, params: &Bayer3dnrParams) -> XCamResult<()>;
+
+    /// 获取空域降噪强度，取值范围 [0,1]。
+    fn get_bayernr_sf_strength(&self) -> XCamResult<f32>;
+
+    /// 设置空域降噪强度，取值范围 [0,1]，0.5 为 IQ 默认强度。
+    fn set_bayernr_sf_strength(&self, percent: f32) -> XCamResult<()>;
+
+    /// 获取时域降噪强度，取值范围 [0,1]。
+    fn get_bayernr_tf_strength(&self) -> XCamResult<f32>;
+
+    /// 设置时域降噪强度，取值范围 [0,1]，0.5 为 IQ 默认强度。
+    fn set_bayernr_tf_strength(&self, percent: f32) -> XCamResult<()>;
+}
+
+impl BayerNoiseRemoval for Context {
+    fn get_bayernr_attrib(&self) -> XCamResult<BayerNrAttr> {
+        let mut attr = BayerNrAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    fn set_bayernr_attrib<T: Into<BayerNrAttr>>(&self, attr: T) -> XCamResult<()> {
+        let mut attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_SetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+        }
+    }
+
+    fn get_bayernr_mode(&self) -> XCamResult<OpMode> {
+        use ffi::Abayernr_OPMode_t::*;
+        self.get_bayernr_attrib().map(|attr| match attr.eMode {
+            ABAYERNR_OP_MODE_AUTO => OpMode::Auto,
+            ABAYERNR_OP_MODE_MANUAL => OpMode::Manual,
+            _ => OpMode::Invalid,
+        })
+    }
+
+    fn set_bayernr_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        use ffi::Abayernr_OPMode_t::*;
+        let mut attr = self.get_bayernr_attrib()?;
+        attr.eMode = match mode {
+            OpMode::Auto => ABAYERNR_OP_MODE_AUTO,
+            OpMode::Manual => ABAYERNR_OP_MODE_MANUAL,
+            _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+        };
+        self.set_bayernr_attrib(attr)
+    }
+
+    fn get_bayer2dnr_auto(&self) -> XCamResult<Vec<IsoParams<Bayer2dnrParams>>> {
+        let attr = self.get_bayernr_attrib()?;
+        let p = &attr.stAuto.st2DParams;
+        let table = (0..BAYERNR_ISO_NUM)
+            .map(|i| IsoParams {
+                iso: p.iso[i],
+                params: Bayer2dnrParams {
+                    luma_point: p.lumapoint.map(|x| x as _),
+                    sigma: p.sigma[i].map(|x| x as _),
+                    filter_strength: p.filter_strength[i],
+                    edge_softness: p.edgesofts[i],
+                    ratio: p.ratio[i],
+                    weight: p.weight[i],
+                    gauss_guide: p.gauss_guide[i] != 0,
+                },
+            })
+            .collect();
+        Ok(table)
+    }
+
+    fn set_bayer2dnr_auto(&self, table: &[IsoParams<Bayer2dnrParams>]) -> XCamResult<()> {
+        let _lock = self.lock();
+        validate_iso_table(table, BAYERNR_ISO_NUM)?;
+        validate_shared(table, |p| p.luma_point)?;
+        let mut attr = self.get_bayernr_attrib()?;
+        attr.eMode = ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_AUTO;
+        let p = &mut attr.stAuto.st2DParams;
+        p.lumapoint = table[0].params.luma_point.map(|x| x as _);
+        for (i, v) in table.iter().enumerate() {
+            p.iso[i] = v.iso;
+            p.sigma[i] = v.params.sigma.map(|x| x as _);
+            p.filter_strength[i] = v.params.filter_strength;
+            p.edgesofts[i] = v.params.edge_softness;
+            p.ratio[i] = v.params.ratio;
+            p.weight[i] = v.params.weight;
+            p.gauss_guide[i] = v.params.gauss_guide as _;
+        }
+        self.set_bayernr_attrib(attr)
+    }
+
+    fn get_bayer3dnr_auto(&self) -> XCamResult<Vec<IsoParams<Bayer3dnrParams>>> {
+        let attr = self.get_bayernr_attrib()?;
+        let p = &attr.stAuto.st3DParams;
+        let table = (0..BAYERNR_ISO_NUM)
+            .map(|i| IsoParams {
+                iso: p.iso[i],
+                params: Bayer3dnrParams {
+                    luma_point: p.lumapoint.map(|x| x as _),
+                    sigma: p.sigma[i].map(|x| x as _),
+                    filter_strength: p.filter_strength[i],
+                    lo_clip_weight: p.lo_clipwgt[i],
+                    hi_clip_weight: p.hi_clipwgt[i],
+                    soft_weight: p.softwgt[i],
+                },
+            })
+            .collect();
+        Ok(table)
+    }
+
+    fn set_bayer3dnr_auto(&self, table: &[IsoParams<Bayer3dnrParams>]) -> XCamResult<()> {
+        let _lock = self.lock();
+        validate_iso_table(table, BAYERNR_ISO_NUM)?;
+        validate_shared(table, |p| p.luma_point)?;
+        let mut attr = self.get_bayernr_attrib()?;
+        attr.eMode = ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_AUTO;
+        let p = &mut attr.stAuto.st3DParams;
+        p.lumapoint = table[0].params.luma_point.map(|x| x as _);
+        for (i, v) in table.iter().enumerate() {
+            p.iso[i] = v.iso;
+            p.sigma[i] = v.params.sigma.map(|x| x as _);
+            p.filter_strength[i] = v.params.filter_strength;
+            p.lo_clipwgt[i] = v.params.lo_clip_weight;
+            p.hi_clipwgt[i] = v.params.hi_clip_weight;
+            p.softwgt[i] = v.params.soft_weight;
+        }
+        self.set_bayernr_attrib(attr)
+    }
+
+    fn get_bayer2dnr_manual(&self) -> XCamResult<Bayer2dnrParams> {
+        let attr = self.get_bayernr_attrib()?;
+        let p = &attr.stManual.st2DParam;
+        Ok(Bayer2dnrParams {
+            luma_point: p.lumapoint.map(|x| x as _),
+            sigma: p.sigma.map(|x| x as _),
+            filter_strength: p.filter_strength,
+            edge_softness: p.edgesofts,
+            ratio: p.ratio,
+            weight: p.weight,
+            gauss_guide: p.gauss_guide != 0,
+        })
+    }
+
+    fn set_bayer2dnr_manual(&self, params: &Bayer2dnrParams) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_bayernr_attrib()?;
+        attr.eMode = ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_MANUAL;
+        let p = &mut attr.stManual.st2DParam;
+        p.lumapoint = params.luma_point.map(|x| x as _);
+        p.sigma = params.sigma.map(|x| x as _);
+        p.filter_strength = params.filter_strength;
+        p.edgesofts = params.edge_softness;
+        p.ratio = params.ratio;
+        p.weight = params.weight;
+        p.gauss_guide = params.gauss_guide as _;
+        self.set_bayernr_attrib(attr)
+    }
+
+    fn get_bayer3dnr_manual(&self) -> XCamResult<Bayer3dnrParams> {
+        let attr = self.get_bayernr_attrib()?;
+        let p = &attr.stManual.st3DParam;
+        Ok(Bayer3dnrParams {
+            luma_point: p.lumapoint.map(|x| x as _),
+            sigma: p.sigma.map(|x| x as _),
+            filter_strength: p.filter_strength,
+            lo_clip_weight: p.lo_clipwgt,
+            hi_clip_weight: p.hi_clipwgt,
+            soft_weight: p.softwgt,
+        })
+    }
+
+    fn set_bayer3dnr_manual(&self, params: &Bayer3dnrParams) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_bayernr_attrib()?;
+        attr.eMode = ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_MANUAL;
+        let p = &mut attr.stManual.st3DParam;
+        p.lumapoint = params.luma_point.map(|x| x as _);
+        p.sigma = params.sigma.map(|x| x as _);
+        p.filter_strength = params.filter_strength;
+        p.lo_clipwgt = params.lo_clip_weight;
+        p.hi_clipwgt = params.hi_clip_weight;
+        p.softwgt = params.soft_weight;
+        self.set_bayernr_attrib(attr)
+    }
+
+    fn get_bayernr_sf_strength(&self) -> XCamResult<f32> {
+        let mut percent: f32 = 0.0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_GetSFStrength(
+                self.lock().as_ptr(),
+                &mut percent,
+            ))
+            .ok()
+            .map(|_| percent)
+        }
+    }
+
+    fn set_bayernr_sf_strength(&self, percent: f32) -> XCamResult<()> {
+        if !(0.0..=1.0).contains(&percent) {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_SetSFStrength(
+                self.lock().as_ptr(),
+                percent,
+            ))
+            .ok()
+        }
+    }
+
+    fn get_bayernr_tf_strength(&self) -> XCamResult<f32> {
+        let mut percent: f32 = 0.0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_GetTFStrength(
+                self.lock().as_ptr(),
+                &mut percent,
+            ))
+            .ok()
+            .map(|_| percent)
+        }
+    }
+
+    fn set_bayernr_tf_strength(&self, percent: f32) -> XCamResult<()> {
+        if !(0.0..=1.0).contains(&percent) {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_SetTFStrength(
+                self.lock().as_ptr(),
+                percent,
+            ))
+            .ok()
+        }
+    }
+}
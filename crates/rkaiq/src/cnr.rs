@@ -0,0 +1,322 @@
+//! 色度降噪
+//!
+//! CNR V1 模块在 YUV 域对色度通道进行降噪，以亮度信息作为引导，
+//! 通过多级双边滤波抑制彩色噪声。
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::nr::{validate_iso_table, IsoInterpolate, IsoParams};
+use super::types::{CnrAttr, OpMode, XCamResult};
+
+/// 自动参数表的 ISO 档位数。
+pub const CNR_ISO_NUM: usize = 13;
+
+/// 一个代表 CNR 亮度引导参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CnrLumaParams {
+    /// 亮度引导增益。
+    pub ex_gain: f32,
+    /// G 通道增益。
+    pub g_gain: f32,
+    /// 引导比例。
+    pub ratio: f32,
+    /// 引导偏移。
+    pub offset: f32,
+}
+
+/// 一个代表 CNR 单级色度滤波参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CnrChromaFilter {
+    /// 值域滤波强度。
+    pub sigma_r: f32,
+    /// UV 增益。
+    pub uv_gain: f32,
+    /// 双边滤波融合比例，部分级别无此参数，此时忽略。
+    pub bf_ratio: f32,
+    /// 中值滤波融合比例，部分级别无此参数，此时忽略。
+    pub med_ratio: f32,
+}
+
+/// 一个代表 CNR 参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CnrParams {
+    /// 亮度引导参数。
+    pub luma: CnrLumaParams,
+    /// 三级色度滤波参数，分别作用于高、中、低频。
+    pub chroma: [CnrChromaFilter; 3],
+    /// 5x5 空域滤波核。
+    pub kernel_5x5: [f32; 5],
+}
+
+impl IsoInterpolate for CnrLumaParams {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            ex_gain: self.ex_gain.lerp(&other.ex_gain, t),
+            g_gain: self.g_gain.lerp(&other.g_gain, t),
+            ratio: self.ratio.lerp(&other.ratio, t),
+            offset: self.offset.lerp(&other.offset, t),
+        }
+    }
+}
+
+impl IsoInterpolate for CnrChromaFilter {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            sigma_r: self.sigma_r.lerp(&other.sigma_r, t),
+            uv_gain: self.uv_gain.lerp(&other.uv_gain, t),
+            bf_ratio: self.bf_ratio.lerp(&other.bf_ratio, t),
+            med_ratio: self.med_ratio.lerp(&other.med_ratio, t),
+        }
+    }
+}
+
+impl IsoInterpolate for CnrParams {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            luma: self.luma.lerp(&other.luma, t),
+            chroma: [
+                self.chroma[0].lerp(&other.chroma[0], t),
+                self.chroma[1].lerp(&other.chroma[1], t),
+                self.chroma[2].lerp(&other.chroma[2], t),
+            ],
+            kernel_5x5: self.kernel_5x5.lerp(&other.kernel_5x5, t),
+        }
+    }
+}
+
+/// 一个描述色度降噪控制的契定。
+pub trait ChromaNoiseRemoval {
+    /// 获取 CNR V1 属性。
+    fn get_cnr_attrib(&self) -> XCamResult<CnrAttr>;
+
+    /// 设置 CNR V1 属性。
+    fn set_cnr_attrib<T: Into<CnrAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 获取 CNR 工作模式。
+    fn get_cnr_mode(&self) -> XCamResult<OpMode>;
+
+    /// 设置 CNR 工作模式，仅支持 `Auto` 及 `Manual`。
+    fn set_cnr_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 获取自动模式下按 ISO 索引的参数表。
+    fn get_cnr_auto(&self) -> XCamResult<Vec<IsoParams<CnrParams>>>;
+
+    /// 设置自动模式下按 ISO 索引的参数表，并切换至自动模式。
+    ///
+    /// # Parameters
+    /// * `table` - 参数表，须包含 [`CNR_ISO_NUM`] 个档位且 ISO 单调递增。
+    fn set_cnr_auto(&self, table: &[IsoParams<CnrParams>]) -> XCamResult<()>;
+
+    /// 获取手动模式参数。
+    fn get_cnr_manual(&self) -> XCamResult<CnrParams>;
+
+    /// 设置手动模式参数，并切换至手动模式。
+    fn set_cnr_manual(&self, params: &CnrParams) -> XCamResult<()>;
+
+    /// 获取色度降噪强度，取值范围 [0,1]。
+    fn get_cnr_strength(&self) -> XCamResult<f32>;
+
+    /// 设置色度降噪强度，取值范围 [0,1]，0.5 为 IQ 默认强度。
+    fn set_cnr_strength(&self, percent: f32) -> XCamResult<()>;
+}
+
+impl ChromaNoiseRemoval for Context {
+    fn get_cnr_attrib(&self) -> XCamResult<CnrAttr> {
+        let mut attr = CnrAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_acnrV1_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    fn set_cnr_attrib<T: Into<CnrAttr>>(&self, attr: T) -> XCamResult<()> {
+        let mut attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_acnrV1_SetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+        }
+    }
+
+    fn get_cnr_mode(&self) -> XCamResult<OpMode> {
+        use ffi::Acnr_OPMode_t::*;
+        self.get_cnr_attrib().map(|attr| match attr.eMode {
+            ACNR_OP_MODE_AUTO => OpMode::Auto,
+            ACNR_OP_MODE_MANUAL => OpMode::Manual,
+            _ => OpMode::Invalid,
+        })
+    }
+
+    fn set_cnr_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        use ffi::Acnr_OPMode_t::*;
+        let mut attr = self.get_cnr_attrib()?;
+        attr.eMode = match mode {
+            OpMode::Auto => ACNR_OP_MODE_AUTO,
+            OpMode::Manual => ACNR_OP_MODE_MANUAL,
+            _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+        };
+        self.set_cnr_attrib(attr)
+    }
+
+    fn get_cnr_auto(&self) -> XCamResult<Vec<IsoParams<CnrParams>>> {
+        let attr = self.get_cnr_attrib()?;
+        let p = &attr.stAuto.stParams;
+        let table = (0..CNR_ISO_NUM)
+            .map(|i| IsoParams {
+                iso: p.iso[i],
+                params: CnrParams {
+                    luma: CnrLumaParams {
+                        ex_gain: p.rkcnr_exgain[i],
+                        g_gain: p.rkcnr_g_gain[i],
+                        ratio: p.ratio[i],
+                        offset: p.offset[i],
+                    },
+                    chroma: [
+                        CnrChromaFilter {
+                            sigma_r: p.sigmaR1[i],
+                            uv_gain: p.uvgain1[i],
+                            bf_ratio: p.bfRatio1[i],
+                            med_ratio: p.medRatio1[i],
+                        },
+                        CnrChromaFilter {
+                            sigma_r: p.sigmaR2[i],
+                            uv_gain: p.uvgain2[i],
+                            bf_ratio: 0.0,
+                            med_ratio: p.medRatio2[i],
+                        },
+                        CnrChromaFilter {
+                            sigma_r: p.sigmaR3[i],
+                            uv_gain: p.uvgain3[i],
+                            bf_ratio: p.bfRatio3[i],
+                            med_ratio: 0.0,
+                        },
+                    ],
+                    kernel_5x5: p.kernel_5x5_table,
+                },
+            })
+            .collect();
+        Ok(table)
+    }
+
+    fn set_cnr_auto(&self, table: &[IsoParams<CnrParams>]) -> XCamResult<()> {
+        let _lock = self.lock();
+        validate_iso_table(table, CNR_ISO_NUM)?;
+        let mut attr = self.get_cnr_attrib()?;
+        attr.eMode = ffi::Acnr_OPMode_t::ACNR_OP_MODE_AUTO;
+        let p = &mut attr.stAuto.stParams;
+        // 滤波核为各 ISO 档位共用，以第一档为准。
+        p.kernel_5x5_table = table[0].params.kernel_5x5;
+        for (i, v) in table.iter().enumerate() {
+            let c = &v.params.chroma;
+            p.iso[i] = v.iso;
+            p.rkcnr_exgain[i] = v.params.luma.ex_gain;
+            p.rkcnr_g_gain[i] = v.params.luma.g_gain;
+            p.ratio[i] = v.params.luma.ratio;
+            p.offset[i] = v.params.luma.offset;
+            p.sigmaR1[i] = c[0].sigma_r;
+            p.uvgain1[i] = c[0].uv_gain;
+            p.bfRatio1[i] = c[0].bf_ratio;
+            p.medRatio1[i] = c[0].med_ratio;
+            p.sigmaR2[i] = c[1].sigma_r;
+            p.uvgain2[i] = c[1].uv_gain;
+            p.medRatio2[i] = c[1].med_ratio;
+            p.sigmaR3[i] = c[2].sigma_r;
+            p.uvgain3[i] = c[2].uv_gain;
+            p.bfRatio3[i] = c[2].bf_ratio;
+        }
+        self.set_cnr_attrib(attr)
+    }
+
+    fn get_cnr_manual(&self) -> XCamResult<CnrParams> {
+        let attr = self.get_cnr_attrib()?;
+        let p = &attr.stManual.stSelect;
+        Ok(CnrParams {
+            luma: CnrLumaParams {
+                ex_gain: p.rkcnr_exgain,
+                g_gain: p.rkcnr_g_gain,
+                ratio: p.ratio,
+                offset: p.offset,
+            },
+            chroma: [
+                CnrChromaFilter {
+                    sigma_r: p.sigmaR1,
+                    uv_gain: p.uvgain1,
+                    bf_ratio: p.bfRatio1,
+                    med_ratio: p.medRatio1,
+                },
+                CnrChromaFilter {
+                    sigma_r: p.sigmaR2,
+                    uv_gain: p.uvgain2,
+                    bf_ratio: 0.0,
+                    med_ratio: p.medRatio2,
+                },
+                CnrChromaFilter {
+                    sigma_r: p.sigmaR3,
+                    uv_gain: p.uvgain3,
+                    bf_ratio: p.bfRatio3,
+                    med_ratio: 0.0,
+                },
+            ],
+            kernel_5x5: p.kernel_5x5_table,
+        })
+    }
+
+    fn set_cnr_manual(&self, params: &CnrParams) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_cnr_attrib()?;
+        attr.eMode = ffi::Acnr_OPMode_t::ACNR_OP_MODE_MANUAL;
+        let p = &mut attr.stManual.stSelect;
+        let c = &params.chroma;
+        p.rkcnr_exgain = params.luma.ex_gain;
+        p.rkcnr_g_gain = params.luma.g_gain;
+        p.ratio = params.luma.ratio;
+        p.offset = params.luma.offset;
+        p.sigmaR1 = c[0].sigma_r;
+        p.uvgain1 = c[0].uv_gain;
+        p.bfRatio1 = c[0].bf_ratio;
+        p.medRatio1 = c[0].med_ratio;
+        p.sigmaR2 = c[1].sigma_r;
+        p.uvgain2 = c[1].uv_gain;
+        p.medRatio2 = c[1].med_ratio;
+        p.sigmaR3 = c[2].sigma_r;
+        p.uvgain3 = c[2].uv_gain;
+        p.bfRatio3 = c[2].bf_ratio;
+        p.kernel_5x5_table = params.kernel_5x5;
+        self.set_cnr_attrib(attr)
+    }
+
+    fn get_cnr_strength(&self) -> XCamResult<f32> {
+        let mut percent: f32 = 0.0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_acnrV1_GetStrength(
+                self.lock().as_ptr(),
+                &mut percent,
+            ))
+            .ok()
+            .map(|_| percent)
+        }
+    }
+
+    fn set_cnr_strength(&self, percent: f32) -> XCamResult<()> {
+        if !(0.0..=1.0).contains(&percent) {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_acnrV1_SetStrength(
+                self.lock().as_ptr(),
+                percent,
+            ))
+            .ok()
+        }
+    }
+}
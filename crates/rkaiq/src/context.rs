@@ -1,17 +1,28 @@
 use super::ffi::{self, XCamReturn};
+use super::iqfile::{IqLookup, IqNotFound, IqResolution, IqResolver, DEFAULT_IQ_FILE_DIR};
+use super::multi_isp::{MultiIsp, MultiIspLayout};
 use super::sysctl;
 use super::types::WorkingMode;
 
 use std::borrow::Cow;
 use std::ffi::CString;
 use std::io;
+use std::marker::PhantomData;
 use std::ptr::NonNull;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, ThreadId};
 
+/// 一个代表 AIQ 上下文的类型。
+///
+/// librkaiq 的 uapi 接口未声明可重入，上下文内部以一把可重入锁串行化全部接口调用，
+/// 读取-修改-写回属性的操作在持锁期间完成，可在多个线程间共享使用。
 #[derive(Debug)]
 pub struct Context {
-    pub(crate) internal: NonNull<ffi::rk_aiq_sys_ctx_t>,
+    internal: NonNull<ffi::rk_aiq_sys_ctx_t>,
+    lock: ContextLock,
 }
 
+// SAFETY: 指针仅能通过 `Context::lock` 取得，全部接口调用均在持锁期间进行。
 unsafe impl Send for Context {}
 unsafe impl Sync for Context {}
 
@@ -30,7 +41,12 @@ impl Context {
         assert!(!ptr.is_null());
         NonNull::new(ptr).map_or_else(
             || Err(io::Error::last_os_error()),
-            |v| Ok(Self { internal: v }),
+            |v| {
+                Ok(Self {
+                    internal: v,
+                    lock: ContextLock::new(),
+                })
+            },
         )
     }
 
@@ -44,6 +60,18 @@ impl Context {
             .map_err(|x| io::Error::new(io::ErrorKind::Other, format!("{}", x)))?;
         Self::new(sns_ent_name, iq_file_dir)
     }
+
+    /// 获取上下文访问锁。
+    ///
+    /// 持锁期间其他线程对该上下文的调用将被阻塞，可用于将多个调用组合为原子操作；
+    /// 同一线程可重复获取。
+    pub fn lock(&self) -> ContextGuard<'_> {
+        self.lock.acquire();
+        ContextGuard {
+            ctx: self,
+            _not_send: PhantomData,
+        }
+    }
 }
 
 impl Drop for Context {
@@ -54,9 +82,87 @@ impl Drop for Context {
     }
 }
 
+/// 一个代表上下文访问锁守卫的类型，释放时解锁。
+#[derive(Debug)]
+pub struct ContextGuard<'a> {
+    ctx: &'a Context,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ContextGuard<'_> {
+    pub(crate) fn as_ptr(&self) -> *mut ffi::rk_aiq_sys_ctx_t {
+        self.ctx.internal.as_ptr()
+    }
+}
+
+impl Drop for ContextGuard<'_> {
+    fn drop(&mut self) {
+        self.ctx.lock.release();
+    }
+}
+
+#[derive(Debug, Default)]
+struct LockState {
+    owner: Option<ThreadId>,
+    count: usize,
+}
+
+/// 可重入锁，同一线程嵌套加锁时仅增加计数。
+#[derive(Debug, Default)]
+struct ContextLock {
+    state: Mutex<LockState>,
+    cond: Condvar,
+}
+
+impl ContextLock {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, LockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn acquire(&self) {
+        let me = thread::current().id();
+        let mut state = self.state();
+        loop {
+            match state.owner {
+                None => {
+                    state.owner = Some(me);
+                    state.count = 1;
+                    return;
+                }
+                Some(owner) if owner == me => {
+                    state.count += 1;
+                    return;
+                }
+                Some(_) => {
+                    state = self
+                        .cond
+                        .wait(state)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        }
+    }
+
+    fn release(&self) {
+        let mut state = self.state();
+        state.count -= 1;
+        if state.count == 0 {
+            state.owner = None;
+            drop(state);
+            self.cond.notify_one();
+        }
+    }
+}
+
 pub struct ContextBuilder<'a> {
     sns_ent_name: Option<Cow<'a, str>>,
     iq_file_dir: Option<Cow<'a, str>>,
+    iq_resolver: Option<(IqResolver, IqLookup)>,
+    working_mode: Option<WorkingMode>,
 }
 
 impl<'a> ContextBuilder<'a> {
@@ -64,6 +170,8 @@ impl<'a> ContextBuilder<'a> {
         Self {
             sns_ent_name: None,
             iq_file_dir: None,
+            iq_resolver: None,
+            working_mode: None,
         }
     }
 
@@ -76,6 +184,61 @@ impl<'a> ContextBuilder<'a> {
         self.iq_file_dir = Some(val.into());
         self
     }
+
+    /// 使用定位器查找 IQ 文件，找到的文件将通过 `pre_init` 强制加载，
+    /// 其所在目录优先于 [`ContextBuilder::iq_file_dir`]。
+    pub fn iq_resolver(mut self, resolver: IqResolver, lookup: IqLookup) -> Self {
+        self.iq_resolver = Some((resolver, lookup));
+        self
+    }
+
+    /// 设置强制加载 IQ 文件时使用的工作模式，默认为 [`WorkingMode::Normal`]。
+    pub fn working_mode(mut self, mode: WorkingMode) -> Self {
+        self.working_mode = Some(mode);
+        self
+    }
+
+    /// 返回将要使用的 IQ 文件，未设置定位器时返回 `None`。
+    pub fn resolve_iq_file(&self) -> Option<Result<IqResolution, IqNotFound>> {
+        self.iq_resolver
+            .as_ref()
+            .map(|(resolver, lookup)| resolver.resolve(lookup))
+    }
+
+    /// 创建 AIQ 上下文。
+    pub fn build(self) -> Result<Context, io::Error> {
+        let sns_ent_name = self.sns_ent_name.as_deref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "sns_ent_name is required")
+        })?;
+        match self.resolve_iq_file() {
+            Some(Ok(iq)) => Context::with_force_iq_file(
+                sns_ent_name,
+                &iq.dir().to_string_lossy(),
+                &iq.file_name(),
+                self.working_mode.unwrap_or(WorkingMode::Normal),
+            ),
+            Some(Err(err)) => Err(io::Error::new(io::ErrorKind::NotFound, err)),
+            None => Context::new(
+                sns_ent_name,
+                self.iq_file_dir.as_deref().unwrap_or(DEFAULT_IQ_FILE_DIR),
+            ),
+        }
+    }
+
+    /// 创建 AIQ 上下文并以整幅图像分辨率准备，传感器工作在多 ISP 拼接模式时一并返回切分方式。
+    pub fn build_prepared(
+        self,
+        width: u32,
+        height: u32,
+    ) -> Result<(Context, Option<MultiIspLayout>), io::Error> {
+        let to_io = |err| io::Error::other(format!("{}", err));
+        let sns_ent_name = self.sns_ent_name.clone().unwrap_or_default();
+        let mode = self.working_mode.unwrap_or_default();
+        let ctx = self.build()?;
+        let info = sysctl::get_static_metas(sns_ent_name.as_bytes()).map_err(to_io)?;
+        let layout = ctx.prepare_for(&info, width, height, mode).map_err(to_io)?;
+        Ok((ctx, layout))
+    }
 }
 
 impl<'a> Default for ContextBuilder<'a> {
@@ -86,10 +249,71 @@ impl<'a> Default for ContextBuilder<'a> {
 
 unsafe extern "C" fn default_error_callback(_err_msg: *mut ffi::rk_aiq_err_msg_t) -> XCamReturn {
     // println!("err_msg={:p}", err_msg);
+    #[cfg(feature = "async")]
+    super::aio::dispatch_error(_err_msg);
     XCamReturn::XCAM_RETURN_NO_ERROR
 }
 
 unsafe extern "C" fn default_metas_callback(_metas: *mut ffi::rk_aiq_metas_t) -> XCamReturn {
     // println!("metas={:p}", metas);
+    #[cfg(feature = "async")]
+    super::aio::dispatch_metas(_metas);
     XCamReturn::XCAM_RETURN_NO_ERROR
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::UnsafeCell;
+    use std::sync::Arc;
+
+    /// 模拟一个非线程安全的属性，读写均需持锁，与 uapi 的 Get/SetAttrib 相同。
+    struct MockAttr {
+        lock: ContextLock,
+        value: UnsafeCell<u64>,
+    }
+
+    unsafe impl Sync for MockAttr {}
+
+    impl MockAttr {
+        fn get(&self) -> u64 {
+            self.lock.acquire();
+            let v = unsafe { *self.value.get() };
+            self.lock.release();
+            v
+        }
+
+        fn set(&self, v: u64) {
+            self.lock.acquire();
+            thread::yield_now();
+            unsafe { *self.value.get() = v };
+            self.lock.release();
+        }
+
+        fn increment(&self) {
+            self.lock.acquire();
+            let v = self.get();
+            self.set(v + 1);
+            self.lock.release();
+        }
+    }
+
+    #[test]
+    fn test_lock_no_lost_updates() {
+        const THREADS: u64 = 8;
+        const ROUNDS: u64 = 2000;
+        let attr = Arc::new(MockAttr {
+            lock: ContextLock::new(),
+            value: UnsafeCell::new(0),
+        });
+        let handles: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let attr = attr.clone();
+                thread::spawn(move || (0..ROUNDS).for_each(|_| attr.increment()))
+            })
+            .collect();
+        handles.into_iter().for_each(|h| h.join().unwrap());
+        assert_eq!(attr.get(), THREADS * ROUNDS);
+        assert_eq!(attr.lock.state().count, 0);
+    }
+}
@@ -0,0 +1,431 @@
+//! 去马赛克
+//!
+//! Debayer 模块通过插值将 Bayer 格式的原始数据还原为全彩色图像，
+//! 可在伪彩抑制与清晰度之间进行权衡。
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::types::{DebayerAttr, DebayerAttrAuto, DebayerAttrManual, OpMode, XCamResult};
+
+/// 自动模式参数表的 ISO 档位数。
+pub const DEBAYER_ISO_NUM: usize = 9;
+
+/// 锐化强度的最大值。
+pub const DEBAYER_SHARP_STRENGTH_MAX: u8 = 4;
+
+/// 一个描述去马赛克控制的契定。
+pub trait Debayer {
+    /// 获取去马赛克属性。
+    fn get_debayer_attrib(&self) -> XCamResult<DebayerAttr>;
+
+    /// 设置去马赛克属性。
+    fn set_debayer_attrib<T: Into<DebayerAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 开启去马赛克模块。
+    fn enable_debayer(&self) -> XCamResult<()>;
+
+    /// 关闭去马赛克模块。
+    fn disable_debayer(&self) -> XCamResult<()>;
+
+    /// 获取去马赛克工作模式。
+    fn get_debayer_mode(&self) -> XCamResult<OpMode>;
+
+    /// 设置去马赛克工作模式，仅支持 `Auto` 及 `Manual`。
+    fn set_debayer_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 设置自动模式下按 ISO 索引的参数表，并切换至自动模式。
+    fn set_debayer_auto<T: Into<DebayerAttrAuto>>(&self, auto: T) -> XCamResult<()>;
+
+    /// 设置手动模式参数，并切换至手动模式。
+    fn set_debayer_manual<T: Into<DebayerAttrManual>>(&self, manual: T) -> XCamResult<()>;
+}
+
+impl Debayer for Context {
+    fn get_debayer_attrib(&self) -> XCamResult<DebayerAttr> {
+        let mut attr = DebayerAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_adebayer_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    fn set_debayer_attrib<T: Into<DebayerAttr>>(&self, attr: T) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_adebayer_SetAttrib(
+                self.lock().as_ptr(),
+                attr.into(),
+            ))
+            .ok()
+        }
+    }
+
+    fn enable_debayer(&self) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_debayer_attrib()?;
+        attr.enable = 1;
+        self.set_debayer_attrib(attr)
+    }
+
+    fn disable_debayer(&self) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_debayer_attrib()?;
+        attr.enable = 0;
+        self.set_debayer_attrib(attr)
+    }
+
+    fn get_debayer_mode(&self) -> XCamResult<OpMode> {
+        self.get_debayer_attrib().map(|attr| attr.mode.into())
+    }
+
+    fn set_debayer_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_debayer_attrib()?;
+        attr.mode = debayer_op_mode(mode)?;
+        self.set_debayer_attrib(attr)
+    }
+
+    fn set_debayer_auto<T: Into<DebayerAttrAuto>>(&self, auto: T) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_debayer_attrib()?;
+        attr.mode = ffi::rk_aiq_debayer_op_mode_t::RK_AIQ_DEBAYER_MODE_AUTO;
+        attr.stAuto = auto.into();
+        self.set_debayer_attrib(attr)
+    }
+
+    fn set_debayer_manual<T: Into<DebayerAttrManual>>(&self, manual: T) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_debayer_attrib()?;
+        attr.mode = ffi::rk_aiq_debayer_op_mode_t::RK_AIQ_DEBAYER_MODE_MANUAL;
+        attr.stManual = manual.into();
+        self.set_debayer_attrib(attr)
+    }
+}
+
+impl From<ffi::rk_aiq_debayer_op_mode_t> for OpMode {
+    fn from(val: ffi::rk_aiq_debayer_op_mode_t) -> Self {
+        use ffi::rk_aiq_debayer_op_mode_t::*;
+        match val {
+            RK_AIQ_DEBAYER_MODE_AUTO => OpMode::Auto,
+            RK_AIQ_DEBAYER_MODE_MANUAL => OpMode::Manual,
+            _ => OpMode::Invalid,
+        }
+    }
+}
+
+fn debayer_op_mode(mode: OpMode) -> XCamResult<ffi::rk_aiq_debayer_op_mode_t> {
+    use ffi::rk_aiq_debayer_op_mode_t::*;
+    match mode {
+        OpMode::Auto => Ok(RK_AIQ_DEBAYER_MODE_AUTO),
+        OpMode::Manual => Ok(RK_AIQ_DEBAYER_MODE_MANUAL),
+        _ => Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+}
+
+/// 一个代表去马赛克手动参数构建器的类型。
+#[derive(Default)]
+pub struct DebayerManualBuilder {
+    sharp_strength: Option<u8>,
+    hf_offset: Option<u16>,
+    filter1: Option<[i8; 5]>,
+    filter2: Option<[i8; 5]>,
+    gain_offset: Option<u8>,
+    offset: Option<u8>,
+    clip: Option<bool>,
+    filter_enables: Option<(bool, bool)>,
+    thresholds: Option<(u8, u8)>,
+    dist_scale: Option<u8>,
+    shift_num: Option<u8>,
+}
+
+impl DebayerManualBuilder {
+    /// 创建一个去马赛克手动参数构建器实例。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置锐化强度，取值越大图像越清晰，伪彩越明显。
+    pub fn sharp_strength(mut self, val: u8) -> Self {
+        self.sharp_strength = Some(val);
+        self
+    }
+
+    /// 设置高频偏移量。
+    pub fn hf_offset(mut self, val: u16) -> Self {
+        self.hf_offset = Some(val);
+        self
+    }
+
+    /// 设置第一组插值滤波器系数。
+    pub fn filter1(mut self, coefs: [i8; 5]) -> Self {
+        self.filter1 = Some(coefs);
+        self
+    }
+
+    /// 设置第二组插值滤波器系数。
+    pub fn filter2(mut self, coefs: [i8; 5]) -> Self {
+        self.filter2 = Some(coefs);
+        self
+    }
+
+    /// 设置增益偏移量。
+    pub fn gain_offset(mut self, val: u8) -> Self {
+        self.gain_offset = Some(val);
+        self
+    }
+
+    /// 设置插值偏移量。
+    pub fn offset(mut self, val: u8) -> Self {
+        self.offset = Some(val);
+        self
+    }
+
+    /// 设置是否开启插值结果钳位。
+    pub fn clip(mut self, enabled: bool) -> Self {
+        self.clip = Some(enabled);
+        self
+    }
+
+    /// 设置是否开启 G 通道及色度通道滤波。
+    pub fn filter_enables(mut self, g: bool, c: bool) -> Self {
+        self.filter_enables = Some((g, c));
+        self
+    }
+
+    /// 设置边缘判定阈值。
+    pub fn thresholds(mut self, thed0: u8, thed1: u8) -> Self {
+        self.thresholds = Some((thed0, thed1));
+        self
+    }
+
+    /// 设置距离缩放系数。
+    pub fn dist_scale(mut self, val: u8) -> Self {
+        self.dist_scale = Some(val);
+        self
+    }
+
+    /// 设置移位数。
+    pub fn shift_num(mut self, val: u8) -> Self {
+        self.shift_num = Some(val);
+        self
+    }
+
+    /// 返回去马赛克手动参数，未设置的字段取默认值。
+    pub fn build(self) -> DebayerAttrManual {
+        self.build_from(DebayerAttrManual::default())
+    }
+
+    /// 在已有手动参数的基础上返回去马赛克手动参数，未设置的字段保持不变。
+    pub fn build_from(self, mut manual: DebayerAttrManual) -> DebayerAttrManual {
+        if let Some(v) = self.sharp_strength {
+            manual.sharp_strength = v;
+        }
+        if let Some(v) = self.hf_offset {
+            manual.debayer_hf_offset = v;
+        }
+        if let Some(v) = self.filter1 {
+            manual.debayer_filter1 = v.map(|x| x as _);
+        }
+        if let Some(v) = self.filter2 {
+            manual.debayer_filter2 = v.map(|x| x as _);
+        }
+        if let Some(v) = self.gain_offset {
+            manual.debayer_gain_offset = v;
+        }
+        if let Some(v) = self.offset {
+            manual.debayer_offset = v;
+        }
+        if let Some(v) = self.clip {
+            manual.debayer_clip_en = v as _;
+        }
+        if let Some((g, c)) = self.filter_enables {
+            manual.debayer_filter_g_en = g as _;
+            manual.debayer_filter_c_en = c as _;
+        }
+        if let Some((thed0, thed1)) = self.thresholds {
+            manual.debayer_thed0 = thed0;
+            manual.debayer_thed1 = thed1;
+        }
+        if let Some(v) = self.dist_scale {
+            manual.debayer_dist_scale = v;
+        }
+        if let Some(v) = self.shift_num {
+            manual.debayer_shift_num = v;
+        }
+        manual
+    }
+}
+
+/// 一个代表去马赛克自动参数构建器的类型。
+///
+/// 自动模式下的参数表以 ISO 为索引，共 [`DEBAYER_ISO_NUM`] 档。
+#[derive(Default)]
+pub struct DebayerAutoBuilder {
+    iso: Option<Vec<i32>>,
+    sharp_strength: Option<Vec<u8>>,
+    hf_offset: Option<Vec<u16>>,
+    filter1: Option<[i8; 5]>,
+    filter2: Option<[i8; 5]>,
+}
+
+impl DebayerAutoBuilder {
+    /// 创建一个去马赛克自动参数构建器实例。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置各档位对应的 ISO 值，须单调递增。
+    pub fn iso(mut self, table: &[i32]) -> Self {
+        self.iso = Some(table.to_vec());
+        self
+    }
+
+    /// 设置各 ISO 档位的锐化强度，取值范围 [0, [`DEBAYER_SHARP_STRENGTH_MAX`]]。
+    pub fn sharp_strength(mut self, table: &[u8]) -> Self {
+        self.sharp_strength = Some(table.to_vec());
+        self
+    }
+
+    /// 设置各 ISO 档位的高频偏移量。
+    pub fn hf_offset(mut self, table: &[u16]) -> Self {
+        self.hf_offset = Some(table.to_vec());
+        self
+    }
+
+    /// 设置第一组插值滤波器系数。
+    pub fn filter1(mut self, coefs: [i8; 5]) -> Self {
+        self.filter1 = Some(coefs);
+        self
+    }
+
+    /// 设置第二组插值滤波器系数。
+    pub fn filter2(mut self, coefs: [i8; 5]) -> Self {
+        self.filter2 = Some(coefs);
+        self
+    }
+
+    /// 返回去马赛克自动参数，未设置的字段取默认值。
+    ///
+    /// 参数表须包含 [`DEBAYER_ISO_NUM`] 个档位且取值在有效范围内，否则返回参数错误。
+    pub fn build(self) -> XCamResult<DebayerAttrAuto> {
+        self.build_from(DebayerAttrAuto::default())
+    }
+
+    /// 在已有自动参数的基础上返回去马赛克自动参数，未设置的字段保持不变。
+    pub fn build_from(self, mut auto: DebayerAttrAuto) -> XCamResult<DebayerAttrAuto> {
+        if let Some(v) = self.iso {
+            if v.windows(2).any(|w| w[0] >= w[1]) {
+                return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+            }
+            auto.ISO = iso_table(&v)?.map(|x| x as _);
+        }
+        if let Some(v) = self.sharp_strength {
+            if v.iter().any(|&x| x > DEBAYER_SHARP_STRENGTH_MAX) {
+                return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+            }
+            auto.sharp_strength = iso_table(&v)?;
+        }
+        if let Some(v) = self.hf_offset {
+            auto.debayer_hf_offset = iso_table(&v)?;
+        }
+        if let Some(v) = self.filter1 {
+            auto.debayer_filter1 = v.map(|x| x as _);
+        }
+        if let Some(v) = self.filter2 {
+            auto.debayer_filter2 = v.map(|x| x as _);
+        }
+        Ok(auto)
+    }
+}
+
+fn iso_table<T: Copy>(table: &[T]) -> XCamResult<[T; DEBAYER_ISO_NUM]> {
+    table
+        .try_into()
+        .map_err(|_| XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISO: [i32; DEBAYER_ISO_NUM] = [50, 100, 200, 400, 800, 1600, 3200, 6400, 12800];
+
+    #[test]
+    fn test_manual_builder() {
+        let manual = DebayerManualBuilder::new()
+            .sharp_strength(3)
+            .filter1([2, -6, 0, 6, -2])
+            .clip(true)
+            .thresholds(2, 4)
+            .build();
+        assert_eq!(manual.sharp_strength, 3);
+        assert_eq!(manual.debayer_filter1, [2, -6, 0, 6, -2].map(|x| x as _));
+        assert_ne!(manual.debayer_clip_en, 0);
+        assert_eq!((manual.debayer_thed0, manual.debayer_thed1), (2, 4));
+        assert_eq!(manual.debayer_hf_offset, 0);
+
+        let base = DebayerManualBuilder::new().hf_offset(7).offset(5).build();
+        let manual = DebayerManualBuilder::new().offset(9).build_from(base);
+        assert_eq!(manual.debayer_hf_offset, 7);
+        assert_eq!(manual.debayer_offset, 9);
+    }
+
+    #[test]
+    fn test_auto_builder() {
+        let auto = DebayerAutoBuilder::new()
+            .iso(&ISO)
+            .sharp_strength(&[4, 4, 4, 3, 3, 2, 2, 1, 1])
+            .filter2([2, -4, 4, -4, 2])
+            .build()
+            .unwrap();
+        assert_eq!(auto.ISO, ISO.map(|x| x as _));
+        assert_eq!(auto.sharp_strength[8], 1);
+        assert_eq!(auto.debayer_filter2, [2, -4, 4, -4, 2].map(|x| x as _));
+        assert_eq!(auto.debayer_hf_offset, [0; DEBAYER_ISO_NUM]);
+
+        let auto = DebayerAutoBuilder::new()
+            .hf_offset(&[1; DEBAYER_ISO_NUM])
+            .build_from(auto)
+            .unwrap();
+        assert_eq!(auto.ISO, ISO.map(|x| x as _));
+        assert_eq!(auto.sharp_strength[0], 4);
+        assert_eq!(auto.debayer_hf_offset, [1; DEBAYER_ISO_NUM]);
+    }
+
+    #[test]
+    fn test_auto_builder_validation() {
+        let param = XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM);
+        assert_eq!(
+            DebayerAutoBuilder::new()
+                .iso(&ISO[1..])
+                .build()
+                .unwrap_err(),
+            param
+        );
+        let mut iso = ISO;
+        iso.swap(2, 3);
+        assert_eq!(
+            DebayerAutoBuilder::new().iso(&iso).build().unwrap_err(),
+            param
+        );
+        assert_eq!(
+            DebayerAutoBuilder::new()
+                .hf_offset(&[0; 10])
+                .build()
+                .unwrap_err(),
+            param
+        );
+        let mut strength = [0; DEBAYER_ISO_NUM];
+        strength[4] = DEBAYER_SHARP_STRENGTH_MAX + 1;
+        assert_eq!(
+            DebayerAutoBuilder::new()
+                .sharp_strength(&strength)
+                .build()
+                .unwrap_err(),
+            param
+        );
+    }
+}
@@ -4,27 +4,117 @@
 use super::context::Context;
 use super::error::XCamError;
 use super::ffi;
+use super::nr::check_range;
+#[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+use super::types::DehazeAttr;
 use super::types::{OpMode, XCamResult};
 
+/// 一个描述去雾模块处理方式的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DehazeFunction {
+    /// 基于暗通道的去雾。
+    Dehaze,
+    /// 图像增强。
+    Enhance,
+    /// 直方图均衡。
+    HistEqualization,
+}
+
+/// 一个代表去雾大气光参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DehazeAirLight {
+    /// 大气光估计下限，取值范围 [0,1024]。
+    pub min: f32,
+    /// 大气光估计上限，取值范围 [0,1024]。
+    pub max: f32,
+    /// 大气光时域滤波强度，取值范围 [0,255]。
+    pub sigma: f32,
+}
+
+impl DehazeAirLight {
+    /// 校验各参数是否在取值范围内，且下限不大于上限。
+    pub fn validate(&self) -> XCamResult<()> {
+        check_range(self.min, 0.0, 1024.0)?;
+        check_range(self.max, self.min, 1024.0)?;
+        check_range(self.sigma, 0.0, 255.0)
+    }
+}
+
+/// 一个代表去雾透射率参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DehazeTransmission {
+    /// 透射率基准值，取值范围 [0,1024]。
+    pub base: f32,
+    /// 透射率偏移量，取值范围 [0,1024]。
+    pub offset: f32,
+    /// 透射率上限，取值范围 [0,1024]。
+    pub max: f32,
+    /// 透射率时域滤波强度，取值范围 [0,255]。
+    pub sigma: f32,
+}
+
+impl DehazeTransmission {
+    /// 校验各参数是否在取值范围内。
+    pub fn validate(&self) -> XCamResult<()> {
+        check_range(self.base, 0.0, 1024.0)?;
+        check_range(self.offset, 0.0, 1024.0)?;
+        check_range(self.max, 0.0, 1024.0)?;
+        check_range(self.sigma, 0.0, 255.0)
+    }
+}
+
 pub trait Defog {
     fn enable_dhz(&self) -> XCamResult<()>;
     fn disable_dhz(&self) -> XCamResult<()>;
 
     fn get_dhz_mode(&self) -> XCamResult<OpMode>;
     fn set_dhz_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 获取去雾模块当前的处理方式。
+    fn get_dhz_function(&self) -> XCamResult<DehazeFunction>;
+
+    /// 设置去雾模块的处理方式，去雾、增强、直方图均衡三者互斥。
+    fn set_dhz_function(&self, func: DehazeFunction) -> XCamResult<()>;
+
+    /// 获取手动去雾强度。
+    fn get_dhz_strength(&self) -> XCamResult<u32>;
+
+    /// 设置手动去雾强度，范围：[0,100]，超出范围时返回参数错误。
+    fn set_dhz_strength(&self, level: u32) -> XCamResult<()>;
+
+    /// 获取手动增强强度。
+    fn get_enhance_strength(&self) -> XCamResult<u32>;
+
+    /// 设置手动增强强度，范围：[0,100]，超出范围时返回参数错误。
+    fn set_enhance_strength(&self, level: u32) -> XCamResult<()>;
+
+    /// 获取手动模式下的大气光参数。
+    fn get_dhz_air_light(&self) -> XCamResult<DehazeAirLight>;
+
+    /// 设置手动模式下的大气光参数，参数超出取值范围时返回参数错误。
+    fn set_dhz_air_light(&self, air: DehazeAirLight) -> XCamResult<()>;
+
+    /// 获取手动模式下的透射率参数。
+    fn get_dhz_transmission(&self) -> XCamResult<DehazeTransmission>;
+
+    /// 设置手动模式下的透射率参数，参数超出取值范围时返回参数错误。
+    fn set_dhz_transmission(&self, tmax: DehazeTransmission) -> XCamResult<()>;
 }
 
 impl Defog for Context {
     fn enable_dhz(&self) -> XCamResult<()> {
         #[cfg(feature = "v2_0")]
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi2_enableDhz(self.internal.as_ptr())).ok()
+            XCamError::from(ffi::rk_aiq_uapi2_enableDhz(self.lock().as_ptr())).ok()
         }
         // #[cfg(feature = "v3_0")]
         #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setDehazeEnable(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 true,
             ))
             .ok()
@@ -34,40 +124,46 @@ impl Defog for Context {
     fn disable_dhz(&self) -> XCamResult<()> {
         #[cfg(feature = "v2_0")]
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi2_disableDhz(self.internal.as_ptr())).ok()
+            XCamError::from(ffi::rk_aiq_uapi2_disableDhz(self.lock().as_ptr())).ok()
         }
         // #[cfg(feature = "v3_0")]
         #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setDehazeEnable(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 false,
             ))
             .ok()
         }
     }
 
+    #[cfg(feature = "v2_0")]
     fn get_dhz_mode(&self) -> XCamResult<OpMode> {
-        #[cfg(feature = "v2_0")]
         unsafe {
             let mut mode: ffi::opMode_t = ffi::opMode_t::OP_AUTO;
             XCamError::from(ffi::rk_aiq_uapi2_getDhzMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut mode,
             ))
             .ok()
             .map(|_| mode.into())
         }
-        // #[cfg(feature = "v3_0")]
-        #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
-        Ok(OpMode::Auto)
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn get_dhz_mode(&self) -> XCamResult<OpMode> {
+        use ffi::dehaze_api_mode_t::*;
+        get_dehaze_attrib(self).map(|attr| match attr.mode {
+            DEHAZE_API_AUTO => OpMode::Auto,
+            DEHAZE_API_MANUAL => OpMode::Manual,
+        })
     }
 
     #[cfg(feature = "v2_0")]
     fn set_dhz_mode(&self, mode: OpMode) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setDhzMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
@@ -75,7 +171,269 @@ impl Defog for Context {
     }
 
     #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
-    fn set_dhz_mode(&self, _mode: OpMode) -> XCamResult<()> {
-        Ok(())
+    fn set_dhz_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        use ffi::dehaze_api_mode_t::*;
+        let mut attr = get_dehaze_attrib(self)?;
+        attr.mode = match mode {
+            OpMode::Auto => DEHAZE_API_AUTO,
+            OpMode::Manual => DEHAZE_API_MANUAL,
+            _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+        };
+        set_dehaze_attrib(self, &attr)
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn get_dhz_function(&self) -> XCamResult<DehazeFunction> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn get_dhz_function(&self) -> XCamResult<DehazeFunction> {
+        let attr = get_dehaze_attrib(self)?;
+        let m = &attr.stManual;
+        if m.hist_setting.en {
+            Ok(DehazeFunction::HistEqualization)
+        } else if m.enhance_setting.en {
+            Ok(DehazeFunction::Enhance)
+        } else {
+            Ok(DehazeFunction::Dehaze)
+        }
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn set_dhz_function(&self, _func: DehazeFunction) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn set_dhz_function(&self, func: DehazeFunction) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = get_dehaze_attrib(self)?;
+        let m = &mut attr.stManual;
+        m.dehaze_setting.en = func == DehazeFunction::Dehaze;
+        m.enhance_setting.en = func == DehazeFunction::Enhance;
+        m.hist_setting.en = func == DehazeFunction::HistEqualization;
+        set_dehaze_attrib(self, &attr)
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn get_dhz_strength(&self) -> XCamResult<u32> {
+        let mut on: bool = false;
+        let mut level: u32 = 0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_getMDhzStrth(
+                self.lock().as_ptr(),
+                &mut on,
+                &mut level,
+            ))
+            .ok()
+            .map(|_| level)
+        }
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn get_dhz_strength(&self) -> XCamResult<u32> {
+        let mut level: u32 = 0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_getMDehazeStrth(
+                self.lock().as_ptr(),
+                &mut level,
+            ))
+            .ok()
+            .map(|_| level)
+        }
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn set_dhz_strength(&self, level: u32) -> XCamResult<()> {
+        check_range(level, 0, 100)?;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_setMDhzStrth(
+                self.lock().as_ptr(),
+                true,
+                level,
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn set_dhz_strength(&self, level: u32) -> XCamResult<()> {
+        check_range(level, 0, 100)?;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_setMDehazeStrth(
+                self.lock().as_ptr(),
+                level,
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn get_enhance_strength(&self) -> XCamResult<u32> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn get_enhance_strength(&self) -> XCamResult<u32> {
+        let mut level: u32 = 0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_getMEnhanceStrth(
+                self.lock().as_ptr(),
+                &mut level,
+            ))
+            .ok()
+            .map(|_| level)
+        }
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn set_enhance_strength(&self, _level: u32) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn set_enhance_strength(&self, level: u32) -> XCamResult<()> {
+        check_range(level, 0, 100)?;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_setMEnhanceStrth(
+                self.lock().as_ptr(),
+                level,
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn get_dhz_air_light(&self) -> XCamResult<DehazeAirLight> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn get_dhz_air_light(&self) -> XCamResult<DehazeAirLight> {
+        let attr = get_dehaze_attrib(self)?;
+        let d = &attr.stManual.dehaze_setting;
+        Ok(DehazeAirLight {
+            min: d.DehazeData.air_min,
+            max: d.DehazeData.air_max,
+            sigma: d.air_sigma,
+        })
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn set_dhz_air_light(&self, _air: DehazeAirLight) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn set_dhz_air_light(&self, air: DehazeAirLight) -> XCamResult<()> {
+        air.validate()?;
+        let _lock = self.lock();
+        let mut attr = get_dehaze_attrib(self)?;
+        let d = &mut attr.stManual.dehaze_setting;
+        d.DehazeData.air_min = air.min;
+        d.DehazeData.air_max = air.max;
+        d.air_sigma = air.sigma;
+        set_dehaze_attrib(self, &attr)
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn get_dhz_transmission(&self) -> XCamResult<DehazeTransmission> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn get_dhz_transmission(&self) -> XCamResult<DehazeTransmission> {
+        let attr = get_dehaze_attrib(self)?;
+        let d = &attr.stManual.dehaze_setting;
+        Ok(DehazeTransmission {
+            base: d.DehazeData.tmax_base,
+            offset: d.DehazeData.tmax_off,
+            max: d.DehazeData.tmax_max,
+            sigma: d.tmax_sigma,
+        })
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn set_dhz_transmission(&self, _tmax: DehazeTransmission) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn set_dhz_transmission(&self, tmax: DehazeTransmission) -> XCamResult<()> {
+        tmax.validate()?;
+        let _lock = self.lock();
+        let mut attr = get_dehaze_attrib(self)?;
+        let d = &mut attr.stManual.dehaze_setting;
+        d.DehazeData.tmax_base = tmax.base;
+        d.DehazeData.tmax_off = tmax.offset;
+        d.DehazeData.tmax_max = tmax.max;
+        d.tmax_sigma = tmax.sigma;
+        set_dehaze_attrib(self, &attr)
+    }
+}
+
+#[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+fn get_dehaze_attrib(ctx: &Context) -> XCamResult<DehazeAttr> {
+    let mut attr = DehazeAttr::default();
+    unsafe {
+        XCamError::from(ffi::rk_aiq_user_api2_adehaze_v11_getSwAttrib(
+            ctx.lock().as_ptr(),
+            &mut attr,
+        ))
+        .ok()
+        .map(|_| attr)
+    }
+}
+
+#[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+fn set_dehaze_attrib(ctx: &Context, attr: &DehazeAttr) -> XCamResult<()> {
+    unsafe {
+        XCamError::from(ffi::rk_aiq_user_api2_adehaze_v11_setSwAttrib(
+            ctx.lock().as_ptr(),
+            attr,
+        ))
+        .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let air = DehazeAirLight {
+            min: 64.0,
+            max: 960.0,
+            sigma: 8.0,
+        };
+        assert!(air.validate().is_ok());
+        assert!(DehazeAirLight { max: 32.0, ..air }.validate().is_err());
+        assert!(DehazeAirLight { max: 1025.0, ..air }.validate().is_err());
+        assert!(DehazeAirLight {
+            sigma: 256.0,
+            ..air
+        }
+        .validate()
+        .is_err());
+
+        let tmax = DehazeTransmission {
+            base: 125.0,
+            offset: 1024.0,
+            max: 1000.0,
+            sigma: 0.0,
+        };
+        assert!(tmax.validate().is_ok());
+        assert!(DehazeTransmission { base: -1.0, ..tmax }
+            .validate()
+            .is_err());
+        assert!(DehazeTransmission {
+            sigma: f32::NAN,
+            ..tmax
+        }
+        .validate()
+        .is_err());
     }
 }
@@ -0,0 +1,293 @@
+//! Degamma
+//!
+//! Degamma 模块将传感器输出的压扩（companded）数据还原为线性数据，
+//! 每个颜色通道使用独立的分段曲线。
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::types::{CurveError, DegammaApiManual, DegammaAttr, OpMode, XCamResult};
+
+/// Degamma 曲线的节点个数。
+pub const DEGAMMA_CURVE_KNOTS: usize = 17;
+
+/// Degamma 曲线输入、输出的最大值（12 位）。
+pub const DEGAMMA_CURVE_MAX: i32 = 4095;
+
+/// 一个代表 Degamma 曲线的类型。
+///
+/// 所有通道共用 X 轴节点，X 轴相邻节点的间距必须为 2 的整数次幂。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DegammaCurve {
+    /// X 轴节点，须从 0 开始严格递增。
+    pub x: [i32; DEGAMMA_CURVE_KNOTS],
+    /// R 通道输出值，须单调不减。
+    pub r: [i32; DEGAMMA_CURVE_KNOTS],
+    /// G 通道输出值，须单调不减。
+    pub g: [i32; DEGAMMA_CURVE_KNOTS],
+    /// B 通道输出值，须单调不减。
+    pub b: [i32; DEGAMMA_CURVE_KNOTS],
+}
+
+impl DegammaCurve {
+    /// 从切片创建一条 Degamma 曲线并进行校验。
+    pub fn from_slices(x: &[i32], r: &[i32], g: &[i32], b: &[i32]) -> Result<Self, CurveError> {
+        let curve = Self {
+            x: to_knots(x)?,
+            r: to_knots(r)?,
+            g: to_knots(g)?,
+            b: to_knots(b)?,
+        };
+        curve.validate()?;
+        Ok(curve)
+    }
+
+    /// 创建一条三通道共用同一输出的 Degamma 曲线并进行校验。
+    pub fn from_luma(x: &[i32], y: &[i32]) -> Result<Self, CurveError> {
+        Self::from_slices(x, y, y, y)
+    }
+
+    /// 校验曲线的节点布局及单调性。
+    pub fn validate(&self) -> Result<(), CurveError> {
+        if self.x[0] != 0 {
+            return Err(CurveError::OutOfRange);
+        }
+        for w in self.x.windows(2) {
+            let dx = w[1] - w[0];
+            if dx <= 0 {
+                return Err(CurveError::NotMonotonic);
+            }
+            if !(dx as u32).is_power_of_two() {
+                return Err(CurveError::BadSegment);
+            }
+        }
+        if self.x[DEGAMMA_CURVE_KNOTS - 1] > DEGAMMA_CURVE_MAX + 1 {
+            return Err(CurveError::OutOfRange);
+        }
+        for ch in [&self.r, &self.g, &self.b] {
+            if ch.iter().any(|&y| !(0..=DEGAMMA_CURVE_MAX).contains(&y)) {
+                return Err(CurveError::OutOfRange);
+            }
+            if ch.windows(2).any(|w| w[1] < w[0]) {
+                return Err(CurveError::NotMonotonic);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&DegammaApiManual> for DegammaCurve {
+    fn from(val: &DegammaApiManual) -> Self {
+        Self {
+            x: val.X_axis,
+            r: val.curve_R,
+            g: val.curve_G,
+            b: val.curve_B,
+        }
+    }
+}
+
+fn to_knots(val: &[i32]) -> Result<[i32; DEGAMMA_CURVE_KNOTS], CurveError> {
+    val.try_into().map_err(|_| CurveError::Length)
+}
+
+/// 一个描述 Degamma 控制的契定。
+pub trait Degamma {
+    /// 获取 Degamma 属性。
+    fn get_degamma_attrib(&self) -> XCamResult<DegammaAttr>;
+
+    /// 设置 Degamma 属性。
+    fn set_degamma_attrib<T: Into<DegammaAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 获取 Degamma 工作模式。
+    fn get_degamma_mode(&self) -> XCamResult<OpMode>;
+
+    /// 设置 Degamma 工作模式，仅支持 `Auto` 及 `Manual`。
+    fn set_degamma_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 开启 Degamma。
+    fn enable_degamma(&self) -> XCamResult<()>;
+
+    /// 关闭 Degamma。
+    fn disable_degamma(&self) -> XCamResult<()>;
+
+    /// 获取手动模式下的 Degamma 曲线。
+    fn get_degamma_curve(&self) -> XCamResult<DegammaCurve>;
+
+    /// 校验并上传手动模式下的 Degamma 曲线，同时切换至手动模式。
+    fn set_degamma_curve(&self, curve: &DegammaCurve) -> XCamResult<()>;
+}
+
+impl Degamma for Context {
+    fn get_degamma_attrib(&self) -> XCamResult<DegammaAttr> {
+        let mut attr = DegammaAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_adegamma_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    fn set_degamma_attrib<T: Into<DegammaAttr>>(&self, attr: T) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_adegamma_SetAttrib(
+                self.lock().as_ptr(),
+                attr.into(),
+            ))
+            .ok()
+        }
+    }
+
+    fn get_degamma_mode(&self) -> XCamResult<OpMode> {
+        use ffi::degamma_op_mode_t::*;
+        self.get_degamma_attrib().map(|attr| match attr.mode {
+            DEGAMMA_MODE_AUTO => OpMode::Auto,
+            DEGAMMA_MODE_MANUAL => OpMode::Manual,
+            _ => OpMode::Invalid,
+        })
+    }
+
+    fn set_degamma_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        use ffi::degamma_op_mode_t::*;
+        let mut attr = self.get_degamma_attrib()?;
+        attr.mode = match mode {
+            OpMode::Auto => DEGAMMA_MODE_AUTO,
+            OpMode::Manual => DEGAMMA_MODE_MANUAL,
+            _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+        };
+        self.set_degamma_attrib(attr)
+    }
+
+    fn enable_degamma(&self) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_degamma_attrib()?;
+        attr.stManual.en = true;
+        self.set_degamma_attrib(attr)
+    }
+
+    fn disable_degamma(&self) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_degamma_attrib()?;
+        attr.stManual.en = false;
+        self.set_degamma_attrib(attr)
+    }
+
+    fn get_degamma_curve(&self) -> XCamResult<DegammaCurve> {
+        self.get_degamma_attrib()
+            .map(|attr| DegammaCurve::from(&attr.stManual))
+    }
+
+    fn set_degamma_curve(&self, curve: &DegammaCurve) -> XCamResult<()> {
+        curve
+            .validate()
+            .map_err(|_| XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM))?;
+        let _lock = self.lock();
+        let attr = self.get_degamma_attrib()?;
+        let attr = DegammaAttrBuilder::with_manual_curve(curve).build_from(attr);
+        self.set_degamma_attrib(attr)
+    }
+}
+
+/// 一个代表 Degamma 属性构建器的类型。
+pub struct DegammaAttrBuilder {
+    mode: Option<ffi::degamma_op_mode_t>,
+    manual: Option<DegammaApiManual>,
+}
+
+impl DegammaAttrBuilder {
+    /// 创建一个 Degamma 属性构建器实例。
+    pub fn new() -> Self {
+        Self {
+            mode: None,
+            manual: None,
+        }
+    }
+
+    /// 创建一个用于配置手动曲线的 Degamma 属性构建器。
+    ///
+    /// 调用者应事先通过 [`DegammaCurve::validate`] 校验曲线。
+    pub fn with_manual_curve(curve: &DegammaCurve) -> Self {
+        Self {
+            mode: Some(ffi::degamma_op_mode_t::DEGAMMA_MODE_MANUAL),
+            manual: Some(DegammaApiManual {
+                en: true,
+                X_axis: curve.x,
+                curve_R: curve.r,
+                curve_G: curve.g,
+                curve_B: curve.b,
+            }),
+        }
+    }
+
+    /// 返回 Degamma 属性，未设置的字段取默认值。
+    pub fn build(self) -> DegammaAttr {
+        self.build_from(DegammaAttr::default())
+    }
+
+    /// 在已有属性的基础上返回 Degamma 属性，未设置的字段保持不变。
+    pub fn build_from(self, mut attr: DegammaAttr) -> DegammaAttr {
+        if let Some(ref v) = self.mode {
+            attr.mode = *v;
+        }
+        if let Some(ref v) = self.manual {
+            attr.stManual = *v;
+        }
+        attr
+    }
+}
+
+impl Default for DegammaAttrBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear() -> DegammaCurve {
+        let x: Vec<i32> = (0..DEGAMMA_CURVE_KNOTS as i32).map(|i| i * 256).collect();
+        let y: Vec<i32> = x.iter().map(|&v| v.min(DEGAMMA_CURVE_MAX)).collect();
+        DegammaCurve::from_luma(&x, &y).unwrap()
+    }
+
+    #[test]
+    fn test_curve_validation() {
+        let curve = linear();
+        assert_eq!(curve.validate(), Ok(()));
+        assert_eq!(
+            DegammaCurve::from_luma(&curve.x[1..], &curve.r[1..]),
+            Err(CurveError::Length)
+        );
+
+        let mut c = curve;
+        c.x[0] = 1;
+        assert_eq!(c.validate(), Err(CurveError::OutOfRange));
+
+        let mut c = curve;
+        c.x.swap(3, 4);
+        assert_eq!(c.validate(), Err(CurveError::NotMonotonic));
+
+        let mut c = curve;
+        c.x[4] += 64;
+        assert_eq!(c.validate(), Err(CurveError::BadSegment));
+
+        let mut c = curve;
+        c.x[DEGAMMA_CURVE_KNOTS - 1] += 256;
+        c.x[DEGAMMA_CURVE_KNOTS - 2] += 256;
+        assert_eq!(c.validate(), Err(CurveError::OutOfRange));
+
+        let mut c = curve;
+        c.g[8] = DEGAMMA_CURVE_MAX + 1;
+        assert_eq!(c.validate(), Err(CurveError::OutOfRange));
+
+        let mut c = curve;
+        c.b.swap(5, 6);
+        assert_eq!(c.validate(), Err(CurveError::NotMonotonic));
+    }
+}
@@ -0,0 +1,344 @@
+//! 动态范围压缩
+//!
+//! DRC 模块通过全局与局部色调映射将高动态范围数据压缩至输出位宽，
+//! 在保留亮区细节的同时提升暗区亮度。
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::nr::check_range;
+use super::types::{DrcAttr, DrcManualAttr, OpMode, XCamResult};
+
+/// DRC 压缩曲线的节点个数。
+pub const DRC_CURVE_LEN: usize = 17;
+
+/// 一个描述 DRC 压缩曲线模式的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrcCompressMode {
+    /// 由算法根据场景自动生成压缩曲线。
+    Auto,
+    /// 使用用户提供的压缩曲线。
+    Manual,
+}
+
+impl From<ffi::CompressMode_t> for DrcCompressMode {
+    fn from(val: ffi::CompressMode_t) -> Self {
+        use ffi::CompressMode_t::*;
+        match val {
+            COMPRESS_AUTO => DrcCompressMode::Auto,
+            COMPRESS_MANUAL => DrcCompressMode::Manual,
+        }
+    }
+}
+
+impl From<DrcCompressMode> for ffi::CompressMode_t {
+    fn from(val: DrcCompressMode) -> Self {
+        use ffi::CompressMode_t::*;
+        match val {
+            DrcCompressMode::Auto => COMPRESS_AUTO,
+            DrcCompressMode::Manual => COMPRESS_MANUAL,
+        }
+    }
+}
+
+/// 一个代表 DRC 增益参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DrcGain {
+    /// DRC 增益，取值范围 [1,8]。
+    pub gain: f32,
+    /// 增益作用系数，取值范围 [0,1]。
+    pub alpha: f32,
+    /// 增益钳位，取值范围 [0,64]。
+    pub clip: f32,
+}
+
+impl DrcGain {
+    /// 校验各参数是否在取值范围内。
+    pub fn validate(&self) -> XCamResult<()> {
+        check_range(self.gain, 1.0, 8.0)?;
+        check_range(self.alpha, 0.0, 1.0)?;
+        check_range(self.clip, 0.0, 64.0)
+    }
+}
+
+/// 一个代表 DRC 局部色调映射强度的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DrcLocalStrength {
+    /// 局部权重，取值范围 [0,1]，越大局部对比度越强。
+    pub local_weight: f32,
+    /// 全局对比度，取值范围 [0,1]。
+    pub global_contrast: f32,
+    /// 低亮区域对比度，取值范围 [0,1]。
+    pub low_light_contrast: f32,
+}
+
+impl DrcLocalStrength {
+    /// 校验各参数是否在取值范围内。
+    pub fn validate(&self) -> XCamResult<()> {
+        check_range(self.local_weight, 0.0, 1.0)?;
+        check_range(self.global_contrast, 0.0, 1.0)?;
+        check_range(self.low_light_contrast, 0.0, 1.0)
+    }
+}
+
+/// 一个描述动态范围压缩控制的契定。
+pub trait DynamicRangeCompression {
+    /// 获取 DRC 属性。
+    fn get_drc_attrib(&self) -> XCamResult<DrcAttr>;
+
+    /// 设置 DRC 属性。
+    fn set_drc_attrib<T: Into<DrcAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 开启 DRC。
+    fn enable_drc(&self) -> XCamResult<()>;
+
+    /// 关闭 DRC。
+    fn disable_drc(&self) -> XCamResult<()>;
+
+    /// 获取 DRC 工作模式。
+    fn get_drc_mode(&self) -> XCamResult<OpMode>;
+
+    /// 设置 DRC 工作模式，仅支持 `Auto` 及 `Manual`。
+    fn set_drc_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 获取手动模式参数。
+    fn get_drc_manual(&self) -> XCamResult<DrcManualAttr>;
+
+    /// 设置手动模式参数，并切换至手动模式。
+    fn set_drc_manual<T: Into<DrcManualAttr>>(&self, manual: T) -> XCamResult<()>;
+
+    /// 设置手动模式下的全局增益（全局色调映射强度）。
+    ///
+    /// 参数超出 [`DrcGain`] 所述取值范围时返回参数错误。
+    fn set_drc_gain(&self, gain: DrcGain) -> XCamResult<()>;
+
+    /// 设置手动模式下的局部色调映射强度。
+    ///
+    /// 参数超出 [`DrcLocalStrength`] 所述取值范围时返回参数错误。
+    fn set_drc_local_strength(&self, strength: DrcLocalStrength) -> XCamResult<()>;
+
+    /// 设置手动模式下的高光压制强度，取值范围 [0,1]。
+    fn set_drc_hilight_strength(&self, strength: f32) -> XCamResult<()>;
+
+    /// 设置手动模式下的亮区压缩曲线。
+    ///
+    /// `None` 表示由算法自动生成压缩曲线。
+    fn set_drc_compress_curve(&self, curve: Option<&[u16; DRC_CURVE_LEN]>) -> XCamResult<()>;
+
+    /// 设置手动模式下按亮度分段的暗区增益曲线。
+    fn set_drc_scale_y(&self, curve: &[u16; DRC_CURVE_LEN]) -> XCamResult<()>;
+
+    /// 获取当前实际生效的 DRC 增益。
+    fn get_applied_drc_gain(&self) -> XCamResult<DrcGain>;
+}
+
+impl DynamicRangeCompression for Context {
+    #[cfg(feature = "isp_hw_v21")]
+    fn get_drc_attrib(&self) -> XCamResult<DrcAttr> {
+        let mut attr = DrcAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_adrc_v21_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    #[cfg(not(feature = "isp_hw_v21"))]
+    fn get_drc_attrib(&self) -> XCamResult<DrcAttr> {
+        let mut attr = DrcAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_adrc_v30_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    #[cfg(feature = "isp_hw_v21")]
+    fn set_drc_attrib<T: Into<DrcAttr>>(&self, attr: T) -> XCamResult<()> {
+        let attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_adrc_v21_SetAttrib(
+                self.lock().as_ptr(),
+                &attr,
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(not(feature = "isp_hw_v21"))]
+    fn set_drc_attrib<T: Into<DrcAttr>>(&self, attr: T) -> XCamResult<()> {
+        let attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_adrc_v30_SetAttrib(
+                self.lock().as_ptr(),
+                &attr,
+            ))
+            .ok()
+        }
+    }
+
+    fn enable_drc(&self) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_drc_attrib()?;
+        attr.stAuto.DrcTuningPara.Enable = true;
+        attr.stManual.Enable = true;
+        self.set_drc_attrib(attr)
+    }
+
+    fn disable_drc(&self) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_drc_attrib()?;
+        attr.stAuto.DrcTuningPara.Enable = false;
+        attr.stManual.Enable = false;
+        self.set_drc_attrib(attr)
+    }
+
+    fn get_drc_mode(&self) -> XCamResult<OpMode> {
+        self.get_drc_attrib().map(|attr| attr.opMode.into())
+    }
+
+    fn set_drc_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_drc_attrib()?;
+        attr.opMode = drc_op_mode(mode)?;
+        self.set_drc_attrib(attr)
+    }
+
+    fn get_drc_manual(&self) -> XCamResult<DrcManualAttr> {
+        self.get_drc_attrib().map(|attr| attr.stManual)
+    }
+
+    fn set_drc_manual<T: Into<DrcManualAttr>>(&self, manual: T) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_drc_attrib()?;
+        attr.opMode = ffi::drc_OpMode_t::DRC_OPMODE_MANUAL;
+        attr.stManual = manual.into();
+        self.set_drc_attrib(attr)
+    }
+
+    fn set_drc_gain(&self, gain: DrcGain) -> XCamResult<()> {
+        gain.validate()?;
+        let _lock = self.lock();
+        let mut manual = self.get_drc_manual()?;
+        manual.DrcGain.DrcGain = gain.gain;
+        manual.DrcGain.Alpha = gain.alpha;
+        manual.DrcGain.Clip = gain.clip;
+        self.set_drc_manual(manual)
+    }
+
+    fn set_drc_local_strength(&self, strength: DrcLocalStrength) -> XCamResult<()> {
+        strength.validate()?;
+        let _lock = self.lock();
+        let mut manual = self.get_drc_manual()?;
+        let local = &mut manual.LocalSetting.LocalData;
+        local.LocalWeit = strength.local_weight;
+        local.GlobalContrast = strength.global_contrast;
+        local.LoLitContrast = strength.low_light_contrast;
+        self.set_drc_manual(manual)
+    }
+
+    fn set_drc_hilight_strength(&self, strength: f32) -> XCamResult<()> {
+        check_range(strength, 0.0, 1.0)?;
+        let _lock = self.lock();
+        let mut manual = self.get_drc_manual()?;
+        manual.HiLight.Strength = strength;
+        self.set_drc_manual(manual)
+    }
+
+    fn set_drc_compress_curve(&self, curve: Option<&[u16; DRC_CURVE_LEN]>) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut manual = self.get_drc_manual()?;
+        match curve {
+            Some(curve) => {
+                manual.CompressSetting.Mode = DrcCompressMode::Manual.into();
+                manual.CompressSetting.Manual_curve = *curve;
+            }
+            None => {
+                manual.CompressSetting.Mode = DrcCompressMode::Auto.into();
+            }
+        }
+        self.set_drc_manual(manual)
+    }
+
+    fn set_drc_scale_y(&self, curve: &[u16; DRC_CURVE_LEN]) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut manual = self.get_drc_manual()?;
+        manual.Scale_y = *curve;
+        self.set_drc_manual(manual)
+    }
+
+    fn get_applied_drc_gain(&self) -> XCamResult<DrcGain> {
+        self.get_drc_attrib().map(|attr| {
+            let v = &attr.Info.ValidParams.DrcGain;
+            DrcGain {
+                gain: v.DrcGain,
+                alpha: v.Alpha,
+                clip: v.Clip,
+            }
+        })
+    }
+}
+
+impl From<ffi::drc_OpMode_t> for OpMode {
+    fn from(val: ffi::drc_OpMode_t) -> Self {
+        use ffi::drc_OpMode_t::*;
+        match val {
+            DRC_OPMODE_AUTO => OpMode::Auto,
+            DRC_OPMODE_MANUAL => OpMode::Manual,
+        }
+    }
+}
+
+fn drc_op_mode(mode: OpMode) -> XCamResult<ffi::drc_OpMode_t> {
+    use ffi::drc_OpMode_t::*;
+    match mode {
+        OpMode::Auto => Ok(DRC_OPMODE_AUTO),
+        OpMode::Manual => Ok(DRC_OPMODE_MANUAL),
+        _ => Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let gain = DrcGain {
+            gain: 4.0,
+            alpha: 0.5,
+            clip: 16.0,
+        };
+        assert!(gain.validate().is_ok());
+        assert!(DrcGain { gain: 0.5, ..gain }.validate().is_err());
+        assert!(DrcGain { alpha: 1.5, ..gain }.validate().is_err());
+        assert!(DrcGain { clip: 65.0, ..gain }.validate().is_err());
+        assert!(DrcGain {
+            gain: f32::NAN,
+            ..gain
+        }
+        .validate()
+        .is_err());
+
+        let local = DrcLocalStrength {
+            local_weight: 1.0,
+            global_contrast: 0.0,
+            low_light_contrast: 0.5,
+        };
+        assert!(local.validate().is_ok());
+        assert!(DrcLocalStrength {
+            global_contrast: -0.1,
+            ..local
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -3,11 +3,35 @@ use std::fmt;
 
 /// 一个描述摄像头访问错误代码的类型。
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub struct XCamError(ffi::XCamReturn);
+pub struct XCamError(Repr);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Repr {
+    Code(ffi::XCamReturn),
+    Unsupported,
+}
 
 impl XCamError {
+    /// 创建一个表示当前硬件或 AIQ 版本不支持该功能的错误。
+    pub const fn unsupported() -> Self {
+        Self(Repr::Unsupported)
+    }
+
+    /// 返回该错误是否表示功能不受支持。
+    pub fn is_unsupported(&self) -> bool {
+        self.0 == Repr::Unsupported
+    }
+
+    /// 返回 AIQ 接口返回的原始错误代码。
+    pub fn code(&self) -> Option<ffi::XCamReturn> {
+        match self.0 {
+            Repr::Code(code) => Some(code),
+            Repr::Unsupported => None,
+        }
+    }
+
     pub fn ok(self) -> Result<(), Self> {
-        if self.0 != ffi::XCamReturn::XCAM_RETURN_NO_ERROR {
+        if self.0 != Repr::Code(ffi::XCamReturn::XCAM_RETURN_NO_ERROR) {
             Err(self)
         } else {
             Ok(())
@@ -18,7 +42,11 @@ impl XCamError {
 impl fmt::Display for XCamError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use ffi::XCamReturn::*;
-        let desc = match self.0 {
+        let code = match self.0 {
+            Repr::Code(code) => code,
+            Repr::Unsupported => return write!(f, "Unsupported"),
+        };
+        let desc = match code {
             XCAM_RETURN_NO_ERROR => "No Error",
             XCAM_RETURN_BYPASS => "ByPass",
             XCAM_RETURN_ERROR_FAILED => "Failed",
@@ -59,13 +87,13 @@ impl From<i32> for XCamError {
             -21 => XCAM_RETURN_ERROR_OUTOFRANGE,
             _ => XCAM_RETURN_ERROR_UNKNOWN,
         };
-        Self(val)
+        Self(Repr::Code(val))
     }
 }
 
 impl From<ffi::XCamReturn> for XCamError {
     fn from(val: ffi::XCamReturn) -> Self {
-        Self(val)
+        Self(Repr::Code(val))
     }
 }
 
@@ -3,22 +3,222 @@
 //! 光学系统、电子扫描系统失真而引起的斜视畸变、枕形、桶形畸变等，都可能使图像产生几何特性失真。
 //! 图像的畸变矫正是以某种变换方式将畸变图像转换为理想图像的过程。
 //! 该模块对x和y方向的图像畸变进行校正。
+//!
+//! 与 [`LDCH`](super::ldch::LDCH) 不同，`rk_aiq_uapi_afec_int.h` 中的 FEC 属性不含网格文件字段，
+//! 鱼眼校正网格仅能由 IQ 文件中 FEC 标定项的 `meshfile` 指定，因此本模块不提供自定义网格加载。
+//! 如需更换网格，请修改 IQ 文件后重新初始化上下文。
 use super::context::Context;
 use super::error::XCamError;
 use super::ffi;
-use super::types::XCamResult;
+use super::types::{FecAttr, XCamResult};
+
+/// 一个描述畸变校正方向的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FecDirection {
+    /// 仅校正水平方向。
+    X,
+    /// 仅校正垂直方向。
+    Y,
+    /// 同时校正水平及垂直方向。
+    XY,
+}
+
+impl From<ffi::fec_correct_direction_t> for FecDirection {
+    fn from(val: ffi::fec_correct_direction_t) -> Self {
+        use ffi::fec_correct_direction_t::*;
+        match val {
+            FEC_CORRECT_DIRECTION_X => FecDirection::X,
+            FEC_CORRECT_DIRECTION_Y => FecDirection::Y,
+            FEC_CORRECT_DIRECTION_XY => FecDirection::XY,
+        }
+    }
+}
+
+impl From<FecDirection> for ffi::fec_correct_direction_t {
+    fn from(val: FecDirection) -> Self {
+        use ffi::fec_correct_direction_t::*;
+        match val {
+            FecDirection::X => FEC_CORRECT_DIRECTION_X,
+            FecDirection::Y => FEC_CORRECT_DIRECTION_Y,
+            FecDirection::XY => FEC_CORRECT_DIRECTION_XY,
+        }
+    }
+}
+
+/// 一个描述校正后图像背景填充方式的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FecFillMode {
+    /// 压缩图像以保留全部视场，边缘空白区域以黑色填充。
+    KeepFov,
+    /// 保持宽高比并裁剪边缘，不产生填充区域。
+    Crop,
+    /// 拉伸图像以保留全部视场，不产生填充区域。
+    Stretch,
+}
+
+impl From<ffi::fec_correct_mode_t> for FecFillMode {
+    fn from(val: ffi::fec_correct_mode_t) -> Self {
+        use ffi::fec_correct_mode_t::*;
+        match val {
+            FEC_COMPRES_IMAGE_KEEP_FOV => FecFillMode::KeepFov,
+            FEC_KEEP_ASPECT_RATIO_REDUCE_FOV => FecFillMode::Crop,
+            FEC_ALTER_ASPECT_RATIO_KEEP_FOV => FecFillMode::Stretch,
+        }
+    }
+}
+
+impl From<FecFillMode> for ffi::fec_correct_mode_t {
+    fn from(val: FecFillMode) -> Self {
+        use ffi::fec_correct_mode_t::*;
+        match val {
+            FecFillMode::KeepFov => FEC_COMPRES_IMAGE_KEEP_FOV,
+            FecFillMode::Crop => FEC_KEEP_ASPECT_RATIO_REDUCE_FOV,
+            FecFillMode::Stretch => FEC_ALTER_ASPECT_RATIO_KEEP_FOV,
+        }
+    }
+}
+
+/// 一个代表 FEC 参数的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FecParams {
+    /// 是否开启。
+    pub enable: bool,
+    /// 是否旁路，旁路时输出未经校正的图像。
+    pub bypass: bool,
+    /// 校正强度，0 为不校正，255 为最大强度。
+    pub level: u8,
+    /// 校正方向。
+    pub direction: FecDirection,
+    /// 背景填充方式。
+    pub fill_mode: FecFillMode,
+}
+
+impl From<&FecAttr> for FecParams {
+    fn from(val: &FecAttr) -> Self {
+        Self {
+            enable: val.en != 0,
+            bypass: val.bypass != 0,
+            level: val.correct_level.clamp(0, u8::MAX as _) as u8,
+            direction: val.direction.into(),
+            fill_mode: val.mode.into(),
+        }
+    }
+}
 
 pub trait FEC {
     fn enable_fec(&self) -> XCamResult<()>;
     fn disable_fec(&self) -> XCamResult<()>;
+
+    /// 获取 FEC 属性。
+    fn get_fec_attrib(&self) -> XCamResult<FecAttr>;
+
+    /// 设置 FEC 属性。
+    fn set_fec_attrib<T: Into<FecAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 获取类型化的 FEC 参数。
+    fn get_fec_params(&self) -> XCamResult<FecParams>;
+
+    /// 设置 FEC 参数。
+    fn set_fec_params(&self, params: &FecParams) -> XCamResult<()>;
+
+    /// 获取校正强度。
+    fn get_fec_level(&self) -> XCamResult<u8>;
+
+    /// 设置校正强度，0 为不校正，255 为最大强度。
+    fn set_fec_level(&self, level: u8) -> XCamResult<()>;
+
+    /// 获取校正方向。
+    fn get_fec_direction(&self) -> XCamResult<FecDirection>;
+
+    /// 设置校正方向。
+    fn set_fec_direction(&self, direction: FecDirection) -> XCamResult<()>;
+
+    /// 获取背景填充方式。
+    fn get_fec_fill_mode(&self) -> XCamResult<FecFillMode>;
+
+    /// 设置背景填充方式。
+    fn set_fec_fill_mode(&self, mode: FecFillMode) -> XCamResult<()>;
 }
 
 impl FEC for Context {
     fn enable_fec(&self) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi2_setFecEn(self.internal.as_ptr(), true)).ok() }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi2_setFecEn(self.lock().as_ptr(), true)).ok() }
     }
 
     fn disable_fec(&self) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi2_setFecEn(self.internal.as_ptr(), false)).ok() }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi2_setFecEn(self.lock().as_ptr(), false)).ok() }
+    }
+
+    fn get_fec_attrib(&self) -> XCamResult<FecAttr> {
+        let mut attr = FecAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_afec_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    fn set_fec_attrib<T: Into<FecAttr>>(&self, attr: T) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_afec_SetAttrib(
+                self.lock().as_ptr(),
+                attr.into(),
+            ))
+            .ok()
+        }
+    }
+
+    fn get_fec_params(&self) -> XCamResult<FecParams> {
+        self.get_fec_attrib().map(|attr| FecParams::from(&attr))
+    }
+
+    fn set_fec_params(&self, params: &FecParams) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_fec_attrib()?;
+        attr.en = params.enable as _;
+        attr.bypass = params.bypass as _;
+        attr.correct_level = params.level as _;
+        attr.direction = params.direction.into();
+        attr.mode = params.fill_mode.into();
+        self.set_fec_attrib(attr)
+    }
+
+    fn get_fec_level(&self) -> XCamResult<u8> {
+        self.get_fec_params().map(|p| p.level)
+    }
+
+    fn set_fec_level(&self, level: u8) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_fec_attrib()?;
+        attr.correct_level = level as _;
+        self.set_fec_attrib(attr)
+    }
+
+    fn get_fec_direction(&self) -> XCamResult<FecDirection> {
+        self.get_fec_attrib().map(|attr| attr.direction.into())
+    }
+
+    fn set_fec_direction(&self, direction: FecDirection) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_fec_attrib()?;
+        attr.direction = direction.into();
+        self.set_fec_attrib(attr)
+    }
+
+    fn get_fec_fill_mode(&self) -> XCamResult<FecFillMode> {
+        self.get_fec_attrib().map(|attr| attr.mode.into())
+    }
+
+    fn set_fec_fill_mode(&self, mode: FecFillMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_fec_attrib()?;
+        attr.mode = mode.into();
+        self.set_fec_attrib(attr)
     }
 }
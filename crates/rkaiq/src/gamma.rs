@@ -22,9 +22,330 @@ use super::types::GammaApiManualV30;
     )
 ))]
 use super::types::GammaCurveType;
+use super::types::{CurveError, GammaAttr, GammaCaliDb, GammaMode, XCamResult};
 #[cfg(feature = "v2_0")]
 use super::types::{GammaApiManual, GammaCurveUsrDefine1Para, GammaCurveUsrDefine2Para};
-use super::types::{GammaAttr, GammaCaliDb, GammaMode, XCamResult};
+
+/// Gamma 曲线的节点个数。
+#[cfg(not(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v30"
+)))]
+pub const GAMMA_CURVE_KNOTS: usize = 45;
+
+/// Gamma 曲线的节点个数。
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v30"
+))]
+pub const GAMMA_CURVE_KNOTS: usize = 49;
+
+/// Gamma 曲线输入、输出的最大值（12 位）。
+pub const GAMMA_CURVE_MAX: u16 = 4095;
+
+/// 对数分段下的 X 轴节点。
+#[cfg(not(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v30"
+)))]
+const GAMMA_LOG_X_AXIS: [u16; GAMMA_CURVE_KNOTS] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 10, 12, 14, 16, 20, 24, 28, 32, 40, 48, 56, 64, 80, 96, 112, 128,
+    160, 192, 224, 256, 320, 384, 448, 512, 640, 768, 896, 1024, 1280, 1536, 1792, 2048, 2560,
+    3072, 3584, 4095,
+];
+
+/// 对数分段下的 X 轴节点。
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v30"
+))]
+const GAMMA_LOG_X_AXIS: [u16; GAMMA_CURVE_KNOTS] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 10, 12, 14, 16, 20, 24, 28, 32, 40, 48, 56, 64, 80, 96, 112, 128,
+    160, 192, 224, 256, 320, 384, 448, 512, 640, 768, 896, 1024, 1280, 1536, 1792, 2048, 2304,
+    2560, 2816, 3072, 3328, 3584, 3840, 4095,
+];
+
+/// 一个描述 Gamma 曲线 X 轴分段方式的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GammaSegment {
+    /// 对数分段，暗区节点密集。
+    Log,
+    /// 等间距分段。
+    Equal,
+}
+
+impl GammaSegment {
+    /// 返回该分段方式下的 X 轴节点。
+    pub fn x_axis(self) -> [u16; GAMMA_CURVE_KNOTS] {
+        match self {
+            GammaSegment::Log => GAMMA_LOG_X_AXIS,
+            GammaSegment::Equal => {
+                let mut x = [0; GAMMA_CURVE_KNOTS];
+                let n = (GAMMA_CURVE_KNOTS - 1) as u32;
+                for (i, v) in x.iter_mut().enumerate() {
+                    *v = ((i as u32 * GAMMA_CURVE_MAX as u32 + n / 2) / n) as u16;
+                }
+                x
+            }
+        }
+    }
+}
+
+/// 一个代表 Gamma 曲线的类型。
+///
+/// 曲线按硬件的对数分段布局存储，X 轴节点见 [`GammaSegment::x_axis`]。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GammaCurve {
+    /// 各节点的输出值，须单调不减且不超过 [`GAMMA_CURVE_MAX`]。
+    #[cfg_attr(feature = "serde", serde(with = "crate::types::serde_array"))]
+    pub y: [u16; GAMMA_CURVE_KNOTS],
+}
+
+impl GammaCurve {
+    /// 从硬件布局的曲线表创建 Gamma 曲线并进行校验。
+    pub fn from_table(table: &[u16]) -> Result<Self, CurveError> {
+        let curve = Self {
+            y: table.try_into().map_err(|_| CurveError::Length)?,
+        };
+        curve.validate()?;
+        Ok(curve)
+    }
+
+    /// 按归一化传递函数创建 Gamma 曲线并进行校验。
+    ///
+    /// 传递函数的输入、输出均归一化至 [0,1]，输出将被限制在该范围内。
+    pub fn from_fn<F: Fn(f64) -> f64>(f: F) -> Result<Self, CurveError> {
+        let curve = Self::sample(f);
+        curve.validate()?;
+        Ok(curve)
+    }
+
+    /// 将任意长度的归一化曲线线性插值至硬件布局并进行校验。
+    ///
+    /// # Parameters
+    /// * `points` - 归一化 (x, y) 节点，至少两个，X 须严格递增且覆盖 [0,1]。
+    pub fn from_points(points: &[(f32, f32)]) -> Result<Self, CurveError> {
+        if points.len() < 2 {
+            return Err(CurveError::Length);
+        }
+        if points.windows(2).any(|w| w[1].0 <= w[0].0) {
+            return Err(CurveError::NotMonotonic);
+        }
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if first.0 > 0.0 || last.0 < 1.0 {
+            return Err(CurveError::OutOfRange);
+        }
+        if points.iter().any(|p| !(0.0..=1.0).contains(&p.1)) {
+            return Err(CurveError::OutOfRange);
+        }
+        Self::from_fn(|x| {
+            let x = x as f32;
+            let i = points
+                .partition_point(|p| p.0 <= x)
+                .clamp(1, points.len() - 1);
+            let (x0, y0) = points[i - 1];
+            let (x1, y1) = points[i];
+            (y0 + (y1 - y0) * (x - x0) / (x1 - x0)) as f64
+        })
+    }
+
+    /// 创建 sRGB（IEC 61966-2-1）编码曲线。
+    pub fn srgb() -> Self {
+        Self::sample(|x| {
+            if x <= 0.0031308 {
+                12.92 * x
+            } else {
+                1.055 * x.powf(1.0 / 2.4) - 0.055
+            }
+        })
+    }
+
+    /// 创建 BT.709 编码曲线。
+    pub fn bt709() -> Self {
+        Self::sample(|x| {
+            if x < 0.018 {
+                4.5 * x
+            } else {
+                1.099 * x.powf(0.45) - 0.099
+            }
+        })
+    }
+
+    /// 创建 HLG（ARIB STD-B67）编码曲线。
+    pub fn hlg() -> Self {
+        const A: f64 = 0.17883277;
+        const B: f64 = 0.28466892;
+        const C: f64 = 0.55991073;
+        Self::sample(|x| {
+            if x <= 1.0 / 12.0 {
+                (3.0 * x).sqrt()
+            } else {
+                A * (12.0 * x - B).ln() + C
+            }
+        })
+    }
+
+    /// 创建对数曲线 `y = ln(1 + a·x) / ln(1 + a)`。
+    ///
+    /// # Parameters
+    /// * `a` - 压缩系数，须大于 0，值越大暗区提升越明显。
+    pub fn log(a: f32) -> Result<Self, CurveError> {
+        if !(a.is_finite() && a > 0.0) {
+            return Err(CurveError::OutOfRange);
+        }
+        let a = a as f64;
+        Ok(Self::sample(|x| (1.0 + a * x).ln() / (1.0 + a).ln()))
+    }
+
+    /// 创建带线性暗区的幂函数曲线。
+    ///
+    /// 在 `toe` 以下为过原点的直线，以上为 `y = x^(1/gamma)`，两段在 `toe` 处连续。
+    ///
+    /// # Parameters
+    /// * `gamma` - Gamma 系数，须大于 0。
+    /// * `toe` - 线性段终点，取值范围 [0,1)，为 0 时即为纯幂函数。
+    pub fn power(gamma: f32, toe: f32) -> Result<Self, CurveError> {
+        if !(gamma.is_finite() && gamma > 0.0) || !(0.0..1.0).contains(&toe) {
+            return Err(CurveError::OutOfRange);
+        }
+        let (g, t) = (1.0 / gamma as f64, toe as f64);
+        Ok(Self::sample(|x| {
+            if x < t {
+                x * t.powf(g) / t
+            } else {
+                x.powf(g)
+            }
+        }))
+    }
+
+    /// 按 Gamma 系数及暗区斜率创建曲线，用于在 v3.0 及以上版本近似 v2.0 的 `USER_DEFINE1` 曲线类型。
+    ///
+    /// 暗区斜率为负时曲线可能出现下降，此时输出将保持前一节点的值以维持单调。
+    pub fn from_coef(gamma_coef: f32, slope_at_zero: f32) -> Self {
+        let (g, s) = (
+            1.0 / gamma_coef.max(f32::EPSILON) as f64,
+            slope_at_zero as f64,
+        );
+        let mut curve = Self::sample(|x| x.powf(g) + s * x * (1.0 - x));
+        for i in 1..GAMMA_CURVE_KNOTS {
+            curve.y[i] = curve.y[i].max(curve.y[i - 1]);
+        }
+        curve
+    }
+
+    /// 校验曲线的节点取值及单调性。
+    pub fn validate(&self) -> Result<(), CurveError> {
+        if self.y.iter().any(|&y| y > GAMMA_CURVE_MAX) {
+            return Err(CurveError::OutOfRange);
+        }
+        if self.y.windows(2).any(|w| w[1] < w[0]) {
+            return Err(CurveError::NotMonotonic);
+        }
+        Ok(())
+    }
+
+    /// 返回对数分段下的 (x, y) 节点。
+    pub fn points(&self) -> Vec<(u16, u16)> {
+        curve_points(GammaSegment::Log, &self.y)
+    }
+
+    fn sample<F: Fn(f64) -> f64>(f: F) -> Self {
+        let max = GAMMA_CURVE_MAX as f64;
+        let mut y = [0; GAMMA_CURVE_KNOTS];
+        for (v, &x) in y.iter_mut().zip(GAMMA_LOG_X_AXIS.iter()) {
+            *v = (f(x as f64 / max).clamp(0.0, 1.0) * max).round() as u16;
+        }
+        Self { y }
+    }
+}
+
+fn curve_points(segment: GammaSegment, y: &[u16; GAMMA_CURVE_KNOTS]) -> Vec<(u16, u16)> {
+    segment
+        .x_axis()
+        .iter()
+        .copied()
+        .zip(y.iter().copied())
+        .collect()
+}
+
+/// 返回 Gamma 属性是否处于手动模式。
+#[cfg(feature = "v2_0")]
+pub fn gamma_is_manual(attr: &GammaAttr) -> bool {
+    attr.mode == GammaMode::RK_AIQ_GAMMA_MODE_MANUAL
+}
+
+/// 返回 Gamma 属性是否处于手动模式。
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v21"
+))]
+pub fn gamma_is_manual(attr: &GammaAttr) -> bool {
+    attr.atrrV21.mode == GammaMode::GAMMA_MODE_MANUAL
+}
+
+/// 返回 Gamma 属性是否处于手动模式。
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v30"
+))]
+pub fn gamma_is_manual(attr: &GammaAttr) -> bool {
+    attr.atrrV30.mode == GammaMode::GAMMA_MODE_MANUAL
+}
+
+/// 返回 Gamma 属性是否处于手动模式。
+#[cfg(not(any(
+    feature = "v2_0",
+    all(
+        any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+        any(feature = "isp_hw_v21", feature = "isp_hw_v30")
+    )
+)))]
+pub fn gamma_is_manual(_attr: &GammaAttr) -> bool {
+    false
+}
+
+/// 将 Gamma 属性中的手动曲线转换为 (x, y) 节点。
+#[cfg(feature = "v2_0")]
+pub fn gamma_curve_points(attr: &GammaAttr) -> Vec<(u16, u16)> {
+    let manual = &attr.stManual;
+    if manual.CurveType == GammaCurveType::RK_GAMMA_CURVE_TYPE_USER_DEFINE1 {
+        return GammaCurve::from_coef(manual.user1.coef1, manual.user1.coef2).points();
+    }
+    let segment = match manual.user2.gamma_out_segnum {
+        1 => GammaSegment::Equal,
+        _ => GammaSegment::Log,
+    };
+    let y = manual
+        .user2
+        .gamma_table
+        .map(|v| v.clamp(0, GAMMA_CURVE_MAX as i32) as u16);
+    curve_points(segment, &y)
+}
+
+/// 将 Gamma 属性中的手动曲线转换为 (x, y) 节点。
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v21"
+))]
+pub fn gamma_curve_points(attr: &GammaAttr) -> Vec<(u16, u16)> {
+    let manual = &attr.atrrV21.stManual;
+    let segment = match manual.Gamma_out_segnum {
+        GammaCurveType::GAMMATYPE_EQU => GammaSegment::Equal,
+        _ => GammaSegment::Log,
+    };
+    curve_points(segment, &manual.Gamma_curve)
+}
+
+/// 将 Gamma 属性中的手动曲线转换为 (x, y) 节点。
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v30"
+))]
+pub fn gamma_curve_points(attr: &GammaAttr) -> Vec<(u16, u16)> {
+    curve_points(GammaSegment::Log, &attr.atrrV30.stManual.Gamma_curve)
+}
 
 /// 一个描述 Gamma 控制的契定。
 pub trait Gamma {
@@ -41,6 +362,12 @@ pub trait Gamma {
     /// * `gamma_coef` Gamma 系数，取值范围 [0,100]，默认值 2.2，精度 0.01。
     /// * `slope_at_zero` 暗区斜率，取值范围 [-0.05,0.05]，默认值 0，精度 0.001。
     fn set_gamma_coef_fast(&self, gamma_coef: f32, slope_at_zero: f32) -> XCamResult<()>;
+
+    /// 获取手动模式下的 Gamma 曲线 (x, y) 节点。
+    fn get_gamma_curve(&self) -> XCamResult<Vec<(u16, u16)>>;
+
+    /// 校验并上传手动模式下的 Gamma 曲线，同时切换至手动模式。
+    fn set_gamma_curve(&self, curve: &GammaCurve) -> XCamResult<()>;
 }
 
 impl Gamma for Context {
@@ -49,7 +376,7 @@ impl Gamma for Context {
         unsafe {
             let mut gamma_attr = GammaAttr::default();
             XCamError::from(ffi::rk_aiq_user_api_agamma_GetAttrib(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut gamma_attr,
             ))
             .ok()
@@ -59,7 +386,7 @@ impl Gamma for Context {
         unsafe {
             let mut gamma_attr = GammaAttr::default();
             XCamError::from(ffi::rk_aiq_user_api2_agamma_GetAttrib(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut gamma_attr,
             ))
             .ok()
@@ -71,7 +398,7 @@ impl Gamma for Context {
         #[cfg(feature = "v2_0")]
         unsafe {
             XCamError::from(ffi::rk_aiq_user_api_agamma_SetAttrib(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 gamma_attr.into(),
             ))
             .ok()
@@ -79,7 +406,7 @@ impl Gamma for Context {
         #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
         unsafe {
             XCamError::from(ffi::rk_aiq_user_api2_agamma_SetAttrib(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 gamma_attr.into(),
             ))
             .ok()
@@ -94,13 +421,60 @@ impl Gamma for Context {
         #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setGammaCoef(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 gamma_coef,
                 slope_at_zero,
             ))
             .ok()
         }
     }
+
+    #[cfg(any(
+        feature = "v2_0",
+        all(
+            any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+            any(feature = "isp_hw_v21", feature = "isp_hw_v30")
+        )
+    ))]
+    fn get_gamma_curve(&self) -> XCamResult<Vec<(u16, u16)>> {
+        self.get_gamma_coef().map(|attr| gamma_curve_points(&attr))
+    }
+
+    #[cfg(not(any(
+        feature = "v2_0",
+        all(
+            any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+            any(feature = "isp_hw_v21", feature = "isp_hw_v30")
+        )
+    )))]
+    fn get_gamma_curve(&self) -> XCamResult<Vec<(u16, u16)>> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(any(
+        feature = "v2_0",
+        all(
+            any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+            any(feature = "isp_hw_v21", feature = "isp_hw_v30")
+        )
+    ))]
+    fn set_gamma_curve(&self, curve: &GammaCurve) -> XCamResult<()> {
+        curve
+            .validate()
+            .map_err(|_| XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM))?;
+        self.set_gamma_coef(GammaAttrBuilder::with_manual_curve(curve).build())
+    }
+
+    #[cfg(not(any(
+        feature = "v2_0",
+        all(
+            any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+            any(feature = "isp_hw_v21", feature = "isp_hw_v30")
+        )
+    )))]
+    fn set_gamma_curve(&self, _curve: &GammaCurve) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
 }
 
 /// 一个代表 Gamma 属性构建器的类型。
@@ -158,14 +532,25 @@ impl GammaAttrBuilder {
         any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
         feature = "isp_hw_v21"
     ))]
-    pub fn with_manual_usr_define1(_coef1: f32, _coef2: f32) -> Self {
+    pub fn with_manual_usr_define1(coef1: f32, coef2: f32) -> Self {
+        Self::with_manual_curve(&GammaCurve::from_coef(coef1, coef2))
+    }
+
+    /// 创建一个用于配置手动曲线的 Gamma 属性构建器。
+    ///
+    /// 调用者应事先通过 [`GammaCurve::validate`] 校验曲线。
+    #[cfg(all(
+        any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+        feature = "isp_hw_v21"
+    ))]
+    pub fn with_manual_curve(curve: &GammaCurve) -> Self {
         Self {
             mode: Some(GammaMode::GAMMA_MODE_MANUAL),
             manual: Some(GammaApiManualV21 {
                 Gamma_en: true,
                 Gamma_out_segnum: GammaCurveType::GAMMATYPE_LOG,
                 Gamma_out_offset: 0,
-                Gamma_curve: [0; 45],
+                Gamma_curve: curve.y,
             }),
             tool: None,
             scene_mode: None,
@@ -177,13 +562,24 @@ impl GammaAttrBuilder {
         any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
         feature = "isp_hw_v30"
     ))]
-    pub fn with_manual_usr_define1(_coef1: f32, _coef2: f32) -> Self {
+    pub fn with_manual_usr_define1(coef1: f32, coef2: f32) -> Self {
+        Self::with_manual_curve(&GammaCurve::from_coef(coef1, coef2))
+    }
+
+    /// 创建一个用于配置手动曲线的 Gamma 属性构建器。
+    ///
+    /// 调用者应事先通过 [`GammaCurve::validate`] 校验曲线。
+    #[cfg(all(
+        any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+        feature = "isp_hw_v30"
+    ))]
+    pub fn with_manual_curve(curve: &GammaCurve) -> Self {
         Self {
             mode: Some(GammaMode::GAMMA_MODE_MANUAL),
             manual: Some(GammaApiManualV30 {
                 Gamma_en: true,
                 Gamma_out_offset: 0,
-                Gamma_curve: [0; 49],
+                Gamma_curve: curve.y,
             }),
             tool: None,
             scene_mode: None,
@@ -191,11 +587,22 @@ impl GammaAttrBuilder {
     }
 
     /// 创建一个用于配置手动曲线表的 Gamma 属性构建器。
+    ///
+    /// 曲线表须包含 [`GAMMA_CURVE_KNOTS`] 个单调不减的节点，否则返回错误。
+    #[cfg(feature = "v2_0")]
+    pub fn with_manual_usr_define2(table: &[i32]) -> Result<Self, CurveError> {
+        let table = table
+            .iter()
+            .map(|&v| u16::try_from(v).map_err(|_| CurveError::OutOfRange))
+            .collect::<Result<Vec<_>, _>>()?;
+        GammaCurve::from_table(&table).map(|curve| Self::with_manual_curve(&curve))
+    }
+
+    /// 创建一个用于配置手动曲线的 Gamma 属性构建器。
+    ///
+    /// 调用者应事先通过 [`GammaCurve::validate`] 校验曲线。
     #[cfg(feature = "v2_0")]
-    pub fn with_manual_usr_define2(table: &[i32]) -> Self {
-        assert_eq!(table.len(), 45);
-        let mut gamma_table: [i32; 45] = [0; 45];
-        gamma_table.copy_from_slice(&table[0..45]);
+    pub fn with_manual_curve(curve: &GammaCurve) -> Self {
         Self {
             mode: Some(GammaMode::RK_AIQ_GAMMA_MODE_MANUAL),
             manual: Some(GammaApiManual {
@@ -205,7 +612,7 @@ impl GammaAttrBuilder {
                 user2: GammaCurveUsrDefine2Para {
                     gamma_out_segnum: 0,
                     gamma_out_offset: 0,
-                    gamma_table,
+                    gamma_table: curve.y.map(|v| v as i32),
                 },
             }),
             tool: None,
@@ -214,46 +621,14 @@ impl GammaAttrBuilder {
     }
 
     /// 创建一个用于配置手动曲线表的 Gamma 属性构建器。
+    ///
+    /// 曲线表须包含 [`GAMMA_CURVE_KNOTS`] 个单调不减的节点，否则返回错误。
     #[cfg(all(
         any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
-        feature = "isp_hw_v21"
-    ))]
-    pub fn with_manual_usr_define2(table: &[u16]) -> Self {
-        assert_eq!(table.len(), 45);
-        let mut gamma_table: [u16; 45] = [0; 45];
-        gamma_table.copy_from_slice(&table[0..45]);
-        Self {
-            mode: Some(GammaMode::GAMMA_MODE_MANUAL),
-            manual: Some(GammaApiManualV21 {
-                Gamma_en: true,
-                Gamma_out_segnum: GammaCurveType::GAMMATYPE_LOG,
-                Gamma_out_offset: 0,
-                Gamma_curve: gamma_table,
-            }),
-            tool: None,
-            scene_mode: None,
-        }
-    }
-
-    /// 创建一个用于配置手动曲线表的 Gamma 属性构建器。
-    #[cfg(all(
-        any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
-        feature = "isp_hw_v30"
+        any(feature = "isp_hw_v21", feature = "isp_hw_v30")
     ))]
-    pub fn with_manual_usr_define2(table: &[u16]) -> Self {
-        assert_eq!(table.len(), 49);
-        let mut gamma_table: [u16; 49] = [0; 49];
-        gamma_table.copy_from_slice(&table[0..49]);
-        Self {
-            mode: Some(GammaMode::GAMMA_MODE_MANUAL),
-            manual: Some(GammaApiManualV30 {
-                Gamma_en: true,
-                Gamma_out_offset: 0,
-                Gamma_curve: gamma_table,
-            }),
-            tool: None,
-            scene_mode: None,
-        }
+    pub fn with_manual_usr_define2(table: &[u16]) -> Result<Self, CurveError> {
+        GammaCurve::from_table(table).map(|curve| Self::with_manual_curve(&curve))
     }
 
     /// 返回 Gamma 属性。
@@ -312,3 +687,48 @@ impl Default for GammaAttrBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_generators() {
+        for curve in [
+            GammaCurve::srgb(),
+            GammaCurve::bt709(),
+            GammaCurve::hlg(),
+            GammaCurve::log(64.0).unwrap(),
+            GammaCurve::power(2.2, 0.01).unwrap(),
+        ] {
+            assert_eq!(curve.validate(), Ok(()));
+            assert_eq!(curve.y[0], 0);
+            assert_eq!(curve.y[GAMMA_CURVE_KNOTS - 1], GAMMA_CURVE_MAX);
+        }
+        assert!(GammaCurve::log(0.0).is_err());
+        assert!(GammaCurve::power(2.2, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_curve_validation() {
+        assert_eq!(
+            GammaCurve::from_table(&[0; GAMMA_CURVE_KNOTS - 1]),
+            Err(CurveError::Length)
+        );
+        let mut table = GammaCurve::srgb().y;
+        table.swap(10, 11);
+        assert_eq!(
+            GammaCurve::from_table(&table),
+            Err(CurveError::NotMonotonic)
+        );
+    }
+
+    #[test]
+    fn test_curve_from_points() {
+        let curve = GammaCurve::from_points(&[(0.0, 0.0), (1.0, 1.0)]).unwrap();
+        for (x, y) in curve.points() {
+            assert_eq!(x, y);
+        }
+        assert!(GammaCurve::from_points(&[(0.0, 0.0), (0.5, 1.0)]).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! 绿通道平衡
+//!
+//! GIC 模块用于校正 Bayer 数据中 Gr 与 Gb 通道之间的不平衡，
+//! 消除由此带来的迷宫格纹理。
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::types::{GicAttr, GicParams, OpMode, XCamResult};
+
+/// 一个描述绿通道平衡控制的契定。
+pub trait GreenImbalanceCorrection {
+    /// 获取绿通道平衡属性。
+    fn get_gic_attrib(&self) -> XCamResult<GicAttr>;
+
+    /// 设置绿通道平衡属性。
+    fn set_gic_attrib<T: Into<GicAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 开启绿通道平衡。
+    fn enable_gic(&self) -> XCamResult<()>;
+
+    /// 关闭绿通道平衡。
+    fn disable_gic(&self) -> XCamResult<()>;
+
+    /// 获取绿通道平衡工作模式。
+    fn get_gic_mode(&self) -> XCamResult<OpMode>;
+
+    /// 设置绿通道平衡工作模式，仅支持 `Auto` 及 `Manual`。
+    fn set_gic_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 获取自动模式下按 ISO 索引的参数表。
+    fn get_gic_auto_params(&self) -> XCamResult<Vec<GicParams>>;
+
+    /// 设置自动模式下按 ISO 索引的参数表，并切换至自动模式。
+    ///
+    /// # Parameters
+    /// * `params` - 参数表，长度不能超过硬件支持的 ISO 档位数。
+    fn set_gic_auto_params(&self, params: &[GicParams]) -> XCamResult<()>;
+
+    /// 获取手动模式参数。
+    fn get_gic_manual_params(&self) -> XCamResult<GicParams>;
+
+    /// 设置手动模式参数，并切换至手动模式。
+    fn set_gic_manual_params<T: Into<GicParams>>(&self, params: T) -> XCamResult<()>;
+}
+
+impl GreenImbalanceCorrection for Context {
+    #[cfg(feature = "isp_hw_v20")]
+    fn get_gic_attrib(&self) -> XCamResult<GicAttr> {
+        let mut attr = GicAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_agic_v1_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    #[cfg(not(feature = "isp_hw_v20"))]
+    fn get_gic_attrib(&self) -> XCamResult<GicAttr> {
+        let mut attr = GicAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_agic_v2_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    #[cfg(feature = "isp_hw_v20")]
+    fn set_gic_attrib<T: Into<GicAttr>>(&self, attr: T) -> XCamResult<()> {
+        let attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_agic_v1_SetAttrib(
+                self.lock().as_ptr(),
+                &attr,
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(not(feature = "isp_hw_v20"))]
+    fn set_gic_attrib<T: Into<GicAttr>>(&self, attr: T) -> XCamResult<()> {
+        let attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_agic_v2_SetAttrib(
+                self.lock().as_ptr(),
+                &attr,
+            ))
+            .ok()
+        }
+    }
+
+    fn enable_gic(&self) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_gic_attrib()?;
+        attr.gic_en = 1;
+        self.set_gic_attrib(attr)
+    }
+
+    fn disable_gic(&self) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_gic_attrib()?;
+        attr.gic_en = 0;
+        self.set_gic_attrib(attr)
+    }
+
+    fn get_gic_mode(&self) -> XCamResult<OpMode> {
+        self.get_gic_attrib().map(|attr| attr.op_mode.into())
+    }
+
+    fn set_gic_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_gic_attrib()?;
+        attr.op_mode = gic_op_mode(mode)?;
+        self.set_gic_attrib(attr)
+    }
+
+    fn get_gic_auto_params(&self) -> XCamResult<Vec<GicParams>> {
+        let attr = self.get_gic_attrib()?;
+        let count = (attr.iso_cnt as usize).min(attr.auto_params.len());
+        Ok(attr.auto_params[..count].to_vec())
+    }
+
+    fn set_gic_auto_params(&self, params: &[GicParams]) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_gic_attrib()?;
+        if params.is_empty() || params.len() > attr.auto_params.len() {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        attr.op_mode = ffi::rkaiq_gic_api_op_mode_t::RKAIQ_GIC_API_OPMODE_AUTO;
+        attr.iso_cnt = params.len() as _;
+        attr.auto_params[..params.len()].copy_from_slice(params);
+        self.set_gic_attrib(attr)
+    }
+
+    fn get_gic_manual_params(&self) -> XCamResult<GicParams> {
+        self.get_gic_attrib().map(|attr| attr.manual_param)
+    }
+
+    fn set_gic_manual_params<T: Into<GicParams>>(&self, params: T) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_gic_attrib()?;
+        attr.op_mode = ffi::rkaiq_gic_api_op_mode_t::RKAIQ_GIC_API_OPMODE_MANUAL;
+        attr.manual_param = params.into();
+        self.set_gic_attrib(attr)
+    }
+}
+
+impl From<ffi::rkaiq_gic_api_op_mode_t> for OpMode {
+    fn from(val: ffi::rkaiq_gic_api_op_mode_t) -> Self {
+        use ffi::rkaiq_gic_api_op_mode_t::*;
+        match val {
+            RKAIQ_GIC_API_OPMODE_AUTO => OpMode::Auto,
+            RKAIQ_GIC_API_OPMODE_MANUAL => OpMode::Manual,
+            _ => OpMode::Invalid,
+        }
+    }
+}
+
+fn gic_op_mode(mode: OpMode) -> XCamResult<ffi::rkaiq_gic_api_op_mode_t> {
+    use ffi::rkaiq_gic_api_op_mode_t::*;
+    match mode {
+        OpMode::Auto => Ok(RKAIQ_GIC_API_OPMODE_AUTO),
+        OpMode::Manual => Ok(RKAIQ_GIC_API_OPMODE_MANUAL),
+        _ => Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+}
@@ -5,6 +5,8 @@
 /// 技术更大曝光动态范围（即更大的明暗差别）图像的一种技术。
 /// HDR 的目的就是要正确地还原出超出现有设备动态范围的现实场景光亮比例。
 use super::context::Context;
+#[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+use super::drc::DynamicRangeCompression;
 use super::error::XCamError;
 use super::ffi;
 use super::types::{OpMode, XCamResult};
@@ -28,23 +30,20 @@ impl HighDynamicRange for Context {
         #[cfg(feature = "v2_0")]
         unsafe {
             let mut mode: ffi::opMode_t = Default::default();
-            XCamError::from(ffi::rk_aiq_uapi_getHDRMode(
-                self.internal.as_ptr(),
-                &mut mode,
-            ))
-            .ok()
-            .map(|_| mode.into())
+            XCamError::from(ffi::rk_aiq_uapi_getHDRMode(self.lock().as_ptr(), &mut mode))
+                .ok()
+                .map(|_| mode.into())
         }
         // #[cfg(feature = "v3_0")]
         #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
-        Ok(OpMode::Auto)
+        self.get_drc_mode()
     }
 
     #[cfg(feature = "v2_0")]
     fn set_hdr_mode<T: Into<OpMode>>(&self, mode: T) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setHDRMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into().into(),
             ))
             .ok()
@@ -53,8 +52,8 @@ impl HighDynamicRange for Context {
 
     // #[cfg(feature = "v3_0")]
     #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
-    fn set_hdr_mode<T: Into<OpMode>>(&self, _mode: T) -> XCamResult<()> {
-        Ok(())
+    fn set_hdr_mode<T: Into<OpMode>>(&self, mode: T) -> XCamResult<()> {
+        self.set_drc_mode(mode.into())
     }
 
     fn get_hdr_strth(&self) -> XCamResult<(bool, u32)> {
@@ -63,7 +62,7 @@ impl HighDynamicRange for Context {
         #[cfg(feature = "v2_0")]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getMHDRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut enabled,
                 &mut level,
             ))
@@ -74,7 +73,7 @@ impl HighDynamicRange for Context {
         #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_getMHDRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut enabled,
                 &mut level,
             ))
@@ -87,7 +86,7 @@ impl HighDynamicRange for Context {
         #[cfg(feature = "v2_0")]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setMHDRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 enabled,
                 level,
             ))
@@ -97,7 +96,7 @@ impl HighDynamicRange for Context {
         #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setMHDRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 enabled,
                 level,
             ))
@@ -0,0 +1,291 @@
+//! IQ 文件定位
+//!
+//! librkaiq 按 `<sensor>_<module>_<lens>.json` 的命名规则在 IQ 目录中查找标定文件。
+//! 本模块依据 [`CameraModuleInfo`] 及 [`StaticInfo`] 中的镜头信息生成候选文件名，
+//! 在配置的目录中依次查找，并返回最终选用的文件及其匹配方式。
+//!
+//! 查找顺序（每一步均依次搜索全部目录）：
+//!
+//! 1. `<sensor>_<module>_<lens>.json`，完全匹配；
+//! 2. `<sensor>_*_<lens>.json`，模组名称未知时按镜头匹配；
+//! 3. `<sensor>_<module>.json`，无镜头信息的模组；
+//! 4. `<sensor>_*.json`，同一传感器的任意标定文件；
+//! 5. `<sensor>.json`，通用标定文件。
+use super::types::{CameraModuleInfo, StaticInfo};
+use std::ffi::CStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// 默认的 IQ 文件目录。
+pub const DEFAULT_IQ_FILE_DIR: &str = "/etc/iqfiles";
+
+/// 一个代表 IQ 文件查找条件的类型。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IqLookup {
+    /// 传感器名称，如 `imx415`。
+    pub sensor: String,
+    /// 模组名称，如 `CMK-OT2022-PX1`。
+    pub module: Option<String>,
+    /// 镜头名称，如 `IR0147-50IRC-8M-F20`。
+    pub lens: Option<String>,
+}
+
+impl IqLookup {
+    pub fn new<T: Into<String>>(sensor: T) -> Self {
+        Self {
+            sensor: sensor.into(),
+            ..Default::default()
+        }
+    }
+
+    /// 由摄像头模块信息及静态信息创建，镜头名称取自 `lens_info.len_name`。
+    ///
+    /// 模组名称不在实体名称及静态信息中，需通过 [`IqLookup::module`] 另行指定。镜头名称
+    /// 未以 NUL 结尾时视为无镜头信息。
+    pub fn from_module_info(info: &CameraModuleInfo, static_info: &StaticInfo) -> Self {
+        let bytes = static_info.lens_info.len_name.map(|c| c as u8);
+        let lookup = Self::new(info.name.as_str());
+        match CStr::from_bytes_until_nul(&bytes) {
+            Ok(lens) => lookup.lens(lens.to_string_lossy().into_owned()),
+            Err(_) => lookup,
+        }
+    }
+
+    /// 设置模组名称，空字符串表示未知。
+    pub fn module<T: Into<String>>(mut self, val: T) -> Self {
+        self.module = Some(val.into()).filter(|s: &String| !s.is_empty());
+        self
+    }
+
+    /// 设置镜头名称，空字符串表示无镜头信息。
+    pub fn lens<T: Into<String>>(mut self, val: T) -> Self {
+        self.lens = Some(val.into()).filter(|s: &String| !s.is_empty());
+        self
+    }
+}
+
+/// 一个描述 IQ 文件匹配方式的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IqMatch {
+    /// `<sensor>_<module>_<lens>.json`
+    Exact,
+    /// `<sensor>_*_<lens>.json`
+    AnyModule,
+    /// `<sensor>_<module>.json`
+    LensLess,
+    /// `<sensor>_*.json`
+    AnySensorFile,
+    /// `<sensor>.json`
+    Generic,
+}
+
+/// 一个代表 IQ 文件查找结果的类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IqResolution {
+    /// 选用的文件路径。
+    pub path: PathBuf,
+    /// 匹配方式。
+    pub matched: IqMatch,
+}
+
+impl IqResolution {
+    /// 返回文件所在目录，用作 `iq_file_dir`。
+    pub fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or_else(|| Path::new("."))
+    }
+
+    /// 返回文件名，用作 `pre_init` 的 `iq_file`。
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for IqResolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?})", self.path.display(), self.matched)
+    }
+}
+
+/// 一个代表未找到 IQ 文件的错误类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IqNotFound {
+    /// 查找条件。
+    pub lookup: IqLookup,
+    /// 已搜索的目录。
+    pub dirs: Vec<PathBuf>,
+}
+
+impl fmt::Display for IqNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No IQ file for sensor {}", self.lookup.sensor)?;
+        if let Some(module) = &self.lookup.module {
+            write!(f, ", module {}", module)?;
+        }
+        if let Some(lens) = &self.lookup.lens {
+            write!(f, ", lens {}", lens)?;
+        }
+        write!(f, " in {:?}", self.dirs)
+    }
+}
+
+impl std::error::Error for IqNotFound {}
+
+/// 一个代表 IQ 文件定位器的类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IqResolver {
+    dirs: Vec<PathBuf>,
+}
+
+impl IqResolver {
+    /// 创建一个仅搜索 [`DEFAULT_IQ_FILE_DIR`] 的定位器。
+    pub fn new() -> Self {
+        Self::with_dirs([DEFAULT_IQ_FILE_DIR])
+    }
+
+    /// 创建一个按给定顺序搜索目录的定位器。
+    pub fn with_dirs<I, P>(dirs: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        Self {
+            dirs: dirs.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// 追加一个搜索目录，优先级低于已有目录。
+    pub fn dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.dirs.push(dir.into());
+        self
+    }
+
+    /// 返回搜索目录。
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// 查找 IQ 文件。
+    pub fn resolve(&self, lookup: &IqLookup) -> Result<IqResolution, IqNotFound> {
+        let sensor = lookup.sensor.as_str();
+        let module = lookup.module.as_deref();
+        let lens = lookup.lens.as_deref();
+
+        let mut steps: Vec<(IqMatch, Pattern)> = Vec::new();
+        if let (Some(module), Some(lens)) = (module, lens) {
+            let name = format!("{}_{}_{}.json", sensor, module, lens);
+            steps.push((IqMatch::Exact, Pattern::Exact(name)));
+        }
+        if let (None, Some(lens)) = (module, lens) {
+            let suffix = format!("_{}.json", lens);
+            steps.push((IqMatch::AnyModule, Pattern::Wildcard(suffix)));
+        }
+        if let Some(module) = module {
+            let name = format!("{}_{}.json", sensor, module);
+            steps.push((IqMatch::LensLess, Pattern::Exact(name)));
+        }
+        steps.push((IqMatch::AnySensorFile, Pattern::Wildcard(".json".into())));
+        steps.push((IqMatch::Generic, Pattern::Exact(format!("{}.json", sensor))));
+
+        for (matched, pattern) in steps {
+            for dir in &self.dirs {
+                if let Some(path) = pattern.find(dir, sensor) {
+                    return Ok(IqResolution { path, matched });
+                }
+            }
+        }
+        Err(IqNotFound {
+            lookup: lookup.clone(),
+            dirs: self.dirs.clone(),
+        })
+    }
+}
+
+impl Default for IqResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum Pattern {
+    /// 完整文件名。
+    Exact(String),
+    /// `<sensor>_` 开头并以给定后缀结尾的文件名，多个匹配时取字典序最小者。
+    Wildcard(String),
+}
+
+impl Pattern {
+    fn find(&self, dir: &Path, sensor: &str) -> Option<PathBuf> {
+        match self {
+            Pattern::Exact(name) => Some(dir.join(name)).filter(|p| p.is_file()),
+            Pattern::Wildcard(suffix) => {
+                let prefix = format!("{}_", sensor);
+                let mut found: Vec<PathBuf> = std::fs::read_dir(dir)
+                    .ok()?
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file())
+                    .filter(|p| {
+                        p.file_name().and_then(|s| s.to_str()).is_some_and(|s| {
+                            s.len() > prefix.len() + suffix.len()
+                                && s.starts_with(&prefix)
+                                && s.ends_with(suffix.as_str())
+                        })
+                    })
+                    .collect();
+                found.sort();
+                found.into_iter().next()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_resolve() {
+        let dir = std::env::temp_dir().join(format!("rkaiq-iqfile-{}", std::process::id()));
+        let generic = dir.join("generic");
+        fs::create_dir_all(&generic).unwrap();
+        for name in [
+            "imx415_CMK-OT2022-PX1_IR0147-50IRC-8M-F20.json",
+            "imx415_A_B.json",
+        ] {
+            fs::write(dir.join(name), "{}").unwrap();
+        }
+        fs::write(generic.join("os04a10.json"), "{}").unwrap();
+        let resolver = IqResolver::with_dirs([&dir, &generic]);
+
+        let lookup = IqLookup::new("imx415")
+            .module("CMK-OT2022-PX1")
+            .lens("IR0147-50IRC-8M-F20");
+        let r = resolver.resolve(&lookup).unwrap();
+        assert_eq!(r.matched, IqMatch::Exact);
+        assert_eq!(r.dir(), dir.as_path());
+
+        let lookup = IqLookup::new("imx415").lens("IR0147-50IRC-8M-F20");
+        let r = resolver.resolve(&lookup).unwrap();
+        assert_eq!(r.matched, IqMatch::AnyModule);
+        assert_eq!(
+            r.file_name(),
+            "imx415_CMK-OT2022-PX1_IR0147-50IRC-8M-F20.json"
+        );
+
+        let lookup = IqLookup::new("imx415").module("X").lens("");
+        let r = resolver.resolve(&lookup).unwrap();
+        assert_eq!(r.matched, IqMatch::AnySensorFile);
+        assert_eq!(r.file_name(), "imx415_A_B.json");
+
+        let r = resolver.resolve(&IqLookup::new("os04a10")).unwrap();
+        assert_eq!(r.matched, IqMatch::Generic);
+        assert_eq!(r.dir(), generic.as_path());
+
+        assert!(resolver.resolve(&IqLookup::new("gc2053")).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
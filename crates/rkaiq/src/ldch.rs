@@ -6,19 +6,176 @@
 use super::context::Context;
 use super::error::XCamError;
 use super::ffi;
-use super::types::XCamResult;
+use super::types::{LdchAttr, XCamResult};
+use std::path::Path;
+
+/// 一个代表 LDCH 参数的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LdchParams {
+    /// 是否开启。
+    pub enable: bool,
+    /// 校正强度，0 为不校正，255 为最大强度。
+    pub level: u8,
+}
+
+impl From<&LdchAttr> for LdchParams {
+    fn from(val: &LdchAttr) -> Self {
+        Self {
+            enable: val.en != 0,
+            level: val.correct_level.clamp(0, u8::MAX as _) as u8,
+        }
+    }
+}
 
 pub trait LDCH {
     fn enable_ldch(&self) -> XCamResult<()>;
     fn disable_ldch(&self) -> XCamResult<()>;
+
+    /// 获取 LDCH 属性。
+    fn get_ldch_attrib(&self) -> XCamResult<LdchAttr>;
+
+    /// 设置 LDCH 属性。
+    fn set_ldch_attrib<T: Into<LdchAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 获取类型化的 LDCH 参数。
+    fn get_ldch_params(&self) -> XCamResult<LdchParams>;
+
+    /// 获取校正强度。
+    fn get_ldch_level(&self) -> XCamResult<u8>;
+
+    /// 设置校正强度，0 为不校正，255 为最大强度。
+    fn set_ldch_level(&self, level: u8) -> XCamResult<()>;
+
+    /// 从外部文件加载自定义校正网格，替代按校正强度在线生成的网格。
+    ///
+    /// # Parameters
+    /// * `path` - 网格文件路径，文件须由 Rockchip 标定工具生成。路径须包含所在目录且为合法的
+    ///   UTF-8 字符串，否则返回参数错误。
+    fn load_ldch_mesh<P: AsRef<Path>>(&self, path: P) -> XCamResult<()>;
 }
 
 impl LDCH for Context {
     fn enable_ldch(&self) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi2_setLdchEn(self.internal.as_ptr(), true)).ok() }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi2_setLdchEn(self.lock().as_ptr(), true)).ok() }
     }
 
     fn disable_ldch(&self) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi2_setLdchEn(self.internal.as_ptr(), false)).ok() }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi2_setLdchEn(self.lock().as_ptr(), false)).ok() }
+    }
+
+    fn get_ldch_attrib(&self) -> XCamResult<LdchAttr> {
+        let mut attr = LdchAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_aldch_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    fn set_ldch_attrib<T: Into<LdchAttr>>(&self, attr: T) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_aldch_SetAttrib(
+                self.lock().as_ptr(),
+                attr.into(),
+            ))
+            .ok()
+        }
+    }
+
+    fn get_ldch_params(&self) -> XCamResult<LdchParams> {
+        self.get_ldch_attrib().map(|attr| LdchParams::from(&attr))
+    }
+
+    fn get_ldch_level(&self) -> XCamResult<u8> {
+        self.get_ldch_params().map(|p| p.level)
+    }
+
+    fn set_ldch_level(&self, level: u8) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_setLdchCorrectLevel(
+                self.lock().as_ptr(),
+                level as _,
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(any(feature = "v4_0", feature = "v5_0"))]
+    fn load_ldch_mesh<P: AsRef<Path>>(&self, path: P) -> XCamResult<()> {
+        let _lock = self.lock();
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_FILE));
+        }
+        let (dir, name) = split_mesh_path(path)?;
+        let mut attr = self.get_ldch_attrib()?;
+        copy_c_str(&mut attr.lut.config_file_dir, dir)?;
+        copy_c_str(&mut attr.lut.mesh_file_name, name)?;
+        attr.update_lut_mode =
+            ffi::rk_aiq_ldch_update_lut_mode_t::RK_AIQ_LDCH_UPDATE_LUT_FROM_EXTERNAL_FILE;
+        self.set_ldch_attrib(attr)
+    }
+
+    #[cfg(not(any(feature = "v4_0", feature = "v5_0")))]
+    fn load_ldch_mesh<P: AsRef<Path>>(&self, _path: P) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
+}
+
+/// 将网格文件路径拆分为目录及文件名。
+#[cfg(any(feature = "v4_0", feature = "v5_0"))]
+fn split_mesh_path(path: &Path) -> XCamResult<(&str, &str)> {
+    let dir = path.parent().and_then(Path::to_str);
+    let name = path.file_name().and_then(|x| x.to_str());
+    match (dir, name) {
+        (Some(dir), Some(name)) if !dir.is_empty() => Ok((dir, name)),
+        _ => Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+}
+
+/// 将字符串复制到以 NUL 结尾的定长 C 字符数组。
+#[cfg(any(feature = "v4_0", feature = "v5_0"))]
+fn copy_c_str(dst: &mut [std::os::raw::c_char], src: &str) -> XCamResult<()> {
+    let bytes = src.as_bytes();
+    if bytes.len() >= dst.len() || bytes.contains(&0) {
+        return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+    }
+    for (d, &s) in dst.iter_mut().zip(bytes) {
+        *d = s as _;
+    }
+    dst[bytes.len()..].fill(0);
+    Ok(())
+}
+
+#[cfg(all(test, any(feature = "v4_0", feature = "v5_0")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_mesh_path() {
+        assert_eq!(
+            split_mesh_path(Path::new("/etc/iqfiles/mesh.bin")),
+            Ok(("/etc/iqfiles", "mesh.bin"))
+        );
+        assert_eq!(
+            split_mesh_path(Path::new("./mesh.bin")),
+            Ok((".", "mesh.bin"))
+        );
+        assert!(split_mesh_path(Path::new("mesh.bin")).is_err());
+        assert!(split_mesh_path(Path::new("/")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_split_mesh_path_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/etc/\xff/mesh.bin"));
+        assert!(split_mesh_path(path).is_err());
     }
 }
@@ -1,20 +1,42 @@
+#[cfg(feature = "iq")]
+pub use rkaiq_iq as iq;
 pub use rkaiq_sys as ffi;
 
 pub mod acm;
 pub mod ae;
 pub mod af;
+#[cfg(feature = "async")]
+pub mod aio;
 pub mod asd;
 pub mod awb;
+pub mod batch;
+pub mod bayernr;
+pub mod cnr;
 pub mod context;
+pub mod debayer;
 pub mod defog;
+pub mod degamma;
+#[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+pub mod drc;
 pub mod error;
 pub mod fec;
 pub mod gamma;
+pub mod gic;
 pub mod hdr;
+pub mod iqfile;
 pub mod ldch;
+#[cfg(feature = "live_tuning")]
+pub mod live;
+pub mod manager;
+pub mod merge;
 pub mod misc;
+pub mod mode_switch;
+pub mod multi_isp;
 pub mod nr;
 pub mod prelude;
+pub mod profile;
+pub mod scene;
 pub mod sharpen;
 pub mod sysctl;
 pub mod types;
+pub mod ynr;
@@ -0,0 +1,380 @@
+//! IQ 文件在线调试
+//!
+//! [`LiveTuning`] 使用 inotify 监视 IQ 文件或目录，文件变化时先以 [`iq`](crate::iq)
+//! 解析器校验，再调用 [`SystemControl::update_iq`] 下发，随后重新应用用户覆盖设置；
+//! 下发或覆盖设置失败时自动回退到最近一次成功的文件。
+//!
+//! ```no_run
+//! use rkaiq::live::LiveTuning;
+//! use rkaiq::prelude::*;
+//!
+//! let ctx = Context::new("m00_b_imx415 4-001a", "/etc/iqfiles").unwrap();
+//! let mut live = LiveTuning::watch_file(&ctx, "/etc/iqfiles/imx415.json").unwrap();
+//! live.add_override(|ctx| ctx.set_sharpness(60));
+//! live.run(|event| println!("{}", event)).unwrap();
+//! ```
+use super::context::Context;
+use super::error::XCamError;
+use super::iq::{IqError, IqFile, IspVersion};
+use super::sysctl::SystemControl;
+use super::types::XCamResult;
+use inotify::{Inotify, WatchMask};
+use std::borrow::Borrow;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 一个代表用户覆盖设置的类型，每次下发 IQ 文件后依次重新应用。
+pub type Override = Box<dyn Fn(&Context) -> XCamResult<()> + Send>;
+
+/// 一个描述 IQ 文件重载失败原因的枚举。
+#[derive(Debug)]
+pub enum ReloadError {
+    /// 读取或备份文件失败。
+    Io(io::Error),
+    /// 文件未通过校验。
+    Invalid(IqError),
+    /// 文件所属 ISP 版本与当前硬件不符。
+    IspMismatch(IspVersion),
+    /// IQ 解析器不支持当前硬件版本，内容为对应的特性名称。
+    UnsupportedIsp(&'static str),
+    /// `update_iq` 失败。
+    Update(XCamError),
+    /// 重新应用第 N 个覆盖设置失败。
+    Override(usize, XCamError),
+}
+
+impl fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReloadError::Io(err) => write!(f, "{}", err),
+            ReloadError::Invalid(err) => write!(f, "{}", err),
+            ReloadError::IspMismatch(isp) => write!(f, "IQ file is for {:?}", isp),
+            ReloadError::UnsupportedIsp(hw) => write!(f, "IQ files for {} are not supported", hw),
+            ReloadError::Update(err) => write!(f, "update_iq: {}", err),
+            ReloadError::Override(i, err) => write!(f, "override #{}: {}", i, err),
+        }
+    }
+}
+
+impl std::error::Error for ReloadError {}
+
+/// 一个描述 IQ 文件重载结果的枚举。
+#[derive(Debug)]
+pub enum ReloadEvent {
+    /// 已成功下发。
+    Applied(PathBuf),
+    /// 未通过校验，未下发。
+    Rejected(PathBuf, ReloadError),
+    /// 下发失败，已回退到最近一次成功的文件。
+    RolledBack(PathBuf, ReloadError),
+    /// 下发失败且回退失败，或没有可回退的文件。
+    Failed(PathBuf, ReloadError),
+}
+
+impl fmt::Display for ReloadEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReloadEvent::Applied(path) => write!(f, "{}: applied", path.display()),
+            ReloadEvent::Rejected(path, err) => {
+                write!(f, "{}: rejected: {}", path.display(), err)
+            }
+            ReloadEvent::RolledBack(path, err) => {
+                write!(f, "{}: rolled back: {}", path.display(), err)
+            }
+            ReloadEvent::Failed(path, err) => write!(f, "{}: failed: {}", path.display(), err),
+        }
+    }
+}
+
+/// 返回当前编译的硬件版本所对应的 IQ 文件 ISP 版本。
+///
+/// 未启用 `isp_hw_*` 特性时返回 `Ok(None)`，不校验版本；IQ 解析器尚不支持的硬件版本
+/// 返回 [`ReloadError::UnsupportedIsp`]。
+pub fn expected_isp_version() -> Result<Option<IspVersion>, ReloadError> {
+    if cfg!(feature = "isp_hw_v20") {
+        Ok(Some(IspVersion::V20))
+    } else if cfg!(feature = "isp_hw_v21") {
+        Ok(Some(IspVersion::V21))
+    } else if cfg!(feature = "isp_hw_v30") {
+        Ok(Some(IspVersion::V30))
+    } else if cfg!(feature = "isp_hw_v31") {
+        Err(ReloadError::UnsupportedIsp("isp_hw_v31"))
+    } else if cfg!(feature = "isp_hw_v32") {
+        Ok(Some(IspVersion::V32))
+    } else {
+        Ok(None)
+    }
+}
+
+/// 一个代表 IQ 文件在线调试会话的类型。
+///
+/// `C` 可为 `&Context` 或 `Arc<Context>`。
+pub struct LiveTuning<C: Borrow<Context>> {
+    ctx: C,
+    inotify: Inotify,
+    dir: PathBuf,
+    file_name: Option<PathBuf>,
+    backup: PathBuf,
+    has_backup: bool,
+    overrides: Vec<Override>,
+}
+
+impl<C: Borrow<Context>> LiveTuning<C> {
+    /// 监视单个 IQ 文件，并以其当前内容作为回退基准。
+    ///
+    /// 实际监视的是文件所在目录，以便跟踪编辑器以重命名方式保存的文件。
+    pub fn watch_file<P: AsRef<Path>>(ctx: C, path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?;
+        let mut live = Self::watch(ctx, dir, Some(PathBuf::from(name)))?;
+        if validate(path).is_ok() {
+            live.has_backup = fs::copy(path, &live.backup).is_ok();
+        }
+        Ok(live)
+    }
+
+    /// 监视目录中全部 `.json` 文件。
+    pub fn watch_dir<P: AsRef<Path>>(ctx: C, dir: P) -> io::Result<Self> {
+        Self::watch(ctx, dir.as_ref(), None)
+    }
+
+    fn watch(ctx: C, dir: &Path, file_name: Option<PathBuf>) -> io::Result<Self> {
+        let inotify = Inotify::init()?;
+        inotify
+            .watches()
+            .add(dir, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)?;
+        let backup = std::env::temp_dir().join(format!(
+            "rkaiq-live-{}-{:p}.json",
+            std::process::id(),
+            ctx.borrow() as *const Context
+        ));
+        Ok(Self {
+            ctx,
+            inotify,
+            dir: dir.to_path_buf(),
+            file_name,
+            backup,
+            has_backup: false,
+            overrides: Vec::new(),
+        })
+    }
+
+    /// 添加一个覆盖设置。
+    pub fn add_override<F>(&mut self, f: F)
+    where
+        F: Fn(&Context) -> XCamResult<()> + Send + 'static,
+    {
+        self.overrides.push(Box::new(f));
+    }
+
+    /// 清除全部覆盖设置。
+    pub fn clear_overrides(&mut self) {
+        self.overrides.clear();
+    }
+
+    /// 校验并下发指定文件，失败时回退。
+    pub fn reload<P: AsRef<Path>>(&mut self, path: P) -> ReloadEvent {
+        let path = path.as_ref().to_path_buf();
+        if let Err(err) = validate(&path) {
+            return ReloadEvent::Rejected(path, err);
+        }
+        // 先保存快照，避免回退时文件已被再次改写。
+        let snapshot = self.backup.with_extension("next.json");
+        if let Err(err) = fs::copy(&path, &snapshot) {
+            return ReloadEvent::Rejected(path, ReloadError::Io(err));
+        }
+        match self.apply(&snapshot) {
+            Ok(()) => match fs::rename(&snapshot, &self.backup) {
+                Ok(()) => {
+                    self.has_backup = true;
+                    ReloadEvent::Applied(path)
+                }
+                Err(err) => ReloadEvent::Failed(path, ReloadError::Io(err)),
+            },
+            Err(err) => {
+                let _ = fs::remove_file(&snapshot);
+                if self.has_backup && self.apply(&self.backup).is_ok() {
+                    ReloadEvent::RolledBack(path, err)
+                } else {
+                    ReloadEvent::Failed(path, err)
+                }
+            }
+        }
+    }
+
+    /// 处理已到达的文件变化，不阻塞。
+    pub fn poll(&mut self) -> io::Result<Vec<ReloadEvent>> {
+        let mut buf = [0; 4096];
+        let changed = match self.inotify.read_events(&mut buf) {
+            Ok(events) => filter_names(
+                &self.dir,
+                self.file_name.as_deref(),
+                events.filter_map(|e| e.name),
+            ),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(changed.into_iter().map(|p| self.reload(p)).collect())
+    }
+
+    /// 等待并处理下一批文件变化。
+    pub fn wait(&mut self) -> io::Result<Vec<ReloadEvent>> {
+        let mut buf = [0; 4096];
+        let events = self.inotify.read_events_blocking(&mut buf)?;
+        let changed = filter_names(
+            &self.dir,
+            self.file_name.as_deref(),
+            events.filter_map(|e| e.name),
+        );
+        Ok(changed.into_iter().map(|p| self.reload(p)).collect())
+    }
+
+    /// 持续监视，直到读取 inotify 事件出错。
+    pub fn run<F: FnMut(&ReloadEvent)>(&mut self, mut on_event: F) -> io::Result<()> {
+        loop {
+            for event in self.wait()? {
+                on_event(&event);
+            }
+        }
+    }
+
+    fn apply(&self, path: &Path) -> Result<(), ReloadError> {
+        let ctx = self.ctx.borrow();
+        ctx.update_iq(path.to_string_lossy().into_owned())
+            .map_err(ReloadError::Update)?;
+        for (i, f) in self.overrides.iter().enumerate() {
+            f(ctx).map_err(|err| ReloadError::Override(i, err))?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: Borrow<Context>> Drop for LiveTuning<C> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.backup);
+    }
+}
+
+/// 从 inotify 事件的文件名中筛选需要重载的文件，去除重复项。
+///
+/// 指定 `file_name` 时仅保留该文件，否则保留全部 `.json` 文件。
+fn filter_names<'a, I: Iterator<Item = &'a OsStr>>(
+    dir: &Path,
+    file_name: Option<&Path>,
+    names: I,
+) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = Vec::new();
+    for name in names {
+        let wanted = match file_name {
+            Some(file_name) => name == file_name.as_os_str(),
+            None => Path::new(name).extension() == Some(OsStr::new("json")),
+        };
+        let path = dir.join(name);
+        if wanted && !changed.contains(&path) {
+            changed.push(path);
+        }
+    }
+    changed
+}
+
+fn validate(path: &Path) -> Result<(), ReloadError> {
+    let iq = IqFile::open(path).map_err(ReloadError::Invalid)?;
+    if let Some(isp) = expected_isp_version()? {
+        if iq.isp_version() != isp {
+            return Err(ReloadError::IspMismatch(iq.isp_version()));
+        }
+    }
+    iq.check().map_err(ReloadError::Invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rkaiq-live-test-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn validate_temp(name: &str, contents: &str) -> Result<(), ReloadError> {
+        let path = write_temp(name, contents);
+        let result = validate(&path);
+        let _ = fs::remove_file(&path);
+        result
+    }
+
+    fn iq_for(isp: IspVersion) -> String {
+        format!(
+            r#"{{"main_scene":[{{"name":"normal","sub_scene":[{{"name":"day","{}":{{}}}}]}}]}}"#,
+            isp.scene_key()
+        )
+    }
+
+    #[test]
+    fn test_filter_names() {
+        let dir = Path::new("/etc/iqfiles");
+        let names = ["a.json", "b.txt", "a.json", "c.json", "json"].map(OsStr::new);
+        assert_eq!(
+            filter_names(dir, None, names.into_iter()),
+            vec![dir.join("a.json"), dir.join("c.json")]
+        );
+        assert_eq!(
+            filter_names(dir, Some(Path::new("c.json")), names.into_iter()),
+            vec![dir.join("c.json")]
+        );
+        assert!(filter_names(dir, Some(Path::new("d.json")), names.into_iter()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid() {
+        assert!(matches!(
+            validate(Path::new("/nonexistent/rkaiq.json")),
+            Err(ReloadError::Invalid(IqError::Io(_)))
+        ));
+        assert!(matches!(
+            validate_temp("truncated.json", "{"),
+            Err(ReloadError::Invalid(IqError::Json(_)))
+        ));
+        assert!(matches!(
+            validate_temp("unknown.json", r#"{"main_scene":[]}"#),
+            Err(ReloadError::Invalid(IqError::UnknownIspVersion))
+        ));
+    }
+
+    #[test]
+    fn test_validate_isp_mismatch() {
+        let expected = match expected_isp_version() {
+            Ok(Some(isp)) => isp,
+            Ok(None) => return,
+            Err(err) => {
+                // 不支持的硬件版本拒绝全部可解析的文件。
+                let result = validate_temp("unsupported.json", &iq_for(IspVersion::V30));
+                assert_eq!(result.unwrap_err().to_string(), err.to_string());
+                return;
+            }
+        };
+        for isp in IspVersion::ALL {
+            let result = validate_temp("isp.json", &iq_for(isp));
+            if isp == expected {
+                // 版本相符，但缺少必需的标定段。
+                assert!(matches!(
+                    result,
+                    Err(ReloadError::Invalid(IqError::Invalid(_)))
+                ));
+            } else {
+                assert!(matches!(result, Err(ReloadError::IspMismatch(v)) if v == isp));
+            }
+        }
+    }
+}
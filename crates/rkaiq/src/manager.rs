@@ -0,0 +1,291 @@
+//! 多摄像头管理
+//!
+//! [`CameraManager`] 枚举 AIQ 识别到的全部传感器，为每个传感器创建并持有一个 [`Context`]，
+//! 支持按序号、朝向或名称查找，并提供统一启停及 AE/AWB 同步（用于多路拼接）等组操作。
+//!
+//! 管理器在创建失败时释放已创建的上下文；销毁时先停止仍在运行的摄像头再释放上下文。
+use super::ae::{AutoExposure, ExpInfo};
+use super::awb::{AutoWhiteBalance, WbInfo};
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::multi_isp::MultiIspLayout;
+use super::sysctl::{StaticMetas, SystemControl};
+use super::types::{CameraFacing, CameraModuleInfo, OpMode, StaticInfo, WorkingMode, XCamResult};
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 一个代表受管理摄像头的类型。
+#[derive(Debug)]
+pub struct Camera {
+    index: usize,
+    name: String,
+    module: Option<CameraModuleInfo>,
+    info: StaticInfo,
+    ctx: Context,
+    started: AtomicBool,
+}
+
+impl Camera {
+    /// 返回在管理器中的序号。
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// 返回传感器实体名称，如 `m00_b_imx415 4-001a`。
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 返回由实体名称解析出的模块信息，名称不符合命名规则时返回 `None`。
+    pub fn module_info(&self) -> Option<&CameraModuleInfo> {
+        self.module.as_ref()
+    }
+
+    /// 返回摄像头朝向，无法解析时返回 `None`。
+    pub fn facing(&self) -> Option<CameraFacing> {
+        self.module.as_ref().map(|m| m.facing)
+    }
+
+    /// 返回静态信息。
+    pub fn static_info(&self) -> &StaticInfo {
+        &self.info
+    }
+
+    /// 返回 AIQ 上下文。
+    pub fn context(&self) -> &Context {
+        &self.ctx
+    }
+
+    /// 返回传感器支持的最大分辨率。
+    pub fn max_resolution(&self) -> Option<(u32, u32)> {
+        let info = &self.info.sensor_info;
+        let num = (info.num.max(0) as usize).min(info.support_fmt.len());
+        info.support_fmt[..num]
+            .iter()
+            .map(|fmt| (fmt.width as u32, fmt.height as u32))
+            .max_by_key(|&(w, h)| w as u64 * h as u64)
+    }
+
+    /// 返回指定分辨率下的多 ISP 切分方式，未工作在拼接模式时返回 `None`。
+    pub fn multi_isp_layout(&self, width: u32, height: u32) -> Option<MultiIspLayout> {
+        MultiIspLayout::from_static_info(&self.info, width, height)
+    }
+
+    /// 返回是否已启动。
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::Acquire)
+    }
+}
+
+/// 一个代表组操作失败的错误类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GroupError {
+    /// 失败的摄像头序号。
+    pub index: usize,
+    /// 错误原因。
+    pub error: XCamError,
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "camera #{}: {}", self.index, self.error)
+    }
+}
+
+impl std::error::Error for GroupError {}
+
+fn on(index: usize) -> impl Fn(XCamError) -> GroupError {
+    move |error| GroupError { index, error }
+}
+
+/// 一个代表多摄像头管理器的类型。
+#[derive(Debug)]
+pub struct CameraManager {
+    cameras: Vec<Camera>,
+}
+
+impl CameraManager {
+    /// 枚举全部传感器，并使用同一 IQ 目录为每个传感器创建上下文。
+    pub fn new(iq_file_dir: &str) -> io::Result<Self> {
+        Self::with_factory(|name, _| Context::new(name, iq_file_dir))
+    }
+
+    /// 枚举全部传感器，并使用给定函数为每个传感器创建上下文。
+    ///
+    /// 函数参数为传感器实体名称及静态信息，可结合 [`ContextBuilder`](super::context::ContextBuilder)
+    /// 为每个传感器定位各自的 IQ 文件。任一上下文创建失败时，已创建的上下文将被释放。
+    pub fn with_factory<F>(mut factory: F) -> io::Result<Self>
+    where
+        F: FnMut(&str, &StaticInfo) -> io::Result<Context>,
+    {
+        let mut cameras = Vec::new();
+        for (index, info) in StaticMetas::new().enumerate() {
+            let name = info.sensor_name();
+            let ctx = factory(&name, &info)?;
+            cameras.push(Camera {
+                index,
+                module: name.parse().ok(),
+                name,
+                info,
+                ctx,
+                started: AtomicBool::new(false),
+            });
+        }
+        Ok(Self { cameras })
+    }
+
+    /// 返回摄像头数量。
+    pub fn len(&self) -> usize {
+        self.cameras.len()
+    }
+
+    /// 返回是否没有摄像头。
+    pub fn is_empty(&self) -> bool {
+        self.cameras.is_empty()
+    }
+
+    /// 返回全部摄像头。
+    pub fn iter(&self) -> std::slice::Iter<'_, Camera> {
+        self.cameras.iter()
+    }
+
+    /// 按序号查找。
+    pub fn get(&self, index: usize) -> Option<&Camera> {
+        self.cameras.get(index)
+    }
+
+    /// 按朝向查找第一个匹配的摄像头。
+    pub fn by_facing(&self, facing: CameraFacing) -> Option<&Camera> {
+        self.cameras.iter().find(|c| c.facing() == Some(facing))
+    }
+
+    /// 按实体名称或传感器名称（如 `imx415`）查找第一个匹配的摄像头。
+    pub fn by_name(&self, name: &str) -> Option<&Camera> {
+        self.cameras
+            .iter()
+            .find(|c| c.name == name || c.module.as_ref().is_some_and(|m| m.name == name))
+    }
+
+    /// 依次准备全部摄像头，`f` 返回每个摄像头的分辨率及工作模式。
+    pub fn prepare_all<F>(&self, mut f: F) -> Result<(), GroupError>
+    where
+        F: FnMut(&Camera) -> (u32, u32, WorkingMode),
+    {
+        for cam in &self.cameras {
+            let (width, height, mode) = f(cam);
+            cam.ctx
+                .prepare(width, height, mode)
+                .map_err(on(cam.index))?;
+        }
+        Ok(())
+    }
+
+    /// 启动全部摄像头，任一失败时停止本次已启动的摄像头。
+    pub fn start_all(&self) -> Result<(), GroupError> {
+        let mut started: Vec<&Camera> = Vec::new();
+        for cam in &self.cameras {
+            if cam.started.load(Ordering::Acquire) {
+                continue;
+            }
+            if let Err(err) = cam.ctx.start() {
+                for cam in started.into_iter().rev() {
+                    if cam.ctx.stop(false).is_ok() {
+                        cam.started.store(false, Ordering::Release);
+                    }
+                }
+                return Err(on(cam.index)(err));
+            }
+            cam.started.store(true, Ordering::Release);
+            started.push(cam);
+        }
+        Ok(())
+    }
+
+    /// 停止全部摄像头，出错时继续停止其余摄像头并返回第一个错误。
+    pub fn stop_all(&self) -> Result<(), GroupError> {
+        let mut result = Ok(());
+        for cam in self.cameras.iter().rev() {
+            if !cam.started.load(Ordering::Acquire) {
+                continue;
+            }
+            match cam.ctx.stop(false) {
+                Ok(()) => cam.started.store(false, Ordering::Release),
+                Err(err) => {
+                    if result.is_ok() {
+                        result = Err(on(cam.index)(err));
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// 以 `reference` 的当前白平衡增益手动设置其余摄像头。
+    ///
+    /// 参考摄像头保持自动白平衡，需周期调用以跟随场景变化。
+    pub fn sync_awb(&self, reference: usize) -> Result<WbInfo, GroupError> {
+        let info = self
+            .reference(reference)?
+            .query_wb_info()
+            .map_err(on(reference))?;
+        self.for_others(reference, |ctx| {
+            ctx.set_wb_mode(OpMode::Manual)?;
+            ctx.set_mwb_gain(info.gain)
+        })?;
+        Ok(info)
+    }
+
+    /// 以 `reference` 的当前曝光结果手动设置其余摄像头。
+    ///
+    /// 参考摄像头保持自动曝光，需周期调用以跟随场景变化。
+    pub fn sync_ae(&self, reference: usize) -> Result<ExpInfo, GroupError> {
+        let info = self
+            .reference(reference)?
+            .query_exp_info()
+            .map_err(on(reference))?;
+        self.for_others(reference, |ctx| ctx.set_manual_exp(info.gain, info.time))?;
+        Ok(info)
+    }
+
+    /// 结束同步，其余摄像头恢复自动曝光及自动白平衡。
+    pub fn release_sync(&self, reference: usize) -> Result<(), GroupError> {
+        self.for_others(reference, |ctx| {
+            ctx.set_exp_mode(OpMode::Auto)?;
+            ctx.set_wb_mode(OpMode::Auto)
+        })
+    }
+
+    fn reference(&self, index: usize) -> Result<&Context, GroupError> {
+        self.cameras
+            .get(index)
+            .map(|c| &c.ctx)
+            .ok_or_else(|| on(index)(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)))
+    }
+
+    fn for_others<F>(&self, reference: usize, f: F) -> Result<(), GroupError>
+    where
+        F: Fn(&Context) -> XCamResult<()>,
+    {
+        self.cameras
+            .iter()
+            .filter(|c| c.index != reference)
+            .try_for_each(|c| f(&c.ctx).map_err(on(c.index)))
+    }
+}
+
+impl<'a> IntoIterator for &'a CameraManager {
+    type Item = &'a Camera;
+    type IntoIter = std::slice::Iter<'a, Camera>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Drop for CameraManager {
+    fn drop(&mut self) {
+        let _ = self.stop_all();
+    }
+}
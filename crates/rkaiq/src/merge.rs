@@ -0,0 +1,513 @@
+//! HDR 合成
+//!
+//! Merge 模块将不同曝光的长短帧按权重曲线合成为一帧高动态范围图像，
+//! 并通过运动检测抑制运动物体的鬼影。
+//!
+//! V2.x 通过 ATMO 控制工作模式，合成曲线由 AMERGE 提供；V3.x 及以上统一由 AMERGE 控制。
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::types::{MergeAttr, OpMode, XCamResult};
+
+/// 一个描述 HDR 合成基准帧的枚举。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HdrMergeMode {
+    /// 以长帧为基准合成。
+    #[default]
+    LongFrame,
+    /// 以短帧为基准合成，仅 ISP_HW V30 及以上支持。
+    ShortFrame,
+}
+
+/// 一个代表合成权重曲线的类型。
+///
+/// 权重曲线为 `1 / (1 + exp(-smooth * (x - offset)))` 形式的 S 型曲线。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MergeCurve {
+    /// 曲线陡峭程度。
+    pub smooth: f32,
+    /// 曲线中心位置。
+    pub offset: f32,
+}
+
+/// 一个代表 HDR 合成手动参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HdrMergeParams {
+    /// 合成基准帧。
+    pub mode: HdrMergeMode,
+    /// 过曝权重曲线，决定长帧过曝区域使用短帧数据的比例。
+    pub oe_curve: MergeCurve,
+    /// 长帧与中帧间的运动检测曲线。
+    pub md_curve_lm: MergeCurve,
+    /// 中帧与短帧间的运动检测曲线。
+    pub md_curve_ms: MergeCurve,
+}
+
+/// 一个代表 HDR 合成自动参数表中单个节点的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HdrMergeAutoPoint {
+    /// 节点索引值，对应场景亮度（EnvLv）或长短帧曝光比。
+    pub key: f32,
+    /// 过曝权重曲线。
+    pub oe_curve: MergeCurve,
+    /// 运动检测系数，取值范围 [0,1]。
+    pub move_coef: f32,
+    /// 长帧与中帧间的运动检测曲线。
+    pub md_curve_lm: MergeCurve,
+    /// 中帧与短帧间的运动检测曲线。
+    pub md_curve_ms: MergeCurve,
+}
+
+/// 一个描述 HDR 合成控制的契定。
+pub trait HdrMerge {
+    /// 获取 HDR 合成属性。
+    fn get_merge_attrib(&self) -> XCamResult<MergeAttr>;
+
+    /// 设置 HDR 合成属性。
+    fn set_merge_attrib<T: Into<MergeAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 获取 HDR 合成工作模式。
+    fn get_merge_mode(&self) -> XCamResult<OpMode>;
+
+    /// 设置 HDR 合成工作模式，仅支持 `Auto` 及 `Manual`。
+    fn set_merge_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 获取手动模式参数。
+    fn get_merge_manual(&self) -> XCamResult<HdrMergeParams>;
+
+    /// 设置手动模式参数，并切换至手动模式。
+    fn set_merge_manual(&self, params: &HdrMergeParams) -> XCamResult<()>;
+
+    /// 获取自动模式参数表。
+    fn get_merge_auto(&self) -> XCamResult<Vec<HdrMergeAutoPoint>>;
+
+    /// 设置自动模式参数表，并切换至自动模式。
+    ///
+    /// # Parameters
+    /// * `points` - 参数表，节点个数必须与 IQ 中的节点个数一致，`key` 须单调递增。
+    fn set_merge_auto(&self, points: &[HdrMergeAutoPoint]) -> XCamResult<()>;
+}
+
+impl HdrMerge for Context {
+    #[cfg(feature = "v2_0")]
+    fn get_merge_attrib(&self) -> XCamResult<MergeAttr> {
+        let mut attr = MergeAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_amerge_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    #[cfg(all(
+        any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+        feature = "isp_hw_v21"
+    ))]
+    fn get_merge_attrib(&self) -> XCamResult<MergeAttr> {
+        let mut attr = MergeAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_amerge_v21_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    #[cfg(all(
+        any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+        not(feature = "isp_hw_v21")
+    ))]
+    fn get_merge_attrib(&self) -> XCamResult<MergeAttr> {
+        let mut attr = MergeAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_amerge_v30_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn set_merge_attrib<T: Into<MergeAttr>>(&self, attr: T) -> XCamResult<()> {
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_amerge_SetAttrib(
+                self.lock().as_ptr(),
+                attr.into(),
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(all(
+        any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+        feature = "isp_hw_v21"
+    ))]
+    fn set_merge_attrib<T: Into<MergeAttr>>(&self, attr: T) -> XCamResult<()> {
+        let attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_amerge_v21_SetAttrib(
+                self.lock().as_ptr(),
+                &attr,
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(all(
+        any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+        not(feature = "isp_hw_v21")
+    ))]
+    fn set_merge_attrib<T: Into<MergeAttr>>(&self, attr: T) -> XCamResult<()> {
+        let attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_amerge_v30_SetAttrib(
+                self.lock().as_ptr(),
+                &attr,
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn get_merge_mode(&self) -> XCamResult<OpMode> {
+        let mut attr = ffi::atmo_attrib_t::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_atmo_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()?;
+        }
+        use ffi::atmo_OpMode_t::*;
+        Ok(match attr.opMode {
+            TMO_OPMODE_AUTO => OpMode::Auto,
+            TMO_OPMODE_MANU => OpMode::Manual,
+            _ => OpMode::Invalid,
+        })
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn get_merge_mode(&self) -> XCamResult<OpMode> {
+        use ffi::merge_OpModeV21_t::*;
+        self.get_merge_attrib().map(|attr| match attr.opMode {
+            MERGE_OPMODE_AUTO => OpMode::Auto,
+            MERGE_OPMODE_MANU => OpMode::Manual,
+        })
+    }
+
+    #[cfg(feature = "v2_0")]
+    fn set_merge_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_merge_attrib()?;
+        attr.opMode = merge_op_mode(mode)?;
+        self.set_merge_attrib(attr)?;
+        sync_tmo_mode(self, mode)
+    }
+
+    #[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+    fn set_merge_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_merge_attrib()?;
+        attr.opMode = merge_op_mode(mode)?;
+        self.set_merge_attrib(attr)
+    }
+
+    #[cfg(any(feature = "v2_0", feature = "isp_hw_v21"))]
+    fn get_merge_manual(&self) -> XCamResult<HdrMergeParams> {
+        let attr = self.get_merge_attrib()?;
+        let m = &attr.stManual;
+        Ok(HdrMergeParams {
+            mode: HdrMergeMode::LongFrame,
+            oe_curve: MergeCurve {
+                smooth: m.OECurve.Smooth,
+                offset: m.OECurve.Offset,
+            },
+            md_curve_lm: MergeCurve {
+                smooth: m.MDCurve.LM_smooth,
+                offset: m.MDCurve.LM_offset,
+            },
+            md_curve_ms: MergeCurve {
+                smooth: m.MDCurve.MS_smooth,
+                offset: m.MDCurve.MS_offset,
+            },
+        })
+    }
+
+    #[cfg(not(any(feature = "v2_0", feature = "isp_hw_v21")))]
+    fn get_merge_manual(&self) -> XCamResult<HdrMergeParams> {
+        let attr = self.get_merge_attrib()?;
+        let m = &attr.stManual;
+        let (mode, oe, md) = match m.MergeMode {
+            ffi::MergeBaseFrame_t::BASEFRAME_LONG => (
+                HdrMergeMode::LongFrame,
+                &m.LongFrameModeData.OECurve,
+                &m.LongFrameModeData.MDCurve,
+            ),
+            ffi::MergeBaseFrame_t::BASEFRAME_SHORT => (
+                HdrMergeMode::ShortFrame,
+                &m.ShortFrameModeData.OECurve,
+                &m.ShortFrameModeData.MDCurve,
+            ),
+        };
+        Ok(HdrMergeParams {
+            mode,
+            oe_curve: MergeCurve {
+                smooth: oe.Smooth,
+                offset: oe.Offset,
+            },
+            md_curve_lm: MergeCurve {
+                smooth: md.LM_smooth,
+                offset: md.LM_offset,
+            },
+            md_curve_ms: MergeCurve {
+                smooth: md.MS_smooth,
+                offset: md.MS_offset,
+            },
+        })
+    }
+
+    #[cfg(any(feature = "v2_0", feature = "isp_hw_v21"))]
+    fn set_merge_manual(&self, params: &HdrMergeParams) -> XCamResult<()> {
+        let _lock = self.lock();
+        if params.mode != HdrMergeMode::LongFrame {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        let mut attr = self.get_merge_attrib()?;
+        attr.opMode = merge_op_mode(OpMode::Manual)?;
+        let m = &mut attr.stManual;
+        m.OECurve.Smooth = params.oe_curve.smooth;
+        m.OECurve.Offset = params.oe_curve.offset;
+        m.MDCurve.LM_smooth = params.md_curve_lm.smooth;
+        m.MDCurve.LM_offset = params.md_curve_lm.offset;
+        m.MDCurve.MS_smooth = params.md_curve_ms.smooth;
+        m.MDCurve.MS_offset = params.md_curve_ms.offset;
+        self.set_merge_attrib(attr)?;
+        sync_tmo_mode(self, OpMode::Manual)
+    }
+
+    #[cfg(not(any(feature = "v2_0", feature = "isp_hw_v21")))]
+    fn set_merge_manual(&self, params: &HdrMergeParams) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_merge_attrib()?;
+        attr.opMode = merge_op_mode(OpMode::Manual)?;
+        let m = &mut attr.stManual;
+        let (oe, md) = match params.mode {
+            HdrMergeMode::LongFrame => {
+                m.MergeMode = ffi::MergeBaseFrame_t::BASEFRAME_LONG;
+                (
+                    &mut m.LongFrameModeData.OECurve,
+                    &mut m.LongFrameModeData.MDCurve,
+                )
+            }
+            HdrMergeMode::ShortFrame => {
+                m.MergeMode = ffi::MergeBaseFrame_t::BASEFRAME_SHORT;
+                (
+                    &mut m.ShortFrameModeData.OECurve,
+                    &mut m.ShortFrameModeData.MDCurve,
+                )
+            }
+        };
+        oe.Smooth = params.oe_curve.smooth;
+        oe.Offset = params.oe_curve.offset;
+        md.LM_smooth = params.md_curve_lm.smooth;
+        md.LM_offset = params.md_curve_lm.offset;
+        md.MS_smooth = params.md_curve_ms.smooth;
+        md.MS_offset = params.md_curve_ms.offset;
+        self.set_merge_attrib(attr)
+    }
+
+    fn get_merge_auto(&self) -> XCamResult<Vec<HdrMergeAutoPoint>> {
+        let mut attr = self.get_merge_attrib()?;
+        let columns = auto_arrays(&mut attr);
+        let len = auto_len(&columns)?;
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        if columns.iter().any(|(ptr, _)| ptr.is_null()) {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        let columns: Vec<&[f32]> = columns
+            .iter()
+            .map(|(ptr, _)| unsafe { std::slice::from_raw_parts(**ptr, len) })
+            .collect();
+        let points = (0..len)
+            .map(|i| {
+                let mut row = [0.0; AUTO_COLUMNS];
+                for (v, col) in row.iter_mut().zip(&columns) {
+                    *v = col[i];
+                }
+                HdrMergeAutoPoint::from_row(row)
+            })
+            .collect();
+        Ok(points)
+    }
+
+    fn set_merge_auto(&self, points: &[HdrMergeAutoPoint]) -> XCamResult<()> {
+        if points.windows(2).any(|w| w[0].key >= w[1].key) {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        // 参数表内存由 AIQ 持有，提交时改为指向本地缓冲区，由 SetAttrib 复制。
+        let mut buffers: Vec<Vec<f32>> = (0..AUTO_COLUMNS)
+            .map(|k| points.iter().map(|pt| pt.to_row()[k]).collect())
+            .collect();
+        let _lock = self.lock();
+        let mut attr = self.get_merge_attrib()?;
+        let columns = auto_arrays(&mut attr);
+        if auto_len(&columns)? != points.len() {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        for ((ptr, _), buf) in columns.into_iter().zip(buffers.iter_mut()) {
+            *ptr = buf.as_mut_ptr();
+        }
+        attr.opMode = merge_op_mode(OpMode::Auto)?;
+        self.set_merge_attrib(attr)?;
+        sync_tmo_mode(self, OpMode::Auto)
+    }
+}
+
+/// 自动参数表的数组个数。
+const AUTO_COLUMNS: usize = 8;
+
+/// 自动参数表各数组的指针及长度，均由 AIQ 持有。
+type AutoArrays<'a> = [(&'a mut *mut f32, &'a mut i32); AUTO_COLUMNS];
+
+impl HdrMergeAutoPoint {
+    fn to_row(self) -> [f32; AUTO_COLUMNS] {
+        [
+            self.key,
+            self.oe_curve.smooth,
+            self.oe_curve.offset,
+            self.move_coef,
+            self.md_curve_lm.smooth,
+            self.md_curve_lm.offset,
+            self.md_curve_ms.smooth,
+            self.md_curve_ms.offset,
+        ]
+    }
+
+    fn from_row(row: [f32; AUTO_COLUMNS]) -> Self {
+        Self {
+            key: row[0],
+            oe_curve: MergeCurve {
+                smooth: row[1],
+                offset: row[2],
+            },
+            move_coef: row[3],
+            md_curve_lm: MergeCurve {
+                smooth: row[4],
+                offset: row[5],
+            },
+            md_curve_ms: MergeCurve {
+                smooth: row[6],
+                offset: row[7],
+            },
+        }
+    }
+}
+
+macro_rules! auto_arrays {
+    ($oe:expr, $md:expr) => {
+        [
+            (&mut $oe.EnvLv, &mut $oe.EnvLv_len),
+            (&mut $oe.Smooth, &mut $oe.Smooth_len),
+            (&mut $oe.Offset, &mut $oe.Offset_len),
+            (&mut $md.MoveCoef, &mut $md.MoveCoef_len),
+            (&mut $md.LM_smooth, &mut $md.LM_smooth_len),
+            (&mut $md.LM_offset, &mut $md.LM_offset_len),
+            (&mut $md.MS_smooth, &mut $md.MS_smooth_len),
+            (&mut $md.MS_offset, &mut $md.MS_offset_len),
+        ]
+    };
+}
+
+#[cfg(any(feature = "v2_0", feature = "isp_hw_v21"))]
+fn auto_arrays(attr: &mut MergeAttr) -> AutoArrays<'_> {
+    let p = &mut attr.stAuto.MergeTuningPara;
+    auto_arrays!(p.OECurve, p.MDCurve)
+}
+
+/// V30 的自动参数按基准帧分为长帧及短帧两组，取当前基准帧对应的一组。
+#[cfg(not(any(feature = "v2_0", feature = "isp_hw_v21")))]
+fn auto_arrays(attr: &mut MergeAttr) -> AutoArrays<'_> {
+    let p = &mut attr.stAuto.MergeTuningPara;
+    match p.BaseFrm {
+        ffi::MergeBaseFrame_t::BASEFRAME_LONG => {
+            auto_arrays!(p.LongFrmModeData.OECurve, p.LongFrmModeData.MDCurve)
+        }
+        ffi::MergeBaseFrame_t::BASEFRAME_SHORT => {
+            auto_arrays!(p.ShortFrmModeData.OECurve, p.ShortFrmModeData.MDCurve)
+        }
+    }
+}
+
+/// 返回自动参数表的节点个数，各数组长度不一致时返回参数错误。
+fn auto_len(columns: &AutoArrays<'_>) -> XCamResult<usize> {
+    let len = *columns[0].1;
+    if len < 0 || columns.iter().any(|(_, n)| **n != len) {
+        return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+    }
+    Ok(len as usize)
+}
+
+#[cfg(feature = "v2_0")]
+fn merge_op_mode(mode: OpMode) -> XCamResult<ffi::merge_OpMode_t> {
+    use ffi::merge_OpMode_t::*;
+    match mode {
+        OpMode::Auto => Ok(MERGE_OPMODE_AUTO),
+        OpMode::Manual => Ok(MERGE_OPMODE_MANU),
+        _ => Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+}
+
+#[cfg(not(feature = "v2_0"))]
+fn merge_op_mode(mode: OpMode) -> XCamResult<ffi::merge_OpModeV21_t> {
+    use ffi::merge_OpModeV21_t::*;
+    match mode {
+        OpMode::Auto => Ok(MERGE_OPMODE_AUTO),
+        OpMode::Manual => Ok(MERGE_OPMODE_MANU),
+        _ => Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+}
+
+/// V2.x 的工作模式由 ATMO 决定，切换 AMERGE 模式时同步切换 ATMO，
+/// 使 [`HdrMerge::get_merge_mode`] 与之一致。
+#[cfg(feature = "v2_0")]
+fn sync_tmo_mode(ctx: &Context, mode: OpMode) -> XCamResult<()> {
+    use ffi::atmo_OpMode_t::*;
+    let op_mode = match mode {
+        OpMode::Auto => TMO_OPMODE_AUTO,
+        OpMode::Manual => TMO_OPMODE_MANU,
+        _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    };
+    let _lock = ctx.lock();
+    let mut attr = ffi::atmo_attrib_t::default();
+    unsafe {
+        XCamError::from(ffi::rk_aiq_user_api2_atmo_GetAttrib(
+            ctx.lock().as_ptr(),
+            &mut attr,
+        ))
+        .ok()?;
+        attr.opMode = op_mode;
+        XCamError::from(ffi::rk_aiq_user_api2_atmo_SetAttrib(
+            ctx.lock().as_ptr(),
+            attr,
+        ))
+        .ok()
+    }
+}
+
+#[cfg(not(feature = "v2_0"))]
+fn sync_tmo_mode(_ctx: &Context, _mode: OpMode) -> XCamResult<()> {
+    Ok(())
+}
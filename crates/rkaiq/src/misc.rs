@@ -29,13 +29,13 @@ pub trait Miscellaneous {
 
 impl Miscellaneous for Context {
     fn get_gray_mode(&self) -> GrayMode {
-        unsafe { ffi::rk_aiq_uapi_getGrayMode(self.internal.as_ptr()) }
+        unsafe { ffi::rk_aiq_uapi_getGrayMode(self.lock().as_ptr()) }
     }
 
     fn set_gray_mode<T: Into<GrayMode>>(&self, mode: T) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setGrayMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
@@ -46,7 +46,7 @@ impl Miscellaneous for Context {
         let mut info: FrameRateInfo = Default::default();
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getFrameRate(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut info,
             ))
             .ok()
@@ -57,7 +57,7 @@ impl Miscellaneous for Context {
     fn set_frame_rate<T: Into<FrameRateInfo>>(&self, info: T) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setFrameRate(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 info.into(),
             ))
             .ok()
@@ -69,7 +69,7 @@ impl Miscellaneous for Context {
         let mut flip: bool = false;
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getMirrorFlip(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut mirror,
                 &mut flip,
             ))
@@ -81,7 +81,7 @@ impl Miscellaneous for Context {
     fn set_mirror_flip(&self, mirror: bool, flip: bool, skip_frm_cnt: i32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setMirroFlip(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mirror,
                 flip,
                 skip_frm_cnt,
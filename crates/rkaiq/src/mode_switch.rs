@@ -0,0 +1,278 @@
+//! 工作模式切换
+//!
+//! 线性与 HDR 模式之间的切换需要停止 AIQ、以新模式重新准备后再启动，重新准备后此前通过
+//! uapi 设置的属性会恢复为 IQ 文件中的默认值。[`ModeSwitcher`] 记录分辨率、当前模式及用户
+//! 设置，切换后依次重新应用；配合 [`AutoHdr`] 可依据场景动态范围自动切换。
+//!
+//! ```no_run
+//! use rkaiq::mode_switch::{AutoHdr, ModeSwitcher};
+//! use rkaiq::prelude::*;
+//! use rkaiq::types::WorkingMode;
+//!
+//! let ctx = Context::new("m00_b_imx415 4-001a", "/etc/iqfiles").unwrap();
+//! let mut switcher = ModeSwitcher::new(&ctx, 3840, 2160, WorkingMode::Normal);
+//! switcher.start().unwrap();
+//! switcher.add_setting(|ctx| ctx.set_sharpness(60)).unwrap();
+//!
+//! let mut policy = AutoHdr::new(42.0, 34.0);
+//! loop {
+//!     if let Some(mode) = switcher.auto(&mut policy).unwrap() {
+//!         println!("switched to {:?}", mode);
+//!     }
+//!     std::thread::sleep(std::time::Duration::from_secs(1));
+//! }
+//! ```
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::sysctl::SystemControl;
+use super::types::{WorkingMode, XCamResult};
+use std::borrow::Borrow;
+
+/// 一个代表用户设置的类型，每次切换工作模式后依次重新应用。
+pub type Setting = Box<dyn Fn(&Context) -> XCamResult<()> + Send>;
+
+/// 一个描述工作模式切换的契定。
+pub trait WorkingModeSwitch {
+    /// 停止 AIQ，以新的工作模式重新准备并启动。
+    ///
+    /// 停止时保留补光灯、IR-CUT 等外部硬件状态。
+    fn switch_working_mode(&self, width: u32, height: u32, mode: WorkingMode) -> XCamResult<()>;
+
+    /// 由 AE 亮度直方图估计场景动态范围，单位：dB。
+    fn estimate_dynamic_range(&self) -> XCamResult<f32>;
+}
+
+impl WorkingModeSwitch for Context {
+    fn switch_working_mode(&self, width: u32, height: u32, mode: WorkingMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        self.stop(true)?;
+        self.prepare(width, height, mode)?;
+        self.start()
+    }
+
+    fn estimate_dynamic_range(&self) -> XCamResult<f32> {
+        let mut stats = ffi::rk_aiq_isp_stats_t::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_uapi2_sysctl_get3AStats(
+                self.lock().as_ptr(),
+                &mut stats,
+            ))
+            .ok()?;
+        }
+        let bins = &stats.aec_stats.ae_data.chn[0].rawhist_big.bins;
+        histogram_dynamic_range(bins)
+            .ok_or(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM))
+    }
+}
+
+/// 由亮度直方图估计动态范围，单位：dB。
+///
+/// 取累计 1% 及 99% 处的亮度之比，直方图为空时返回 `None`。
+pub fn histogram_dynamic_range(bins: &[u32]) -> Option<f32> {
+    let total: u64 = bins.iter().map(|&x| x as u64).sum();
+    if total == 0 {
+        return None;
+    }
+    let percentile = |p: u64| {
+        let mut acc = 0;
+        bins.iter()
+            .position(|&x| {
+                acc += x as u64;
+                acc * 100 >= total * p
+            })
+            .unwrap_or(bins.len() - 1)
+    };
+    let low = percentile(1) as f32 + 1.0;
+    let high = percentile(99) as f32 + 1.0;
+    Some(20.0 * (high / low).log10())
+}
+
+/// 一个代表自动 HDR 切换策略的类型。
+///
+/// 动态范围连续 `hold` 次高于进入阈值时切换到 HDR 模式，连续 `hold` 次低于退出阈值时
+/// 切换回线性模式，两个阈值之间的差值用于避免来回切换。
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutoHdr {
+    enter: f32,
+    leave: f32,
+    hdr_mode: WorkingMode,
+    hold: u32,
+    count: u32,
+}
+
+impl AutoHdr {
+    /// 创建切换策略，HDR 模式默认为 [`WorkingMode::IspHdr2`]，默认连续 3 次后切换。
+    ///
+    /// # Parameters
+    /// * `enter` - 进入 HDR 模式的动态范围阈值，单位：dB。
+    /// * `leave` - 退出 HDR 模式的动态范围阈值，单位：dB，应小于 `enter`。
+    pub fn new(enter: f32, leave: f32) -> Self {
+        Self {
+            enter,
+            leave: leave.min(enter),
+            hdr_mode: WorkingMode::IspHdr2,
+            hold: 3,
+            count: 0,
+        }
+    }
+
+    /// 设置进入 HDR 时使用的模式。
+    pub fn hdr_mode(mut self, mode: WorkingMode) -> Self {
+        self.hdr_mode = mode;
+        self
+    }
+
+    /// 设置切换前需连续满足条件的次数。
+    pub fn hold(mut self, count: u32) -> Self {
+        self.hold = count.max(1);
+        self
+    }
+
+    /// 输入一次动态范围测量值，需要切换时返回目标模式。
+    pub fn update(&mut self, current: WorkingMode, dynamic_range: f32) -> Option<WorkingMode> {
+        let target = if current.is_hdr() {
+            Some(WorkingMode::Normal).filter(|_| dynamic_range < self.leave)
+        } else {
+            Some(self.hdr_mode).filter(|_| dynamic_range > self.enter)
+        };
+        match target {
+            Some(_) => self.count += 1,
+            None => self.count = 0,
+        }
+        if self.count >= self.hold {
+            self.count = 0;
+            target
+        } else {
+            None
+        }
+    }
+
+    /// 清除累计次数。
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+/// 一个代表工作模式切换器的类型。
+///
+/// `C` 可为 `&Context` 或 `Arc<Context>`。
+pub struct ModeSwitcher<C: Borrow<Context>> {
+    ctx: C,
+    width: u32,
+    height: u32,
+    mode: WorkingMode,
+    settings: Vec<Setting>,
+}
+
+impl<C: Borrow<Context>> ModeSwitcher<C> {
+    /// 创建切换器，`mode` 为上下文当前或即将使用的工作模式。
+    pub fn new(ctx: C, width: u32, height: u32, mode: WorkingMode) -> Self {
+        Self {
+            ctx,
+            width,
+            height,
+            mode,
+            settings: Vec::new(),
+        }
+    }
+
+    /// 以当前工作模式准备并启动 AIQ，随后应用全部用户设置。
+    pub fn start(&self) -> XCamResult<()> {
+        let ctx = self.ctx.borrow();
+        ctx.prepare(self.width, self.height, self.mode)?;
+        ctx.start()?;
+        self.apply_settings()
+    }
+
+    /// 返回上下文。
+    pub fn context(&self) -> &Context {
+        self.ctx.borrow()
+    }
+
+    /// 返回当前工作模式。
+    pub fn mode(&self) -> WorkingMode {
+        self.mode
+    }
+
+    /// 添加一个用户设置，并立即应用。
+    pub fn add_setting<F>(&mut self, f: F) -> XCamResult<()>
+    where
+        F: Fn(&Context) -> XCamResult<()> + Send + 'static,
+    {
+        f(self.ctx.borrow())?;
+        self.settings.push(Box::new(f));
+        Ok(())
+    }
+
+    /// 清除全部用户设置，已生效的设置不受影响。
+    pub fn clear_settings(&mut self) {
+        self.settings.clear();
+    }
+
+    /// 切换到指定工作模式并重新应用用户设置，已处于该模式时返回 `false`。
+    ///
+    /// 以新模式准备或启动失败时尝试恢复原模式，并返回新模式下的错误。
+    pub fn switch_to(&mut self, mode: WorkingMode) -> XCamResult<bool> {
+        if mode == self.mode {
+            return Ok(false);
+        }
+        let ctx = self.ctx.borrow();
+        if let Err(err) = ctx.switch_working_mode(self.width, self.height, mode) {
+            if ctx
+                .switch_working_mode(self.width, self.height, self.mode)
+                .is_ok()
+            {
+                let _ = self.apply_settings();
+            }
+            return Err(err);
+        }
+        self.mode = mode;
+        self.apply_settings()?;
+        Ok(true)
+    }
+
+    /// 估计当前场景动态范围并按策略切换，发生切换时返回新模式。
+    pub fn auto(&mut self, policy: &mut AutoHdr) -> XCamResult<Option<WorkingMode>> {
+        let dynamic_range = self.ctx.borrow().estimate_dynamic_range()?;
+        match policy.update(self.mode, dynamic_range) {
+            Some(mode) => self.switch_to(mode).map(|_| Some(mode)),
+            None => Ok(None),
+        }
+    }
+
+    fn apply_settings(&self) -> XCamResult<()> {
+        let ctx = self.ctx.borrow();
+        self.settings.iter().try_for_each(|f| f(ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_hdr() {
+        let mut bins = [0u32; 256];
+        bins[0] = 50;
+        bins[255] = 50;
+        let dr = histogram_dynamic_range(&bins).unwrap();
+        assert!((dr - 48.16).abs() < 0.01);
+        assert_eq!(histogram_dynamic_range(&[0; 256]), None);
+
+        let mut policy = AutoHdr::new(42.0, 34.0).hold(2);
+        assert_eq!(policy.update(WorkingMode::Normal, 45.0), None);
+        assert_eq!(policy.update(WorkingMode::Normal, 40.0), None);
+        assert_eq!(policy.update(WorkingMode::Normal, 45.0), None);
+        assert_eq!(
+            policy.update(WorkingMode::Normal, 45.0),
+            Some(WorkingMode::IspHdr2)
+        );
+        assert_eq!(policy.update(WorkingMode::IspHdr2, 38.0), None);
+        assert_eq!(policy.update(WorkingMode::IspHdr2, 30.0), None);
+        assert_eq!(
+            policy.update(WorkingMode::IspHdr2, 30.0),
+            Some(WorkingMode::Normal)
+        );
+    }
+}
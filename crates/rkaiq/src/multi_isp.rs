@@ -0,0 +1,270 @@
+//! 多 ISP 拼接模式
+//!
+//! 高分辨率传感器（如 RK3588 上的 8K 传感器）超出单个 ISP 的处理能力时，librkaiq 以多 ISP
+//! 拼接（unite）模式工作：图像按列均分给各个 ISP，每个 ISP 的输入在分界处额外多取
+//! `multi_isp_extended_pixel` 个像素用于滤波，输出时再裁掉重叠部分。
+//!
+//! [`MultiIspLayout`] 描述这种切分方式，并在整幅图像坐标与各 ISP 局部坐标之间转换
+//! ROI、测光窗口等区域。
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::sysctl::SystemControl;
+use super::types::{Rect, StaticInfo, WorkingMode, XCamResult};
+
+/// 拼接模式下参与处理的 ISP 数量。
+pub const MULTI_ISP_COUNT: u32 = 2;
+
+/// 一个代表单个 ISP 处理区域的类型，坐标均为整幅图像坐标。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IspRegion {
+    /// ISP 序号，从左到右。
+    pub index: usize,
+    /// 输入区域，含扩展像素。
+    pub input: Rect,
+    /// 有效输出区域，不含重叠部分。
+    pub output: Rect,
+}
+
+/// 一个代表多 ISP 切分方式的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MultiIspLayout {
+    width: u32,
+    height: u32,
+    extended: u32,
+}
+
+impl MultiIspLayout {
+    /// 创建切分方式。
+    ///
+    /// 宽度须能被 ISP 数量整除，且每份宽度须大于扩展像素数，否则返回 `None`。
+    pub fn new(width: u32, height: u32, extended: u32) -> Option<Self> {
+        let part = width / MULTI_ISP_COUNT;
+        if !width.is_multiple_of(MULTI_ISP_COUNT) || part <= extended || height == 0 {
+            return None;
+        }
+        Some(Self {
+            width,
+            height,
+            extended,
+        })
+    }
+
+    /// 由静态信息创建，传感器未工作在拼接模式时返回 `None`。
+    pub fn from_static_info(info: &StaticInfo, width: u32, height: u32) -> Option<Self> {
+        multi_isp_extended_pixel(info).and_then(|ext| Self::new(width, height, ext))
+    }
+
+    /// 返回整幅图像宽度。
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// 返回整幅图像高度。
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// 返回扩展像素数。
+    pub fn extended_pixel(&self) -> u32 {
+        self.extended
+    }
+
+    /// 返回 ISP 数量。
+    pub fn isp_count(&self) -> usize {
+        MULTI_ISP_COUNT as usize
+    }
+
+    /// 返回各 ISP 的处理区域。
+    pub fn regions(&self) -> Vec<IspRegion> {
+        (0..self.isp_count()).map(|i| self.region(i)).collect()
+    }
+
+    /// 返回指定 ISP 的处理区域。
+    ///
+    /// # Panics
+    ///
+    /// 序号超出 ISP 数量时 panic。
+    pub fn region(&self, index: usize) -> IspRegion {
+        assert!(index < self.isp_count());
+        let part = self.width / MULTI_ISP_COUNT;
+        let left = part * index as u32;
+        let in_left = left.saturating_sub(self.extended);
+        let in_right = (left + part + self.extended).min(self.width);
+        IspRegion {
+            index,
+            input: rect(in_left, 0, in_right - in_left, self.height),
+            output: rect(left, 0, part, self.height),
+        }
+    }
+
+    /// 返回整幅图像中某点所属的 ISP 序号。
+    pub fn isp_of(&self, x: u32) -> usize {
+        let part = self.width / MULTI_ISP_COUNT;
+        ((x / part) as usize).min(self.isp_count() - 1)
+    }
+
+    /// 将整幅图像中的区域转换为各 ISP 的局部区域。
+    ///
+    /// 区域按各 ISP 的有效输出范围裁剪，返回与之相交的 ISP 序号及其局部坐标下的区域。
+    pub fn to_isp(&self, area: Rect) -> Vec<(usize, Rect)> {
+        self.regions()
+            .into_iter()
+            .filter_map(|r| {
+                intersect(area, r.output).map(|mut a| {
+                    a.left -= r.input.left;
+                    (r.index, a)
+                })
+            })
+            .collect()
+    }
+
+    /// 将指定 ISP 局部坐标下的区域转换为整幅图像中的区域。
+    pub fn from_isp(&self, index: usize, area: Rect) -> Rect {
+        let r = self.region(index);
+        Rect {
+            left: area.left + r.input.left,
+            ..area
+        }
+    }
+
+    /// 将整幅图像上的测光权重表（行优先，`cols` 列）按 ISP 切分。
+    ///
+    /// 每个 ISP 获得覆盖其有效输出范围的列，列数须能被 ISP 数量整除。
+    pub fn split_grid<T: Copy>(&self, grid: &[T], cols: usize) -> Option<Vec<Vec<T>>> {
+        let count = self.isp_count();
+        if cols == 0 || !cols.is_multiple_of(count) || !grid.len().is_multiple_of(cols) {
+            return None;
+        }
+        let part = cols / count;
+        let parts = (0..count)
+            .map(|i| {
+                grid.chunks(cols)
+                    .flat_map(|row| row[i * part..(i + 1) * part].iter().copied())
+                    .collect()
+            })
+            .collect();
+        Some(parts)
+    }
+}
+
+/// 返回传感器是否工作在多 ISP 拼接模式，是则返回扩展像素数。
+#[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+pub fn multi_isp_extended_pixel(info: &StaticInfo) -> Option<u32> {
+    if info.is_multi_isp_mode {
+        Some(info.multi_isp_extended_pixel as u32)
+    } else {
+        None
+    }
+}
+
+/// 返回传感器是否工作在多 ISP 拼接模式，是则返回扩展像素数。
+#[cfg(not(any(feature = "v3_0", feature = "v4_0", feature = "v5_0")))]
+pub fn multi_isp_extended_pixel(_info: &StaticInfo) -> Option<u32> {
+    None
+}
+
+/// 一个描述多 ISP 相关操作的契定。
+pub trait MultiIsp {
+    /// 按静态信息检查分辨率后准备 AIQ，拼接模式下返回切分方式。
+    ///
+    /// 拼接模式下 `width` 须为整幅图像宽度，且满足 [`MultiIspLayout::new`] 的要求，
+    /// 否则返回参数错误。
+    fn prepare_for(
+        &self,
+        info: &StaticInfo,
+        width: u32,
+        height: u32,
+        mode: WorkingMode,
+    ) -> XCamResult<Option<MultiIspLayout>>;
+
+    /// 以整幅图像坐标设置裁剪区域，拼接模式下裁剪区域须横跨分界线。
+    fn set_full_crop(&self, layout: Option<&MultiIspLayout>, crop: Rect) -> XCamResult<()>;
+}
+
+impl MultiIsp for Context {
+    fn prepare_for(
+        &self,
+        info: &StaticInfo,
+        width: u32,
+        height: u32,
+        mode: WorkingMode,
+    ) -> XCamResult<Option<MultiIspLayout>> {
+        let layout = match multi_isp_extended_pixel(info) {
+            Some(ext) => Some(
+                MultiIspLayout::new(width, height, ext)
+                    .ok_or(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM))?,
+            ),
+            None => None,
+        };
+        self.prepare(width, height, mode)?;
+        Ok(layout)
+    }
+
+    fn set_full_crop(&self, layout: Option<&MultiIspLayout>, crop: Rect) -> XCamResult<()> {
+        if let Some(layout) = layout {
+            if layout.to_isp(crop).len() != layout.isp_count() {
+                return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+            }
+        }
+        self.set_crop(crop)
+    }
+}
+
+fn rect(left: u32, top: u32, width: u32, height: u32) -> Rect {
+    Rect {
+        left: left as _,
+        top: top as _,
+        width: width as _,
+        height: height as _,
+    }
+}
+
+fn intersect(a: Rect, b: Rect) -> Option<Rect> {
+    let left = a.left.max(b.left);
+    let top = a.top.max(b.top);
+    let right = (a.left + a.width).min(b.left + b.width);
+    let bottom = (a.top + a.height).min(b.top + b.height);
+    if right > left && bottom > top {
+        Some(Rect {
+            left,
+            top,
+            width: right - left,
+            height: bottom - top,
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        assert!(MultiIspLayout::new(7681, 4320, 128).is_none());
+        let layout = MultiIspLayout::new(7680, 4320, 128).unwrap();
+        let regions = layout.regions();
+        assert_eq!(regions[0].input, rect(0, 0, 3968, 4320));
+        assert_eq!(regions[1].input, rect(3712, 0, 3968, 4320));
+        assert_eq!(regions[1].output, rect(3840, 0, 3840, 4320));
+        assert_eq!(layout.isp_of(3839), 0);
+        assert_eq!(layout.isp_of(7679), 1);
+
+        let roi = rect(3000, 100, 2000, 200);
+        let parts = layout.to_isp(roi);
+        assert_eq!(
+            parts,
+            vec![
+                (0, rect(3000, 100, 840, 200)),
+                (1, rect(128, 100, 1160, 200))
+            ]
+        );
+        assert_eq!(layout.from_isp(1, parts[1].1), rect(3840, 100, 1160, 200));
+
+        let grid: Vec<u8> = (0..8).collect();
+        let split = layout.split_grid(&grid, 4).unwrap();
+        assert_eq!(split, vec![vec![0, 1, 4, 5], vec![2, 3, 6, 7]]);
+    }
+}
@@ -3,6 +3,7 @@
 //! AWB 模块的功能是通过改变拍摄设备的色彩通道的增益，
 //！对色温环境所造成的颜色偏差和拍摄设备本身所固有的色彩通道增益的偏差进行统一补偿，
 //！从而让获得的图像能正确反映物体的真实色彩。
+use super::batch::Batching;
 use super::context::Context;
 use super::error::XCamError;
 use super::ffi;
@@ -38,19 +39,16 @@ impl NoiseRemoval for Context {
     fn get_nr_mode(&self) -> XCamResult<OpMode> {
         let mut mode = ffi::opMode_t::OP_INVAL;
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_getNRMode(
-                self.internal.as_ptr(),
-                &mut mode,
-            ))
-            .ok()
-            .map(|_| mode.into())
+            XCamError::from(ffi::rk_aiq_uapi_getNRMode(self.lock().as_ptr(), &mut mode))
+                .ok()
+                .map(|_| mode.into())
         }
     }
 
     fn set_nr_mode(&self, mode: OpMode) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setNRMode(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 mode.into(),
             ))
             .ok()
@@ -61,7 +59,7 @@ impl NoiseRemoval for Context {
         let mut strength: u32 = 0;
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getANRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut strength,
             ))
             .ok()
@@ -72,11 +70,7 @@ impl NoiseRemoval for Context {
     #[cfg(any(feature = "v1_0", feature = "v2_0", feature = "v3_0"))]
     fn set_anr_strength(&self, strength: u32) -> XCamResult<()> {
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_setANRStrth(
-                self.internal.as_ptr(),
-                strength,
-            ))
-            .ok()
+            XCamError::from(ffi::rk_aiq_uapi_setANRStrth(self.lock().as_ptr(), strength)).ok()
         }
     }
 
@@ -84,7 +78,7 @@ impl NoiseRemoval for Context {
     fn set_anr_strength(&self, strength: u32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setANRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 strength,
             ))
             .ok()
@@ -96,7 +90,7 @@ impl NoiseRemoval for Context {
         let mut strength: u32 = 0;
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getMSpaNRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut on,
                 &mut strength,
             ))
@@ -109,7 +103,7 @@ impl NoiseRemoval for Context {
     fn set_ms_nr_strength(&self, on: bool, strength: u32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setMSpaNRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 on,
                 strength,
             ))
@@ -121,7 +115,7 @@ impl NoiseRemoval for Context {
     fn set_ms_nr_strength(&self, on: bool, strength: u32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setMSpaNRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 on,
                 strength,
             ))
@@ -134,7 +128,7 @@ impl NoiseRemoval for Context {
         let mut strength: u32 = 0;
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getMTNRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut on,
                 &mut strength,
             ))
@@ -147,7 +141,7 @@ impl NoiseRemoval for Context {
     fn set_mt_nr_strength(&self, on: bool, strength: u32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_setMTNRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 on,
                 strength,
             ))
@@ -159,7 +153,7 @@ impl NoiseRemoval for Context {
     fn set_mt_nr_strength(&self, on: bool, strength: u32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_setMTNRStrth(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 on,
                 strength,
             ))
@@ -168,202 +162,226 @@ impl NoiseRemoval for Context {
     }
 
     fn enable_bayernr2d(&self) -> XCamResult<()> {
-        unsafe {
-            let mut attr: ffi::rk_aiq_bayernr_attrib_v2_t = Default::default();
-            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_GetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()?;
-            match attr.eMode {
-                ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_AUTO => {
-                    attr.stAuto.bayernr2DEn = 1;
-                }
-                ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_MANUAL => {
-                    attr.stManual.bayernr2DEn = 1;
-                }
-                _ => {}
-            }
-            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_SetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()
-        }
+        self.batch(|b| {
+            b.enable_bayernr2d();
+        })
+        .map_err(XCamError::from)
     }
 
     fn disable_bayernr2d(&self) -> XCamResult<()> {
-        unsafe {
-            let mut attr: ffi::rk_aiq_bayernr_attrib_v2_t = Default::default();
-            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_GetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()?;
-            match attr.eMode {
-                ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_AUTO => {
-                    attr.stAuto.bayernr2DEn = 0;
-                }
-                ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_MANUAL => {
-                    attr.stManual.bayernr2DEn = 0;
-                }
-                _ => {}
-            }
-            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_SetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()
-        }
+        self.batch(|b| {
+            b.disable_bayernr2d();
+        })
+        .map_err(XCamError::from)
     }
 
     fn enable_bayernr3d(&self) -> XCamResult<()> {
-        unsafe {
-            let mut attr: ffi::rk_aiq_bayernr_attrib_v2_t = Default::default();
-            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_GetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()?;
-            match attr.eMode {
-                ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_AUTO => {
-                    attr.stAuto.bayernr3DEn = 1;
-                }
-                ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_MANUAL => {
-                    attr.stManual.bayernr3DEn = 1;
-                }
-                _ => {}
-            }
-            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_SetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()
-        }
+        self.batch(|b| {
+            b.enable_bayernr3d();
+        })
+        .map_err(XCamError::from)
     }
 
     fn disable_bayernr3d(&self) -> XCamResult<()> {
-        unsafe {
-            let mut attr: ffi::rk_aiq_bayernr_attrib_v2_t = Default::default();
-            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_GetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()?;
-            match attr.eMode {
-                ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_AUTO => {
-                    attr.stAuto.bayernr3DEn = 0;
-                }
-                ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_MANUAL => {
-                    attr.stManual.bayernr3DEn = 0;
-                }
-                _ => {}
-            }
-            XCamError::from(ffi::rk_aiq_user_api2_abayernrV2_SetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()
-        }
+        self.batch(|b| {
+            b.disable_bayernr3d();
+        })
+        .map_err(XCamError::from)
     }
 
     fn enable_cnr(&self) -> XCamResult<()> {
-        unsafe {
-            let mut attr: ffi::rk_aiq_cnr_attrib_v1_t = Default::default();
-            XCamError::from(ffi::rk_aiq_user_api2_acnrV1_GetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()?;
-            match attr.eMode {
-                ffi::Acnr_OPMode_t::ACNR_OP_MODE_AUTO => {
-                    attr.stAuto.cnrEn = 1;
-                }
-                ffi::Acnr_OPMode_t::ACNR_OP_MODE_MANUAL => {
-                    attr.stManual.cnrEn = 1;
-                }
-                _ => {}
-            }
-            XCamError::from(ffi::rk_aiq_user_api2_acnrV1_SetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()
-        }
+        self.batch(|b| {
+            b.enable_cnr();
+        })
+        .map_err(XCamError::from)
     }
 
     fn disable_cnr(&self) -> XCamResult<()> {
-        unsafe {
-            let mut attr: ffi::rk_aiq_cnr_attrib_v1_t = Default::default();
-            XCamError::from(ffi::rk_aiq_user_api2_acnrV1_GetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()?;
-            match attr.eMode {
-                ffi::Acnr_OPMode_t::ACNR_OP_MODE_AUTO => {
-                    attr.stAuto.cnrEn = 0;
-                }
-                ffi::Acnr_OPMode_t::ACNR_OP_MODE_MANUAL => {
-                    attr.stManual.cnrEn = 0;
-                }
-                _ => {}
-            }
-            XCamError::from(ffi::rk_aiq_user_api2_acnrV1_SetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()
-        }
+        self.batch(|b| {
+            b.disable_cnr();
+        })
+        .map_err(XCamError::from)
     }
 
     fn enable_ynr(&self) -> XCamResult<()> {
-        unsafe {
-            let mut attr: ffi::rk_aiq_ynr_attrib_v2_t = Default::default();
-            XCamError::from(ffi::rk_aiq_user_api2_aynrV2_GetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()?;
-            match attr.eMode {
-                ffi::Aynr_OPMode_t::AYNR_OP_MODE_AUTO => {
-                    attr.stAuto.ynrEn = 1;
-                }
-                ffi::Aynr_OPMode_t::AYNR_OP_MODE_MANUAL => {
-                    attr.stManual.ynrEn = 1;
-                }
-                _ => {}
-            }
-            XCamError::from(ffi::rk_aiq_user_api2_aynrV2_SetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()
-        }
+        self.batch(|b| {
+            b.enable_ynr();
+        })
+        .map_err(XCamError::from)
     }
 
     fn disable_ynr(&self) -> XCamResult<()> {
-        unsafe {
-            let mut attr: ffi::rk_aiq_ynr_attrib_v2_t = Default::default();
-            XCamError::from(ffi::rk_aiq_user_api2_aynrV2_GetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()?;
-            match attr.eMode {
-                ffi::Aynr_OPMode_t::AYNR_OP_MODE_AUTO => {
-                    attr.stAuto.ynrEn = 0;
-                }
-                ffi::Aynr_OPMode_t::AYNR_OP_MODE_MANUAL => {
-                    attr.stManual.ynrEn = 0;
-                }
-                _ => {}
-            }
-            XCamError::from(ffi::rk_aiq_user_api2_aynrV2_SetAttrib(
-                self.internal.as_ptr(),
-                &mut attr,
-            ))
-            .ok()
+        self.batch(|b| {
+            b.disable_ynr();
+        })
+        .map_err(XCamError::from)
+    }
+}
+
+/// 一个代表按 ISO 索引的参数表中单个档位的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IsoParams<T> {
+    /// 该档位对应的 ISO 值。
+    pub iso: f32,
+    /// 该档位的参数。
+    pub params: T,
+}
+
+/// 一个描述可在两组参数间线性插值的契定。
+pub trait IsoInterpolate: Sized {
+    /// 返回 `self` 与 `other` 之间按比例 `t`（取值范围 [0,1]）插值的结果。
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl IsoInterpolate for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl IsoInterpolate for i32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        (*self as f32).lerp(&(*other as f32), t).round() as i32
+    }
+}
+
+impl IsoInterpolate for i16 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        (*self as f32).lerp(&(*other as f32), t).round() as i16
+    }
+}
+
+impl IsoInterpolate for bool {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        if t < 0.5 {
+            *self
+        } else {
+            *other
+        }
+    }
+}
+
+impl<T: IsoInterpolate + Copy + Default, const N: usize> IsoInterpolate for [T; N] {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mut out = [T::default(); N];
+        for (i, v) in out.iter_mut().enumerate() {
+            *v = self[i].lerp(&other[i], t);
         }
+        out
+    }
+}
+
+/// 按 ISO 在参数表中插值出当前应使用的参数。
+///
+/// 参数表须按 ISO 递增排列；超出参数表范围的 ISO 取两端档位的参数。
+/// 参数表为空时返回 `None`。
+pub fn interpolate_iso<T: IsoInterpolate + Clone>(table: &[IsoParams<T>], iso: f32) -> Option<T> {
+    let first = table.first()?;
+    let last = table.last()?;
+    if iso <= first.iso {
+        return Some(first.params.clone());
+    }
+    if iso >= last.iso {
+        return Some(last.params.clone());
+    }
+    table.windows(2).find_map(|w| {
+        if iso >= w[0].iso && iso <= w[1].iso {
+            let span = w[1].iso - w[0].iso;
+            let t = if span > 0.0 {
+                (iso - w[0].iso) / span
+            } else {
+                0.0
+            };
+            Some(w[0].params.lerp(&w[1].params, t))
+        } else {
+            None
+        }
+    })
+}
+
+/// 校验参数表的档位数及 ISO 单调性。
+pub(crate) fn validate_iso_table<T>(table: &[IsoParams<T>], len: usize) -> XCamResult<()> {
+    if table.len() != len || table.windows(2).any(|w| w[0].iso >= w[1].iso) {
+        return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+    }
+    Ok(())
+}
+
+/// 校验参数表中各 ISO 档位共用的字段取值一致。
+pub(crate) fn validate_shared<T, K: PartialEq>(
+    table: &[IsoParams<T>],
+    field: impl Fn(&T) -> K,
+) -> XCamResult<()> {
+    let mut values = table.iter().map(|v| field(&v.params));
+    match values.next() {
+        Some(first) if values.any(|v| v != first) => {
+            Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 校验参数位于闭区间 `[min, max]` 内。
+pub(crate) fn check_range<T: PartialOrd>(val: T, min: T, max: T) -> XCamResult<()> {
+    if (min..=max).contains(&val) {
+        Ok(())
+    } else {
+        Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Vec<IsoParams<f32>> {
+        vec![
+            IsoParams {
+                iso: 100.0,
+                params: 1.0,
+            },
+            IsoParams {
+                iso: 200.0,
+                params: 3.0,
+            },
+            IsoParams {
+                iso: 400.0,
+                params: 7.0,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_interpolate_iso() {
+        let t = table();
+        assert_eq!(interpolate_iso(&t, 50.0), Some(1.0));
+        assert_eq!(interpolate_iso(&t, 150.0), Some(2.0));
+        assert_eq!(interpolate_iso(&t, 300.0), Some(5.0));
+        assert_eq!(interpolate_iso(&t, 800.0), Some(7.0));
+        assert_eq!(interpolate_iso::<f32>(&[], 100.0), None);
+    }
+
+    #[test]
+    fn test_validate_shared() {
+        let t = table();
+        assert!(validate_shared(&t, |_| [0, 16, 32]).is_ok());
+        assert!(validate_shared(&t, |p| *p).is_err());
+        assert!(validate_shared::<f32, i32>(&[], |_| 0).is_ok());
+    }
+
+    #[test]
+    fn test_check_range() {
+        assert!(check_range(0u32, 0, 100).is_ok());
+        assert!(check_range(100u32, 0, 100).is_ok());
+        assert!(check_range(101u32, 0, 100).is_err());
+        assert!(check_range(f32::NAN, 0.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_interpolate_array() {
+        let a = [0i32, 10, 20];
+        let b = [10i32, 20, 40];
+        assert_eq!(a.lerp(&b, 0.5), [5, 15, 30]);
     }
 }
@@ -3,15 +3,27 @@ pub use super::ae::AutoExposure;
 pub use super::af::AutoFocus;
 pub use super::asd::AmbientDetection;
 pub use super::awb::AutoWhiteBalance;
+pub use super::batch::Batching;
+pub use super::bayernr::BayerNoiseRemoval;
+pub use super::cnr::ChromaNoiseRemoval;
 pub use super::context::Context;
+pub use super::debayer::Debayer;
 pub use super::defog::Defog;
+pub use super::degamma::Degamma;
+#[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+pub use super::drc::DynamicRangeCompression;
 pub use super::error::XCamError;
 pub use super::fec::FEC;
 pub use super::gamma::Gamma;
+pub use super::gic::GreenImbalanceCorrection;
 pub use super::hdr::HighDynamicRange;
 pub use super::ldch::LDCH;
+pub use super::merge::HdrMerge;
 pub use super::misc::Miscellaneous;
+pub use super::mode_switch::WorkingModeSwitch;
+pub use super::multi_isp::MultiIsp;
 pub use super::nr::NoiseRemoval;
 pub use super::sharpen::Sharpen;
 pub use super::sysctl::SystemControl;
 pub use super::types::XCamResult;
+pub use super::ynr::LumaNoiseRemoval;
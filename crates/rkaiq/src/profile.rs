@@ -0,0 +1,436 @@
+//! ISP 配置档案
+//!
+//! [`IspProfile`] 一次性采集运行中 [`Context`] 各模块的当前状态，并可按固定顺序整体应用，
+//! 用于在重启后恢复画面风格，或比较两份配置之间的差异。
+use super::acm::{AcpLevels, AutoColorManagment};
+use super::ae::AutoExposure;
+use super::awb::AutoWhiteBalance;
+use super::context::Context;
+use super::defog::Defog;
+use super::error::XCamError;
+use super::gamma::{gamma_is_manual, Gamma, GammaCurve, GammaSegment};
+use super::nr::NoiseRemoval;
+use super::sharpen::Sharpen;
+use super::types::{GammaAttr, OpMode, WbGain, XCamResult};
+use std::fmt;
+
+/// 一个描述配置档案所含模块的枚举，按应用顺序排列。
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProfileModule {
+    Exposure,
+    WhiteBalance,
+    NoiseRemoval,
+    Sharpen,
+    Gamma,
+    Defog,
+    Color,
+}
+
+impl fmt::Display for ProfileModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProfileModule::Exposure => "AE",
+            ProfileModule::WhiteBalance => "AWB",
+            ProfileModule::NoiseRemoval => "NR",
+            ProfileModule::Sharpen => "Sharpen",
+            ProfileModule::Gamma => "Gamma",
+            ProfileModule::Defog => "Defog",
+            ProfileModule::Color => "ACP",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 一个代表曝光配置的类型。
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExposureProfile {
+    /// 曝光模式。
+    pub mode: OpMode,
+    /// 增益范围。
+    pub gain_range: (f32, f32),
+    /// 曝光时间范围。
+    pub time_range: (f32, f32),
+}
+
+/// 一个代表白平衡配置的类型。
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WhiteBalanceProfile {
+    /// 白平衡模式。
+    pub mode: OpMode,
+    /// 手动模式下的 R、Gr、Gb、B 增益。
+    pub gains: [f32; 4],
+}
+
+/// 一个代表降噪配置的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NoiseProfile {
+    /// 降噪模式。
+    pub mode: OpMode,
+    /// 整体降噪强度。
+    pub strength: u32,
+    /// 空域降噪开关及强度。
+    pub spatial: (bool, u32),
+    /// 时域降噪开关及强度。
+    pub temporal: (bool, u32),
+}
+
+/// 一个代表去雾配置的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DefogProfile {
+    /// 去雾模式，不支持查询时为 `None`。
+    pub mode: Option<OpMode>,
+    /// 去雾强度。
+    pub strength: u32,
+}
+
+/// 一个代表 ISP 配置档案的类型。
+///
+/// 各模块字段为 `None` 表示该模块未被采集，应用时保持不变。
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IspProfile {
+    pub exposure: Option<ExposureProfile>,
+    pub white_balance: Option<WhiteBalanceProfile>,
+    pub noise: Option<NoiseProfile>,
+    /// 锐化等级。
+    pub sharpness: Option<u32>,
+    /// 手动 Gamma 曲线，Gamma 处于自动模式时不采集。
+    pub gamma: Option<GammaCurve>,
+    pub defog: Option<DefogProfile>,
+    pub color: Option<AcpLevels>,
+}
+
+/// 一个代表两份配置档案之间单项差异的类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProfileChange {
+    /// 所属模块。
+    pub module: ProfileModule,
+    /// 字段名称。
+    pub field: &'static str,
+    /// 原值，`None` 表示未采集。
+    pub from: Option<String>,
+    /// 新值，`None` 表示未采集。
+    pub to: Option<String>,
+}
+
+impl fmt::Display for ProfileChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let from = self.from.as_deref().unwrap_or("-");
+        let to = self.to.as_deref().unwrap_or("-");
+        write!(f, "{}.{}: {} -> {}", self.module, self.field, from, to)
+    }
+}
+
+/// 一个代表配置档案应用失败的类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProfileApplyError {
+    /// 应用失败的模块。
+    pub module: ProfileModule,
+    /// 失败原因。
+    pub error: XCamError,
+    /// 已应用的模块，已尝试回滚。
+    pub applied: Vec<ProfileModule>,
+    /// 回滚失败的模块及原因，为空表示已完整恢复至应用前的状态。应用前无法读取状态的模块
+    /// 同样计入，原因为读取时的错误。
+    pub rollback_failures: Vec<(ProfileModule, XCamError)>,
+}
+
+impl fmt::Display for ProfileApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Apply {} failed: {}", self.module, self.error)?;
+        if !self.rollback_failures.is_empty() {
+            write!(f, ", rollback failed on")?;
+            for (m, e) in &self.rollback_failures {
+                write!(f, " {}({})", m, e)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ProfileApplyError {}
+
+impl IspProfile {
+    /// 采集运行中 Context 各模块的当前状态。
+    ///
+    /// 当前版本不支持或查询失败的模块不会被采集。
+    pub fn capture(ctx: &Context) -> Self {
+        Self {
+            exposure: capture_exposure(ctx).ok(),
+            white_balance: capture_white_balance(ctx).ok(),
+            noise: capture_noise(ctx).ok(),
+            sharpness: ctx.get_sharpness().ok(),
+            gamma: capture_gamma(ctx).ok().flatten(),
+            defog: capture_defog(ctx).ok(),
+            color: ctx.get_acp_levels().ok(),
+        }
+    }
+
+    /// 返回档案中已采集的模块，按应用顺序排列。
+    pub fn modules(&self) -> Vec<ProfileModule> {
+        use ProfileModule::*;
+        [
+            (Exposure, self.exposure.is_some()),
+            (WhiteBalance, self.white_balance.is_some()),
+            (NoiseRemoval, self.noise.is_some()),
+            (Sharpen, self.sharpness.is_some()),
+            (Gamma, self.gamma.is_some()),
+            (Defog, self.defog.is_some()),
+            (Color, self.color.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(m, present)| present.then_some(m))
+        .collect()
+    }
+
+    /// 按 [`ProfileModule`] 的顺序将档案应用至 Context。
+    ///
+    /// 应用在持有上下文锁期间进行。任一模块失败时，已应用的模块将回滚至应用前的状态，并在
+    /// 错误中报告失败的模块及回滚结果。
+    pub fn apply(&self, ctx: &Context) -> Result<(), ProfileApplyError> {
+        let _lock = ctx.lock();
+        let backup = Backup::capture(ctx);
+        let mut applied = Vec::new();
+        for module in self.modules() {
+            if let Err(error) = self.apply_module(ctx, module) {
+                let rollback_failures = applied
+                    .iter()
+                    .rev()
+                    .filter_map(|&m| backup.restore(ctx, m, self).err().map(|e| (m, e)))
+                    .collect();
+                return Err(ProfileApplyError {
+                    module,
+                    error,
+                    applied,
+                    rollback_failures,
+                });
+            }
+            applied.push(module);
+        }
+        Ok(())
+    }
+
+    /// 比较两份档案，返回从 `self` 到 `other` 的全部差异。
+    pub fn diff(&self, other: &Self) -> Vec<ProfileChange> {
+        let mut changes = Vec::new();
+        let mut push = |module, field, from: Option<String>, to: Option<String>| {
+            if from != to {
+                changes.push(ProfileChange {
+                    module,
+                    field,
+                    from,
+                    to,
+                });
+            }
+        };
+        macro_rules! field {
+            ($module:expr, $a:expr, $b:expr, $name:ident) => {
+                push(
+                    $module,
+                    stringify!($name),
+                    $a.as_ref().map(|v| format!("{:?}", v.$name)),
+                    $b.as_ref().map(|v| format!("{:?}", v.$name)),
+                )
+            };
+        }
+        use ProfileModule::*;
+        field!(Exposure, self.exposure, other.exposure, mode);
+        field!(Exposure, self.exposure, other.exposure, gain_range);
+        field!(Exposure, self.exposure, other.exposure, time_range);
+        field!(WhiteBalance, self.white_balance, other.white_balance, mode);
+        field!(WhiteBalance, self.white_balance, other.white_balance, gains);
+        field!(NoiseRemoval, self.noise, other.noise, mode);
+        field!(NoiseRemoval, self.noise, other.noise, strength);
+        field!(NoiseRemoval, self.noise, other.noise, spatial);
+        field!(NoiseRemoval, self.noise, other.noise, temporal);
+        push(
+            Sharpen,
+            "level",
+            self.sharpness.map(|v| v.to_string()),
+            other.sharpness.map(|v| v.to_string()),
+        );
+        field!(Gamma, self.gamma, other.gamma, y);
+        field!(Defog, self.defog, other.defog, mode);
+        field!(Defog, self.defog, other.defog, strength);
+        field!(Color, self.color, other.color, brightness);
+        field!(Color, self.color, other.color, contrast);
+        field!(Color, self.color, other.color, saturation);
+        field!(Color, self.color, other.color, hue);
+        changes
+    }
+
+    fn apply_module(&self, ctx: &Context, module: ProfileModule) -> XCamResult<()> {
+        match module {
+            ProfileModule::Exposure => self.exposure.map_or(Ok(()), |v| {
+                ctx.set_exp_gain_range(v.gain_range.0, v.gain_range.1)?;
+                ctx.set_exp_time_range(v.time_range.0, v.time_range.1)?;
+                ctx.set_exp_mode(v.mode)
+            }),
+            ProfileModule::WhiteBalance => self.white_balance.map_or(Ok(()), |v| {
+                if v.mode == OpMode::Manual {
+                    let [rgain, grgain, gbgain, bgain] = v.gains;
+                    ctx.set_mwb_gain(WbGain {
+                        rgain,
+                        grgain,
+                        gbgain,
+                        bgain,
+                    })?;
+                }
+                ctx.set_wb_mode(v.mode)
+            }),
+            ProfileModule::NoiseRemoval => self.noise.map_or(Ok(()), |v| {
+                ctx.set_nr_mode(v.mode)?;
+                ctx.set_anr_strength(v.strength)?;
+                ctx.set_ms_nr_strength(v.spatial.0, v.spatial.1)?;
+                ctx.set_mt_nr_strength(v.temporal.0, v.temporal.1)
+            }),
+            ProfileModule::Sharpen => self.sharpness.map_or(Ok(()), |v| ctx.set_sharpness(v)),
+            ProfileModule::Gamma => self.gamma.map_or(Ok(()), |v| ctx.set_gamma_curve(&v)),
+            ProfileModule::Defog => self.defog.map_or(Ok(()), |v| {
+                if let Some(mode) = v.mode {
+                    ctx.set_dhz_mode(mode)?;
+                }
+                ctx.set_dhz_strength(v.strength)
+            }),
+            ProfileModule::Color => self.color.map_or(Ok(()), |v| ctx.set_acp_levels(v)),
+        }
+    }
+}
+
+/// 应用前采集的各模块状态，用于回滚。
+///
+/// 与 [`IspProfile`] 不同，Gamma 保存完整属性以便恢复自动模式，读取失败的模块保留错误原因。
+struct Backup {
+    exposure: XCamResult<ExposureProfile>,
+    white_balance: XCamResult<WhiteBalanceProfile>,
+    noise: XCamResult<NoiseProfile>,
+    sharpness: XCamResult<u32>,
+    gamma: XCamResult<GammaAttr>,
+    defog_mode: XCamResult<OpMode>,
+    defog_strength: XCamResult<u32>,
+    color: XCamResult<AcpLevels>,
+}
+
+impl Backup {
+    fn capture(ctx: &Context) -> Self {
+        Self {
+            exposure: capture_exposure(ctx),
+            white_balance: capture_white_balance(ctx),
+            noise: capture_noise(ctx),
+            sharpness: ctx.get_sharpness(),
+            gamma: ctx.get_gamma_coef(),
+            defog_mode: ctx.get_dhz_mode(),
+            defog_strength: ctx.get_dhz_strength(),
+            color: ctx.get_acp_levels(),
+        }
+    }
+
+    /// 将模块恢复至采集时的状态，`applied` 为已应用的档案。
+    fn restore(
+        &self,
+        ctx: &Context,
+        module: ProfileModule,
+        applied: &IspProfile,
+    ) -> XCamResult<()> {
+        let mut profile = IspProfile::default();
+        match module {
+            ProfileModule::Exposure => profile.exposure = Some(self.exposure?),
+            ProfileModule::WhiteBalance => profile.white_balance = Some(self.white_balance?),
+            ProfileModule::NoiseRemoval => profile.noise = Some(self.noise?),
+            ProfileModule::Sharpen => profile.sharpness = Some(self.sharpness?),
+            ProfileModule::Gamma => return ctx.set_gamma_coef(self.gamma?),
+            ProfileModule::Defog => {
+                // 档案修改了去雾模式时，必须能够恢复原模式。
+                let mode = match applied.defog.and_then(|v| v.mode) {
+                    Some(_) => Some(self.defog_mode?),
+                    None => self.defog_mode.ok(),
+                };
+                profile.defog = Some(DefogProfile {
+                    mode,
+                    strength: self.defog_strength?,
+                });
+            }
+            ProfileModule::Color => profile.color = Some(self.color?),
+        }
+        profile.apply_module(ctx, module)
+    }
+}
+
+fn capture_exposure(ctx: &Context) -> XCamResult<ExposureProfile> {
+    Ok(ExposureProfile {
+        mode: ctx.get_exp_mode()?,
+        gain_range: ctx.get_exp_gain_range()?,
+        time_range: ctx.get_exp_time_range()?,
+    })
+}
+
+fn capture_white_balance(ctx: &Context) -> XCamResult<WhiteBalanceProfile> {
+    let gain = ctx.get_mwb_gain()?;
+    Ok(WhiteBalanceProfile {
+        mode: ctx.get_wb_mode()?,
+        gains: [gain.rgain, gain.grgain, gain.gbgain, gain.bgain],
+    })
+}
+
+fn capture_noise(ctx: &Context) -> XCamResult<NoiseProfile> {
+    Ok(NoiseProfile {
+        mode: ctx.get_nr_mode()?,
+        strength: ctx.get_anr_strength()?,
+        spatial: ctx.get_ms_nr_strength()?,
+        temporal: ctx.get_mt_nr_strength()?,
+    })
+}
+
+fn capture_gamma(ctx: &Context) -> XCamResult<Option<GammaCurve>> {
+    if !gamma_is_manual(&ctx.get_gamma_coef()?) {
+        return Ok(None);
+    }
+    let log_x = GammaSegment::Log.x_axis();
+    let points = ctx.get_gamma_curve()?;
+    if points.iter().map(|p| p.0).ne(log_x.iter().copied()) {
+        // 等间距分段的曲线无法以 GammaCurve 表示。
+        return Err(XCamError::unsupported());
+    }
+    let y: Vec<u16> = points.iter().map(|p| p.1).collect();
+    GammaCurve::from_table(&y)
+        .map(Some)
+        .map_err(|_| XCamError::from(super::ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM))
+}
+
+fn capture_defog(ctx: &Context) -> XCamResult<DefogProfile> {
+    Ok(DefogProfile {
+        mode: ctx.get_dhz_mode().ok(),
+        strength: ctx.get_dhz_strength()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_diff() {
+        let a = IspProfile {
+            sharpness: Some(50),
+            color: Some(AcpLevels::default()),
+            ..Default::default()
+        };
+        let mut b = a.clone();
+        assert!(a.diff(&b).is_empty());
+        b.sharpness = Some(60);
+        b.color.as_mut().unwrap().contrast = 200.into();
+        let changes = a.diff(&b);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].module, ProfileModule::Sharpen);
+        assert_eq!(changes[1].field, "contrast");
+        assert_eq!(
+            a.modules(),
+            vec![ProfileModule::Sharpen, ProfileModule::Color]
+        );
+    }
+}
@@ -0,0 +1,524 @@
+//! 场景预设
+//!
+//! 场景预设将曝光范围、背光补偿、强光抑制、白平衡场景、去雾、降噪及锐化等参数打包，
+//! 供安装人员按场景而非按参数进行配置。内置预设依据 [`SensorCaps`] 中的传感器能力生成，
+//! 用户预设可由 TOML 文件加载（需开启 `toml` 特性）。
+//!
+//! TOML 文件格式如下：
+//!
+//! ```toml
+//! [[scene]]
+//! name = "parking"
+//! exposure = { gain_range = [1.0, 64.0], time_range = [0.0001, 0.04] }
+//! hlc = { enabled = true, strength = 60 }
+//! wb_scene = "Auto"
+//! nr_strength = 70
+//! sharpness = 40
+//! ```
+use super::ae::{AeMeasAreaType, AutoExposure};
+use super::awb::AutoWhiteBalance;
+use super::context::Context;
+use super::defog::Defog;
+use super::ffi;
+use super::nr::NoiseRemoval;
+use super::sharpen::Sharpen;
+use super::types::{OpMode, StaticInfo, XCamResult};
+use std::thread;
+use std::time::Duration;
+
+/// 一个代表传感器能力的类型，用于生成内置预设。
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorCaps {
+    /// 传感器支持的最高帧率。
+    pub max_fps: f32,
+    /// 是否带有红外截止滤光片。
+    pub has_ircut: bool,
+    /// 是否带有补光灯。
+    pub has_flash: bool,
+}
+
+impl Default for SensorCaps {
+    fn default() -> Self {
+        Self {
+            max_fps: 25.0,
+            has_ircut: false,
+            has_flash: false,
+        }
+    }
+}
+
+impl From<&StaticInfo> for SensorCaps {
+    fn from(val: &StaticInfo) -> Self {
+        let info = &val.sensor_info;
+        let num = (info.num.max(0) as usize).min(info.support_fmt.len());
+        let max_fps = info.support_fmt[..num]
+            .iter()
+            .map(|fmt| fmt.fps as f32)
+            .fold(0.0, f32::max);
+        Self {
+            max_fps: if max_fps > 0.0 {
+                max_fps
+            } else {
+                Self::default().max_fps
+            },
+            has_ircut: val.has_irc,
+            has_flash: val.has_fl,
+        }
+    }
+}
+
+/// 一个代表曝光范围的类型。
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneExposure {
+    /// 增益范围。
+    pub gain_range: (f32, f32),
+    /// 曝光时间范围，单位：秒。
+    pub time_range: (f32, f32),
+}
+
+/// 一个代表背光补偿设置的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneBlc {
+    /// 是否开启。
+    pub enabled: bool,
+    /// 测光区域。
+    pub area: AeMeasAreaType,
+    /// 补偿强度，范围：[1,100]。
+    pub strength: i32,
+}
+
+/// 一个代表强光抑制设置的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneHlc {
+    /// 是否开启。
+    pub enabled: bool,
+    /// 抑制强度，范围：[1,100]。
+    pub strength: i32,
+}
+
+/// 一个描述白平衡场景的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SceneWb {
+    /// 自动白平衡。
+    Auto,
+    Incandescent,
+    Fluorescent,
+    WarmFluorescent,
+    Daylight,
+    CloudyDaylight,
+    Twilight,
+    Shade,
+}
+
+impl SceneWb {
+    fn to_ffi(self) -> Option<ffi::rk_aiq_wb_scene_t> {
+        use ffi::rk_aiq_wb_scene_t::*;
+        match self {
+            SceneWb::Auto => None,
+            SceneWb::Incandescent => Some(RK_AIQ_WBCT_INCANDESCENT),
+            SceneWb::Fluorescent => Some(RK_AIQ_WBCT_FLUORESCENT),
+            SceneWb::WarmFluorescent => Some(RK_AIQ_WBCT_WARM_FLUORESCENT),
+            SceneWb::Daylight => Some(RK_AIQ_WBCT_DAYLIGHT),
+            SceneWb::CloudyDaylight => Some(RK_AIQ_WBCT_CLOUDY_DAYLIGHT),
+            SceneWb::Twilight => Some(RK_AIQ_WBCT_TWILIGHT),
+            SceneWb::Shade => Some(RK_AIQ_WBCT_SHADE),
+        }
+    }
+}
+
+/// 一个描述内置场景的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BuiltinScene {
+    /// 室内，人工光源为主。
+    Indoor,
+    /// 室外日间。
+    Outdoor,
+    /// 低照度，如夜间。
+    LowLight,
+    /// 逆光，如出入口、窗边。
+    Backlight,
+    /// 道路交通，高速运动目标，如卡口、电子警察。
+    Traffic,
+}
+
+impl BuiltinScene {
+    /// 全部内置场景。
+    pub const ALL: [BuiltinScene; 5] = [
+        BuiltinScene::Indoor,
+        BuiltinScene::Outdoor,
+        BuiltinScene::LowLight,
+        BuiltinScene::Backlight,
+        BuiltinScene::Traffic,
+    ];
+
+    /// 返回场景名称。
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinScene::Indoor => "indoor",
+            BuiltinScene::Outdoor => "outdoor",
+            BuiltinScene::LowLight => "low-light",
+            BuiltinScene::Backlight => "backlight",
+            BuiltinScene::Traffic => "traffic",
+        }
+    }
+}
+
+/// 一个代表场景预设的类型。
+///
+/// 各字段为 `None` 表示应用该预设时保持对应参数不变。
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ScenePreset {
+    /// 预设名称。
+    pub name: String,
+    /// 自动曝光范围。
+    pub exposure: Option<SceneExposure>,
+    /// 背光补偿。
+    pub blc: Option<SceneBlc>,
+    /// 强光抑制。
+    pub hlc: Option<SceneHlc>,
+    /// 白平衡场景。
+    pub wb_scene: Option<SceneWb>,
+    /// 去雾强度，范围：[0,100]。
+    pub dehaze_strength: Option<u32>,
+    /// 降噪强度，范围：[0,100]。
+    pub nr_strength: Option<u32>,
+    /// 锐化等级，范围：[0,100]。
+    pub sharpness: Option<u32>,
+}
+
+/// 一个代表场景切换过渡方式的类型。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Transition {
+    /// 过渡步数，为 0 或 1 时立即切换。
+    pub steps: u32,
+    /// 相邻两步之间的间隔。
+    pub interval: Duration,
+}
+
+impl Transition {
+    /// 立即切换。
+    pub const IMMEDIATE: Self = Self {
+        steps: 1,
+        interval: Duration::ZERO,
+    };
+
+    /// 创建一个在指定时长内均匀过渡的方式。
+    pub fn over(duration: Duration, steps: u32) -> Self {
+        let steps = steps.max(1);
+        Self {
+            steps,
+            interval: duration / steps,
+        }
+    }
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self::over(Duration::from_millis(500), 10)
+    }
+}
+
+impl ScenePreset {
+    /// 按传感器能力生成内置预设。
+    pub fn builtin(scene: BuiltinScene, caps: &SensorCaps) -> Self {
+        // 常规场景下曝光时间不超过一帧，低照度场景允许降帧至最高帧率的一半（不低于 12.5 帧）。
+        let max_fps = caps.max_fps.max(1.0);
+        let frame = 1.0 / max_fps;
+        let slow = 1.0 / (max_fps / 2.0).max(12.5).min(max_fps);
+        let mut preset = Self {
+            name: scene.name().to_owned(),
+            blc: Some(SceneBlc {
+                enabled: false,
+                area: AeMeasAreaType::Auto,
+                strength: 1,
+            }),
+            hlc: Some(SceneHlc {
+                enabled: false,
+                strength: 1,
+            }),
+            wb_scene: Some(SceneWb::Auto),
+            dehaze_strength: Some(0),
+            ..Default::default()
+        };
+        match scene {
+            BuiltinScene::Indoor => {
+                preset.exposure = Some(SceneExposure {
+                    gain_range: (1.0, 32.0),
+                    time_range: (0.0001, frame),
+                });
+                preset.nr_strength = Some(50);
+                preset.sharpness = Some(50);
+            }
+            BuiltinScene::Outdoor => {
+                preset.exposure = Some(SceneExposure {
+                    gain_range: (1.0, 16.0),
+                    time_range: (0.00002, frame),
+                });
+                preset.dehaze_strength = Some(30);
+                preset.nr_strength = Some(40);
+                preset.sharpness = Some(60);
+            }
+            BuiltinScene::LowLight => {
+                // 带补光灯或红外截止滤光片的模组可依靠补光，降噪可相对保守。
+                let aided = caps.has_flash || caps.has_ircut;
+                preset.exposure = Some(SceneExposure {
+                    gain_range: (1.0, if aided { 64.0 } else { 128.0 }),
+                    time_range: (0.0001, slow),
+                });
+                preset.nr_strength = Some(if aided { 70 } else { 85 });
+                preset.sharpness = Some(30);
+            }
+            BuiltinScene::Backlight => {
+                preset.exposure = Some(SceneExposure {
+                    gain_range: (1.0, 32.0),
+                    time_range: (0.00002, frame),
+                });
+                preset.blc = Some(SceneBlc {
+                    enabled: true,
+                    area: AeMeasAreaType::Center,
+                    strength: 50,
+                });
+                preset.hlc = Some(SceneHlc {
+                    enabled: true,
+                    strength: 50,
+                });
+                preset.nr_strength = Some(50);
+                preset.sharpness = Some(50);
+            }
+            BuiltinScene::Traffic => {
+                // 高速目标优先短快门，以增益补偿亮度。
+                preset.exposure = Some(SceneExposure {
+                    gain_range: (1.0, 64.0),
+                    time_range: (0.00002, frame.min(0.002)),
+                });
+                preset.nr_strength = Some(60);
+                preset.sharpness = Some(60);
+            }
+        }
+        preset
+    }
+
+    /// 按传感器能力生成全部内置预设。
+    pub fn builtins(caps: &SensorCaps) -> Vec<Self> {
+        BuiltinScene::ALL
+            .iter()
+            .map(|&scene| Self::builtin(scene, caps))
+            .collect()
+    }
+
+    /// 将预设应用至 Context。
+    ///
+    /// 数值参数在 `transition` 指定的步数内由当前值线性过渡至目标值，开关、背光/强光
+    /// 补偿强度及白平衡场景在过渡开始前立即生效。无法读取当前值的参数将直接设为目标值。
+    ///
+    /// 过渡中途失败时，已读取到起点的数值参数将尽力恢复至起点；此前已生效的开关、补偿强度
+    /// 及白平衡场景不会恢复，预设处于部分应用的状态。
+    pub fn apply(&self, ctx: &Context, transition: Transition) -> XCamResult<()> {
+        let start = SceneState::read(ctx);
+        let steps = transition.steps.max(1);
+
+        if let Some(v) = self.blc {
+            ctx.set_blc_mode(v.enabled, v.area)?;
+            if v.enabled {
+                ctx.set_blc_strength(v.strength)?;
+            }
+        }
+        if let Some(v) = self.hlc {
+            ctx.set_hlc_mode(v.enabled)?;
+            if v.enabled {
+                ctx.set_hlc_strength(v.strength)?;
+            }
+        }
+        match self.wb_scene.map(SceneWb::to_ffi) {
+            Some(Some(scene)) => ctx.set_mwb_scene(scene)?,
+            Some(None) => ctx.set_wb_mode(OpMode::Auto)?,
+            None => {}
+        }
+
+        let result = (1..=steps).try_for_each(|step| {
+            let t = step as f32 / steps as f32;
+            if let Some(v) = self.exposure {
+                let gain = lerp_range(start.gain_range, v.gain_range, t);
+                let time = lerp_range(start.time_range, v.time_range, t);
+                ctx.set_exp_gain_range(gain.0, gain.1)?;
+                ctx.set_exp_time_range(time.0, time.1)?;
+            }
+            if let Some(v) = self.dehaze_strength {
+                ctx.set_dhz_strength(lerp_u32(start.dehaze_strength, v, t))?;
+            }
+            if let Some(v) = self.nr_strength {
+                ctx.set_anr_strength(lerp_u32(start.nr_strength, v, t))?;
+            }
+            if let Some(v) = self.sharpness {
+                ctx.set_sharpness(lerp_u32(start.sharpness, v, t))?;
+            }
+            if step < steps {
+                thread::sleep(transition.interval);
+            }
+            Ok(())
+        });
+        if result.is_err() {
+            start.restore(ctx, self);
+        }
+        result
+    }
+}
+
+/// 过渡起点，读取失败的参数为 `None`。
+struct SceneState {
+    gain_range: Option<(f32, f32)>,
+    time_range: Option<(f32, f32)>,
+    dehaze_strength: Option<u32>,
+    nr_strength: Option<u32>,
+    sharpness: Option<u32>,
+}
+
+impl SceneState {
+    fn read(ctx: &Context) -> Self {
+        Self {
+            gain_range: ctx.get_exp_gain_range().ok(),
+            time_range: ctx.get_exp_time_range().ok(),
+            dehaze_strength: ctx.get_dhz_strength().ok(),
+            nr_strength: ctx.get_anr_strength().ok(),
+            sharpness: ctx.get_sharpness().ok(),
+        }
+    }
+
+    /// 尽力将 `preset` 涉及的数值参数恢复至起点，忽略恢复过程中的错误。
+    fn restore(&self, ctx: &Context, preset: &ScenePreset) {
+        if preset.exposure.is_some() {
+            if let Some((min, max)) = self.gain_range {
+                let _ = ctx.set_exp_gain_range(min, max);
+            }
+            if let Some((min, max)) = self.time_range {
+                let _ = ctx.set_exp_time_range(min, max);
+            }
+        }
+        if let (Some(_), Some(v)) = (preset.dehaze_strength, self.dehaze_strength) {
+            let _ = ctx.set_dhz_strength(v);
+        }
+        if let (Some(_), Some(v)) = (preset.nr_strength, self.nr_strength) {
+            let _ = ctx.set_anr_strength(v);
+        }
+        if let (Some(_), Some(v)) = (preset.sharpness, self.sharpness) {
+            let _ = ctx.set_sharpness(v);
+        }
+    }
+}
+
+fn lerp_range(from: Option<(f32, f32)>, to: (f32, f32), t: f32) -> (f32, f32) {
+    match from {
+        Some(from) => (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t),
+        None => to,
+    }
+}
+
+fn lerp_u32(from: Option<u32>, to: u32, t: f32) -> u32 {
+    match from {
+        Some(from) => (from as f32 + (to as f32 - from as f32) * t).round() as u32,
+        None => to,
+    }
+}
+
+/// 一个代表场景预设加载错误的类型。
+#[cfg(feature = "toml")]
+#[derive(Debug)]
+pub enum SceneLoadError {
+    /// 读取文件失败。
+    Io(std::io::Error),
+    /// 解析 TOML 失败。
+    Parse(toml::de::Error),
+}
+
+#[cfg(feature = "toml")]
+impl std::fmt::Display for SceneLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneLoadError::Io(err) => write!(f, "{}", err),
+            SceneLoadError::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+impl std::error::Error for SceneLoadError {}
+
+#[cfg(feature = "toml")]
+#[derive(serde::Deserialize)]
+struct SceneFile {
+    #[serde(default)]
+    scene: Vec<ScenePreset>,
+}
+
+/// 从 TOML 文本解析用户预设。
+#[cfg(feature = "toml")]
+pub fn parse_presets(s: &str) -> Result<Vec<ScenePreset>, SceneLoadError> {
+    toml::from_str::<SceneFile>(s)
+        .map(|f| f.scene)
+        .map_err(SceneLoadError::Parse)
+}
+
+/// 从 TOML 文件加载用户预设。
+#[cfg(feature = "toml")]
+pub fn load_presets<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<Vec<ScenePreset>, SceneLoadError> {
+    let s = std::fs::read_to_string(path).map_err(SceneLoadError::Io)?;
+    parse_presets(&s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_presets() {
+        let caps = SensorCaps {
+            max_fps: 60.0,
+            ..Default::default()
+        };
+        let presets = ScenePreset::builtins(&caps);
+        assert_eq!(presets.len(), BuiltinScene::ALL.len());
+        let low_light = ScenePreset::builtin(BuiltinScene::LowLight, &caps);
+        assert_eq!(low_light.exposure.unwrap().time_range.1, 1.0 / 30.0);
+        let low_light = ScenePreset::builtin(BuiltinScene::LowLight, &SensorCaps::default());
+        assert_eq!(low_light.exposure.unwrap().time_range.1, 1.0 / 12.5);
+        let traffic = ScenePreset::builtin(BuiltinScene::Traffic, &caps);
+        assert!(traffic.exposure.unwrap().time_range.1 <= 0.002);
+    }
+
+    #[test]
+    fn test_lerp() {
+        assert_eq!(lerp_u32(Some(0), 100, 0.5), 50);
+        assert_eq!(lerp_u32(None, 100, 0.5), 100);
+        assert_eq!(lerp_range(Some((0.0, 2.0)), (2.0, 4.0), 0.5), (1.0, 3.0));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_parse_presets() {
+        let presets = parse_presets(
+            r#"
+            [[scene]]
+            name = "parking"
+            exposure = { gain_range = [1.0, 64.0], time_range = [0.0001, 0.04] }
+            hlc = { enabled = true, strength = 60 }
+            wb_scene = "Auto"
+            sharpness = 40
+            "#,
+        )
+        .unwrap();
+        assert_eq!(presets.len(), 1);
+        assert_eq!(presets[0].name, "parking");
+        assert_eq!(presets[0].sharpness, Some(40));
+        assert_eq!(presets[0].nr_strength, None);
+    }
+}
@@ -4,11 +4,150 @@
 use super::context::Context;
 use super::error::XCamError;
 use super::ffi;
-use super::types::XCamResult;
+#[cfg(not(feature = "isp_hw_v20"))]
+use super::nr::{validate_iso_table, validate_shared};
+use super::nr::{IsoInterpolate, IsoParams};
+use super::types::{OpMode, XCamResult};
+#[cfg(not(feature = "isp_hw_v20"))]
+use super::types::{SharpAttr, SharpParamsSelect};
+
+/// 亮度相关曲线的节点个数。
+pub const SHARP_LUMA_POINTS: usize = 8;
+
+/// 自动参数表的 ISO 档位数。
+pub const SHARP_ISO_NUM: usize = 13;
+
+/// 一个代表锐化滤波器参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SharpenFilter {
+    /// 滤波增益。
+    pub gain: f32,
+    /// 滤波偏移。
+    pub add: f32,
+    /// 滤波结果的融合比例，取值范围 [0,1]。
+    pub ratio: f32,
+}
+
+/// 一个代表锐化参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SharpenParams {
+    /// 亮度节点，自动参数表中各 ISO 档位共用。
+    pub luma_point: [i16; SHARP_LUMA_POINTS],
+    /// 各亮度节点对应的噪声标准差。
+    pub luma_sigma: [i16; SHARP_LUMA_POINTS],
+    /// 预双边滤波（PBF）参数。
+    pub pbf: SharpenFilter,
+    /// 高频双边滤波（HBF）参数。
+    pub hbf: SharpenFilter,
+    /// 中频高斯滤波（MBF）融合比例，取值范围 [0,1]。
+    pub mbf_ratio: f32,
+    /// 细节锐化强度。
+    pub detail_strength: f32,
+    /// 按亮度分段的锐化上限曲线，用于抑制亮区过冲。
+    pub clip_curve: [i16; SHARP_LUMA_POINTS],
+    /// 按亮度分段的边缘阈值曲线，决定边缘锐化强度。
+    pub edge_threshold: [i16; SHARP_LUMA_POINTS],
+}
+
+#[cfg(not(feature = "isp_hw_v20"))]
+impl SharpenParams {
+    /// 从 AIQ 参数中提取类型化的参数。
+    pub fn from_select(val: &SharpParamsSelect) -> Self {
+        Self {
+            luma_point: val.luma_point.map(|x| x as _),
+            luma_sigma: val.luma_sigma.map(|x| x as _),
+            pbf: SharpenFilter {
+                gain: val.pbf_gain,
+                add: val.pbf_add,
+                ratio: val.pbf_ratio,
+            },
+            hbf: SharpenFilter {
+                gain: val.bf_gain,
+                add: val.bf_add,
+                ratio: val.bf_ratio,
+            },
+            mbf_ratio: val.gaus_ratio,
+            detail_strength: val.sharp_ratio,
+            clip_curve: val.lum_clip_h.map(|x| x as _),
+            edge_threshold: val.ehf_th.map(|x| x as _),
+        }
+    }
+
+    /// 将参数写入 AIQ 参数，未建模的字段保持不变。
+    pub fn apply_to(&self, val: &mut SharpParamsSelect) {
+        val.luma_point = self.luma_point.map(|x| x as _);
+        val.luma_sigma = self.luma_sigma.map(|x| x as _);
+        val.pbf_gain = self.pbf.gain;
+        val.pbf_add = self.pbf.add;
+        val.pbf_ratio = self.pbf.ratio;
+        val.bf_gain = self.hbf.gain;
+        val.bf_add = self.hbf.add;
+        val.bf_ratio = self.hbf.ratio;
+        val.gaus_ratio = self.mbf_ratio;
+        val.sharp_ratio = self.detail_strength;
+        val.lum_clip_h = self.clip_curve.map(|x| x as _);
+        val.ehf_th = self.edge_threshold.map(|x| x as _);
+    }
+}
+
+impl IsoInterpolate for SharpenFilter {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            gain: self.gain.lerp(&other.gain, t),
+            add: self.add.lerp(&other.add, t),
+            ratio: self.ratio.lerp(&other.ratio, t),
+        }
+    }
+}
+
+impl IsoInterpolate for SharpenParams {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            luma_point: self.luma_point,
+            luma_sigma: self.luma_sigma.lerp(&other.luma_sigma, t),
+            pbf: self.pbf.lerp(&other.pbf, t),
+            hbf: self.hbf.lerp(&other.hbf, t),
+            mbf_ratio: self.mbf_ratio.lerp(&other.mbf_ratio, t),
+            detail_strength: self.detail_strength.lerp(&other.detail_strength, t),
+            clip_curve: self.clip_curve.lerp(&other.clip_curve, t),
+            edge_threshold: self.edge_threshold.lerp(&other.edge_threshold, t),
+        }
+    }
+}
 
 pub trait Sharpen {
     fn get_sharpness(&self) -> XCamResult<u32>;
     fn set_sharpness(&self, mode: u32) -> XCamResult<()>;
+
+    /// 获取锐化工作模式。
+    fn get_sharp_mode(&self) -> XCamResult<OpMode>;
+
+    /// 设置锐化工作模式，仅支持 `Auto` 及 `Manual`。
+    fn set_sharp_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 获取自动模式下按 ISO 索引的参数表。
+    fn get_sharp_auto(&self) -> XCamResult<Vec<IsoParams<SharpenParams>>>;
+
+    /// 设置自动模式下按 ISO 索引的参数表。
+    ///
+    /// # Parameters
+    /// * `table` - 参数表，须包含 [`SHARP_ISO_NUM`] 个档位且 ISO 单调递增，
+    ///   各档位的 `luma_point` 须一致。
+    fn set_sharp_auto(&self, table: &[IsoParams<SharpenParams>]) -> XCamResult<()>;
+
+    /// 获取手动模式参数。
+    fn get_sharp_manual(&self) -> XCamResult<SharpenParams>;
+
+    /// 设置手动模式参数，并切换至手动模式。
+    fn set_sharp_manual(&self, params: &SharpenParams) -> XCamResult<()>;
+
+    /// 获取锐化强度，取值范围 [0,1]。
+    fn get_sharp_strength(&self) -> XCamResult<f32>;
+
+    /// 设置锐化强度，取值范围 [0,1]，0.5 为 IQ 默认强度。
+    fn set_sharp_strength(&self, percent: f32) -> XCamResult<()>;
 }
 
 impl Sharpen for Context {
@@ -16,7 +155,7 @@ impl Sharpen for Context {
         let mut level: u32 = 0;
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_getSharpness(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut level,
             ))
             .ok()
@@ -25,8 +164,271 @@ impl Sharpen for Context {
     }
 
     fn set_sharpness(&self, level: u32) -> XCamResult<()> {
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_setSharpness(self.lock().as_ptr(), level)).ok() }
+    }
+
+    #[cfg(feature = "isp_hw_v20")]
+    fn get_sharp_mode(&self) -> XCamResult<OpMode> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(not(feature = "isp_hw_v20"))]
+    fn get_sharp_mode(&self) -> XCamResult<OpMode> {
+        get_sharp_attrib(self).map(|attr| sharp_op_mode_from(attr.eMode))
+    }
+
+    #[cfg(feature = "isp_hw_v20")]
+    fn set_sharp_mode(&self, _mode: OpMode) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(not(feature = "isp_hw_v20"))]
+    fn set_sharp_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = get_sharp_attrib(self)?;
+        attr.eMode = sharp_op_mode_into(mode)?;
+        set_sharp_attrib(self, attr)
+    }
+
+    #[cfg(feature = "isp_hw_v20")]
+    fn get_sharp_auto(&self) -> XCamResult<Vec<IsoParams<SharpenParams>>> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(not(feature = "isp_hw_v20"))]
+    fn get_sharp_auto(&self) -> XCamResult<Vec<IsoParams<SharpenParams>>> {
+        let attr = get_sharp_attrib(self)?;
+        let p = &attr.stAuto.stParams;
+        let table = (0..SHARP_ISO_NUM)
+            .map(|i| IsoParams {
+                iso: p.iso[i],
+                params: SharpenParams {
+                    luma_point: p.luma_point.map(|x| x as _),
+                    luma_sigma: p.luma_sigma[i].map(|x| x as _),
+                    pbf: SharpenFilter {
+                        gain: p.pbf_gain[i],
+                        add: p.pbf_add[i],
+                        ratio: p.pbf_ratio[i],
+                    },
+                    hbf: SharpenFilter {
+                        gain: p.bf_gain[i],
+                        add: p.bf_add[i],
+                        ratio: p.bf_ratio[i],
+                    },
+                    mbf_ratio: p.gaus_ratio[i],
+                    detail_strength: p.sharp_ratio[i],
+                    clip_curve: p.lum_clip_h[i].map(|x| x as _),
+                    edge_threshold: p.ehf_th[i].map(|x| x as _),
+                },
+            })
+            .collect();
+        Ok(table)
+    }
+
+    #[cfg(feature = "isp_hw_v20")]
+    fn set_sharp_auto(&self, _table: &[IsoParams<SharpenParams>]) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(not(feature = "isp_hw_v20"))]
+    fn set_sharp_auto(&self, table: &[IsoParams<SharpenParams>]) -> XCamResult<()> {
+        let _lock = self.lock();
+        validate_iso_table(table, SHARP_ISO_NUM)?;
+        validate_shared(table, |p| p.luma_point)?;
+        let mut attr = get_sharp_attrib(self)?;
+        let p = &mut attr.stAuto.stParams;
+        p.luma_point = table[0].params.luma_point.map(|x| x as _);
+        for (i, v) in table.iter().enumerate() {
+            let v2 = &v.params;
+            p.iso[i] = v.iso;
+            p.luma_sigma[i] = v2.luma_sigma.map(|x| x as _);
+            p.pbf_gain[i] = v2.pbf.gain;
+            p.pbf_add[i] = v2.pbf.add;
+            p.pbf_ratio[i] = v2.pbf.ratio;
+            p.bf_gain[i] = v2.hbf.gain;
+            p.bf_add[i] = v2.hbf.add;
+            p.bf_ratio[i] = v2.hbf.ratio;
+            p.gaus_ratio[i] = v2.mbf_ratio;
+            p.sharp_ratio[i] = v2.detail_strength;
+            p.lum_clip_h[i] = v2.clip_curve.map(|x| x as _);
+            p.ehf_th[i] = v2.edge_threshold.map(|x| x as _);
+        }
+        set_sharp_attrib(self, attr)
+    }
+
+    #[cfg(feature = "isp_hw_v20")]
+    fn get_sharp_manual(&self) -> XCamResult<SharpenParams> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(not(feature = "isp_hw_v20"))]
+    fn get_sharp_manual(&self) -> XCamResult<SharpenParams> {
+        get_sharp_attrib(self).map(|attr| SharpenParams::from_select(&attr.stManual.stSelect))
+    }
+
+    #[cfg(feature = "isp_hw_v20")]
+    fn set_sharp_manual(&self, _params: &SharpenParams) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(not(feature = "isp_hw_v20"))]
+    fn set_sharp_manual(&self, params: &SharpenParams) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = get_sharp_attrib(self)?;
+        attr.eMode = sharp_op_mode_into(OpMode::Manual)?;
+        params.apply_to(&mut attr.stManual.stSelect);
+        set_sharp_attrib(self, attr)
+    }
+
+    #[cfg(feature = "isp_hw_v20")]
+    fn get_sharp_strength(&self) -> XCamResult<f32> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(feature = "isp_hw_v21")]
+    fn get_sharp_strength(&self) -> XCamResult<f32> {
+        let mut percent: f32 = 0.0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_asharpV3_GetStrength(
+                self.lock().as_ptr(),
+                &mut percent,
+            ))
+            .ok()
+            .map(|_| percent)
+        }
+    }
+
+    #[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+    fn get_sharp_strength(&self) -> XCamResult<f32> {
+        let mut percent: f32 = 0.0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_asharpV4_GetStrength(
+                self.lock().as_ptr(),
+                &mut percent,
+            ))
+            .ok()
+            .map(|_| percent)
+        }
+    }
+
+    #[cfg(feature = "isp_hw_v20")]
+    fn set_sharp_strength(&self, _percent: f32) -> XCamResult<()> {
+        Err(XCamError::unsupported())
+    }
+
+    #[cfg(feature = "isp_hw_v21")]
+    fn set_sharp_strength(&self, percent: f32) -> XCamResult<()> {
+        if !(0.0..=1.0).contains(&percent) {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_asharpV3_SetStrength(
+                self.lock().as_ptr(),
+                percent,
+            ))
+            .ok()
+        }
+    }
+
+    #[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+    fn set_sharp_strength(&self, percent: f32) -> XCamResult<()> {
+        if !(0.0..=1.0).contains(&percent) {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
         unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_setSharpness(self.internal.as_ptr(), level)).ok()
+            XCamError::from(ffi::rk_aiq_user_api2_asharpV4_SetStrength(
+                self.lock().as_ptr(),
+                percent,
+            ))
+            .ok()
         }
     }
 }
+
+#[cfg(feature = "isp_hw_v21")]
+fn get_sharp_attrib(ctx: &Context) -> XCamResult<SharpAttr> {
+    let mut attr = SharpAttr::default();
+    unsafe {
+        XCamError::from(ffi::rk_aiq_user_api2_asharpV3_GetAttrib(
+            ctx.lock().as_ptr(),
+            &mut attr,
+        ))
+        .ok()
+        .map(|_| attr)
+    }
+}
+
+#[cfg(feature = "isp_hw_v21")]
+fn set_sharp_attrib(ctx: &Context, mut attr: SharpAttr) -> XCamResult<()> {
+    unsafe {
+        XCamError::from(ffi::rk_aiq_user_api2_asharpV3_SetAttrib(
+            ctx.lock().as_ptr(),
+            &mut attr,
+        ))
+        .ok()
+    }
+}
+
+#[cfg(feature = "isp_hw_v21")]
+fn sharp_op_mode_from(val: ffi::Asharp3_OPMode_t) -> OpMode {
+    use ffi::Asharp3_OPMode_t::*;
+    match val {
+        ASHARP3_OP_MODE_AUTO => OpMode::Auto,
+        ASHARP3_OP_MODE_MANUAL => OpMode::Manual,
+        _ => OpMode::Invalid,
+    }
+}
+
+#[cfg(feature = "isp_hw_v21")]
+fn sharp_op_mode_into(mode: OpMode) -> XCamResult<ffi::Asharp3_OPMode_t> {
+    use ffi::Asharp3_OPMode_t::*;
+    match mode {
+        OpMode::Auto => Ok(ASHARP3_OP_MODE_AUTO),
+        OpMode::Manual => Ok(ASHARP3_OP_MODE_MANUAL),
+        _ => Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+}
+
+#[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+fn get_sharp_attrib(ctx: &Context) -> XCamResult<SharpAttr> {
+    let mut attr = SharpAttr::default();
+    unsafe {
+        XCamError::from(ffi::rk_aiq_user_api2_asharpV4_GetAttrib(
+            ctx.lock().as_ptr(),
+            &mut attr,
+        ))
+        .ok()
+        .map(|_| attr)
+    }
+}
+
+#[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+fn set_sharp_attrib(ctx: &Context, mut attr: SharpAttr) -> XCamResult<()> {
+    unsafe {
+        XCamError::from(ffi::rk_aiq_user_api2_asharpV4_SetAttrib(
+            ctx.lock().as_ptr(),
+            &mut attr,
+        ))
+        .ok()
+    }
+}
+
+#[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+fn sharp_op_mode_from(val: ffi::Asharp4_OPMode_t) -> OpMode {
+    use ffi::Asharp4_OPMode_t::*;
+    match val {
+        ASHARP4_OP_MODE_AUTO => OpMode::Auto,
+        ASHARP4_OP_MODE_MANUAL => OpMode::Manual,
+        _ => OpMode::Invalid,
+    }
+}
+
+#[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+fn sharp_op_mode_into(mode: OpMode) -> XCamResult<ffi::Asharp4_OPMode_t> {
+    use ffi::Asharp4_OPMode_t::*;
+    match mode {
+        OpMode::Auto => Ok(ASHARP4_OP_MODE_AUTO),
+        OpMode::Manual => Ok(ASHARP4_OP_MODE_MANUAL),
+        _ => Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+    }
+}
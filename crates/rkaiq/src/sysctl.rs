@@ -89,7 +89,7 @@ impl SystemControl for Context {
     fn prepare(&self, width: u32, height: u32, mode: WorkingMode) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_prepare(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 width,
                 height,
                 mode.into(),
@@ -99,13 +99,13 @@ impl SystemControl for Context {
     }
 
     fn start(&self) -> XCamResult<()> {
-        unsafe { XCamError::from(ffi::rk_aiq_uapi2_sysctl_start(self.internal.as_ptr())).ok() }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi2_sysctl_start(self.lock().as_ptr())).ok() }
     }
 
     fn stop(&self, keep_ext_hw_st: bool) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_stop(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 keep_ext_hw_st,
             ))
             .ok()
@@ -115,7 +115,7 @@ impl SystemControl for Context {
     fn enable_module<T: Into<ModuleId>>(&self, id: T) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_setModuleCtl(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 id.into(),
                 true,
             ))
@@ -126,7 +126,7 @@ impl SystemControl for Context {
     fn disable_module<T: Into<ModuleId>>(&self, id: T) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_setModuleCtl(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 id.into(),
                 false,
             ))
@@ -138,7 +138,7 @@ impl SystemControl for Context {
         let mut enabled = false;
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_getModuleCtl(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 id.into(),
                 &mut enabled,
             ))
@@ -150,7 +150,7 @@ impl SystemControl for Context {
     fn register_lib(&self, mut algo_lib_des: AlgoDescComm) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_sysctl_regLib(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut algo_lib_des,
             ))
             .ok()
@@ -160,7 +160,7 @@ impl SystemControl for Context {
     fn unregister_lib(&self, algo_type: i32, lib_id: i32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi_sysctl_unRegLib(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 algo_type,
                 lib_id,
             ))
@@ -171,7 +171,7 @@ impl SystemControl for Context {
     fn enable_ax_lib(&self, algo_type: i32, lib_id: i32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_enableAxlib(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 algo_type,
                 lib_id,
                 true,
@@ -183,7 +183,7 @@ impl SystemControl for Context {
     fn disable_ax_lib(&self, algo_type: i32, lib_id: i32) -> XCamResult<()> {
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_enableAxlib(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 algo_type,
                 lib_id,
                 false,
@@ -193,18 +193,18 @@ impl SystemControl for Context {
     }
 
     fn is_ax_lib_enabled(&self, algo_type: i32, lib_id: i32) -> bool {
-        unsafe { ffi::rk_aiq_uapi_sysctl_getAxlibStatus(self.internal.as_ptr(), algo_type, lib_id) }
+        unsafe { ffi::rk_aiq_uapi_sysctl_getAxlibStatus(self.lock().as_ptr(), algo_type, lib_id) }
     }
 
     unsafe fn get_enabled_ax_lib_ctx(&self, algo_type: i32) -> *const AlgoContext {
-        ffi::rk_aiq_uapi2_sysctl_getEnabledAxlibCtx(self.internal.as_ptr(), algo_type)
+        ffi::rk_aiq_uapi2_sysctl_getEnabledAxlibCtx(self.lock().as_ptr(), algo_type)
     }
 
     fn get_cps_lt_info(&self) -> XCamResult<CpslInfo> {
         let mut info = CpslInfo::default();
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_getCpsLtInfo(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut info,
             ))
             .ok()
@@ -216,7 +216,7 @@ impl SystemControl for Context {
         let mut cap = CpslCap::default();
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_queryCpsLtCap(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut cap,
             ))
             .ok()
@@ -228,7 +228,7 @@ impl SystemControl for Context {
         let mut cfg = cfg.into();
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_setCpsLtCfg(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut cfg,
             ))
             .ok()
@@ -239,7 +239,7 @@ impl SystemControl for Context {
         let iq_file = CString::new(iq_file).unwrap();
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_updateIq(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 iq_file.as_ptr() as *mut _,
             ))
             .ok()
@@ -250,7 +250,7 @@ impl SystemControl for Context {
         let mut crop = Rect::default();
         unsafe {
             XCamError::from(ffi::rk_aiq_uapi2_sysctl_getCrop(
-                self.internal.as_ptr(),
+                self.lock().as_ptr(),
                 &mut crop,
             ))
             .ok()
@@ -259,13 +259,7 @@ impl SystemControl for Context {
     }
 
     fn set_crop(&self, crop: Rect) -> XCamResult<()> {
-        unsafe {
-            XCamError::from(ffi::rk_aiq_uapi_sysctl_setCrop(
-                self.internal.as_ptr(),
-                crop,
-            ))
-            .ok()
-        }
+        unsafe { XCamError::from(ffi::rk_aiq_uapi_sysctl_setCrop(self.lock().as_ptr(), crop)).ok() }
     }
 }
 
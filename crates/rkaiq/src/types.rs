@@ -1,14 +1,48 @@
 use super::error::XCamError;
 use super::ffi;
 
+pub type AcpAttr = ffi::acp_attrib_t;
+pub type AfAttr = ffi::rk_aiq_af_attrib_t;
 pub type AlgoContext = ffi::RkAiqAlgoContext;
 pub type AlgoDescComm = ffi::RkAiqAlgoDesComm;
 pub type AntiFlickerMode = ffi::antiFlickerMode_t;
 pub type AsdAttrib = ffi::asd_attrib_t;
+pub type BayerNrAttr = ffi::rk_aiq_bayernr_attrib_v2_t;
+pub type CnrAttr = ffi::rk_aiq_cnr_attrib_v1_t;
 pub type CpslCfg = ffi::rk_aiq_cpsl_cfg_t;
 pub type CpslCap = ffi::rk_aiq_cpsl_cap_t;
 pub type CpslInfo = ffi::rk_aiq_cpsl_info_t;
+pub type DebayerAttr = ffi::adebayer_attrib_t;
+pub type DebayerAttrAuto = ffi::adebayer_attrib_auto_t;
+pub type DebayerAttrManual = ffi::adebayer_attrib_manual_t;
+pub type DebayerOpMode = ffi::rk_aiq_debayer_op_mode_t;
+pub type DegammaApiManual = ffi::Adegamma_api_manual_t;
+pub type DegammaAttr = ffi::rk_aiq_degamma_attrib_t;
+/// ADEHAZE V11 属性，ISP_HW V21（RK356x）及 V30（RK3588）共用。
+#[cfg(any(feature = "v3_0", feature = "v4_0", feature = "v5_0"))]
+pub type DehazeAttr = ffi::adehaze_sw_v11_t;
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v21"
+))]
+pub type DrcAttr = ffi::drcAttrV21_t;
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    not(feature = "isp_hw_v21")
+))]
+pub type DrcAttr = ffi::drcAttrV30_t;
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v21"
+))]
+pub type DrcManualAttr = ffi::mdrcAttr_V21_t;
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    not(feature = "isp_hw_v21")
+))]
+pub type DrcManualAttr = ffi::mdrcAttr_V30_t;
 pub type ExpPwrLineFreq = ffi::expPwrLineFreq_t;
+pub type FecAttr = ffi::rk_aiq_fec_attrib_t;
 pub type FrameRateInfo = ffi::frameRateInfo_t;
 #[cfg(feature = "v2_0")]
 pub type GammaApiManual = ffi::Agamma_api_manual_t;
@@ -34,17 +68,49 @@ pub type GammaMode = ffi::rk_aiq_gamma_op_mode_t;
 #[cfg(not(feature = "v2_0"))]
 pub type GammaMode = ffi::gamma_op_mode_t;
 pub type GammaOpMode = ffi::rk_aiq_gamma_op_mode_t;
+#[cfg(feature = "isp_hw_v20")]
+pub type GicAttr = ffi::rkaiq_gic_v1_api_attr_t;
+#[cfg(not(feature = "isp_hw_v20"))]
+pub type GicAttr = ffi::rkaiq_gic_v2_api_attr_t;
+#[cfg(feature = "isp_hw_v20")]
+pub type GicParams = ffi::rkaiq_gic_v1_param_selected_t;
+#[cfg(not(feature = "isp_hw_v20"))]
+pub type GicParams = ffi::rkaiq_gic_v2_param_selected_t;
 pub type GrayMode = ffi::rk_aiq_gray_mode_t;
+pub type LdchAttr = ffi::rk_aiq_ldch_attrib_t;
+#[cfg(feature = "v2_0")]
+pub type MergeAttr = ffi::amerge_attrib_t;
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    feature = "isp_hw_v21"
+))]
+pub type MergeAttr = ffi::mergeAttrV21_t;
+#[cfg(all(
+    any(feature = "v3_0", feature = "v4_0", feature = "v5_0"),
+    not(feature = "isp_hw_v21")
+))]
+pub type MergeAttr = ffi::mergeAttrV30_t;
 pub type ModuleId = ffi::rk_aiq_module_id_t;
 pub type PaRange = ffi::paRange_t;
 pub type Rect = ffi::rk_aiq_rect_t;
+#[cfg(feature = "isp_hw_v21")]
+pub type SharpAttr = ffi::rk_aiq_sharp_attrib_v3_t;
+#[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+pub type SharpAttr = ffi::rk_aiq_sharp_attrib_v4_t;
+#[cfg(feature = "isp_hw_v21")]
+pub type SharpParamsSelect = ffi::RK_SHARP_Params_V3_Select_t;
+#[cfg(not(any(feature = "isp_hw_v20", feature = "isp_hw_v21")))]
+pub type SharpParamsSelect = ffi::RK_SHARP_Params_V4_Select_t;
 pub type StaticInfo = ffi::rk_aiq_static_info_t;
 pub type WbGain = ffi::rk_aiq_wb_gain_t;
 pub type WbScene = ffi::rk_aiq_wb_scene_t;
 pub type XCamResult<T> = Result<T, XCamError>;
+pub type YnrAttr = ffi::rk_aiq_ynr_attrib_v2_t;
+pub type YnrParamsSelect = ffi::RK_YNR_Params_V2_Select_t;
 
 /// 一个描述自动手动模式的枚举。
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OpMode {
     Auto,
     Manual,
@@ -82,12 +148,35 @@ impl From<OpMode> for ffi::opMode_t {
     }
 }
 
+/// 一个描述 AIQ 工作模式的枚举。
+///
+/// 帧合成或行交叠（DOL）等 HDR 曝光方式由传感器驱动决定，`rk_aiq_working_mode_t`
+/// 仅区分合成帧数，故此处只有线性、两帧及三帧 HDR 三种模式。
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WorkingMode {
+    #[default]
     Normal,
     IspHdr2,
     IspHdr3,
 }
 
+impl WorkingMode {
+    /// 返回是否为 HDR 模式。
+    pub fn is_hdr(&self) -> bool {
+        *self != WorkingMode::Normal
+    }
+
+    /// 返回每帧输出所合成的曝光帧数。
+    pub fn frame_count(&self) -> usize {
+        match self {
+            WorkingMode::Normal => 1,
+            WorkingMode::IspHdr2 => 2,
+            WorkingMode::IspHdr3 => 3,
+        }
+    }
+}
+
 impl From<ffi::rk_aiq_working_mode_t> for WorkingMode {
     fn from(val: ffi::rk_aiq_working_mode_t) -> Self {
         use ffi::rk_aiq_working_mode_t::*;
@@ -110,8 +199,91 @@ impl From<WorkingMode> for ffi::rk_aiq_working_mode_t {
     }
 }
 
+/// 为超过 32 个元素的定长数组提供序列化支持。
+#[cfg(feature = "serde")]
+pub(crate) mod serde_array {
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::ser::SerializeTuple;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::marker::PhantomData;
+
+    pub fn serialize<S, T, const N: usize>(val: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        let mut tup = serializer.serialize_tuple(N)?;
+        for v in val {
+            tup.serialize_element(v)?;
+        }
+        tup.end()
+    }
+
+    pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + Copy + Default,
+    {
+        struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+        impl<'de, T, const N: usize> Visitor<'de> for ArrayVisitor<T, N>
+        where
+            T: Deserialize<'de> + Copy + Default,
+        {
+            type Value = [T; N];
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "an array of length {}", N)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut arr = [T::default(); N];
+                for (i, v) in arr.iter_mut().enumerate() {
+                    *v = seq
+                        .next_element()?
+                        .ok_or_else(|| Error::invalid_length(i, &self))?;
+                }
+                if seq.next_element::<T>()?.is_some() {
+                    return Err(Error::invalid_length(N + 1, &self));
+                }
+                Ok(arr)
+            }
+        }
+
+        deserializer.deserialize_tuple(N, ArrayVisitor::<T, N>(PhantomData))
+    }
+}
+
+/// 一个代表曲线校验错误的枚举。
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CurveError {
+    /// 节点个数与硬件要求不符。
+    Length,
+    /// 曲线不满足单调性要求。
+    NotMonotonic,
+    /// 节点取值超出硬件范围。
+    OutOfRange,
+    /// 分段间距不满足硬件要求。
+    BadSegment,
+}
+
+impl std::fmt::Display for CurveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let desc = match self {
+            CurveError::Length => "Bad Length",
+            CurveError::NotMonotonic => "Not Monotonic",
+            CurveError::OutOfRange => "Out of Range",
+            CurveError::BadSegment => "Bad Segment",
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+impl std::error::Error for CurveError {}
+
 /// 一个代表摄像头朝向的枚举。
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CameraFacing {
     #[default]
     Back,
@@ -142,6 +314,7 @@ impl std::str::FromStr for CameraFacing {
 
 /// 一个代表摄像头模块信息的类型。
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CameraModuleInfo {
     /// 摄像头模块编号。
     pub index: usize,
@@ -0,0 +1,239 @@
+//! 亮度降噪
+//!
+//! YNR V2 模块在 YUV 域对亮度通道进行降噪，按亮度分段的噪声曲线描述噪声水平，
+//! 低频与高频分量分别使用独立的滤波强度。
+use super::context::Context;
+use super::error::XCamError;
+use super::ffi;
+use super::nr::{validate_iso_table, validate_shared, IsoInterpolate, IsoParams};
+use super::types::{OpMode, XCamResult, YnrAttr, YnrParamsSelect};
+
+/// 亮度噪声曲线的节点个数。
+pub const YNR_SIGMA_CURVE_LEN: usize = 17;
+
+/// 自动参数表的 ISO 档位数。
+pub const YNR_ISO_NUM: usize = 13;
+
+/// 一个代表 YNR 噪声曲线的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct YnrSigmaCurve {
+    /// 亮度节点。
+    pub luma_points: [i16; YNR_SIGMA_CURVE_LEN],
+    /// 各亮度节点对应的噪声标准差。
+    pub sigma: [f32; YNR_SIGMA_CURVE_LEN],
+}
+
+/// 一个代表 YNR 参数的类型。
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct YnrParams {
+    /// 噪声曲线。
+    pub sigma_curve: YnrSigmaCurve,
+    /// 按距图像中心半径分段的降噪强度。
+    pub radial_strength: [f32; YNR_SIGMA_CURVE_LEN],
+    /// 低频降噪阈值调整系数。
+    pub low_thred_adj: f32,
+    /// 低频双边滤波强度。
+    pub low_filt_strength: [f32; 2],
+    /// 低频降噪结果的融合权重，取值范围 [0,1]。
+    pub low_weight: f32,
+    /// 高频降噪阈值调整系数。
+    pub high_thred_adj: f32,
+    /// 高频降噪结果的融合权重，取值范围 [0,1]。
+    pub high_weight: f32,
+}
+
+impl YnrParams {
+    /// 从 AIQ 参数中提取类型化的参数。
+    pub fn from_select(val: &YnrParamsSelect) -> Self {
+        Self {
+            sigma_curve: YnrSigmaCurve {
+                luma_points: val.lumaPoints_V2.map(|x| x as _),
+                sigma: val.noiseSigma_V2,
+            },
+            radial_strength: val.ynr_rnr_strength_V2,
+            low_thred_adj: val.ynr_low_thred_adj_V2,
+            low_filt_strength: val.ynr_low_filt_strength_V2,
+            low_weight: val.ynr_low_weight_V2,
+            high_thred_adj: val.ynr_high_thred_adj_V2,
+            high_weight: val.ynr_high_weight_V2,
+        }
+    }
+
+    /// 将参数写入 AIQ 参数，未建模的字段保持不变。
+    pub fn apply_to(&self, val: &mut YnrParamsSelect) {
+        val.lumaPoints_V2 = self.sigma_curve.luma_points.map(|x| x as _);
+        val.noiseSigma_V2 = self.sigma_curve.sigma;
+        val.ynr_rnr_strength_V2 = self.radial_strength;
+        val.ynr_low_thred_adj_V2 = self.low_thred_adj;
+        val.ynr_low_filt_strength_V2 = self.low_filt_strength;
+        val.ynr_low_weight_V2 = self.low_weight;
+        val.ynr_high_thred_adj_V2 = self.high_thred_adj;
+        val.ynr_high_weight_V2 = self.high_weight;
+    }
+}
+
+impl IsoInterpolate for YnrParams {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            sigma_curve: YnrSigmaCurve {
+                luma_points: self.sigma_curve.luma_points,
+                sigma: self.sigma_curve.sigma.lerp(&other.sigma_curve.sigma, t),
+            },
+            radial_strength: self.radial_strength.lerp(&other.radial_strength, t),
+            low_thred_adj: self.low_thred_adj.lerp(&other.low_thred_adj, t),
+            low_filt_strength: self.low_filt_strength.lerp(&other.low_filt_strength, t),
+            low_weight: self.low_weight.lerp(&other.low_weight, t),
+            high_thred_adj: self.high_thred_adj.lerp(&other.high_thred_adj, t),
+            high_weight: self.high_weight.lerp(&other.high_weight, t),
+        }
+    }
+}
+
+/// 一个描述亮度降噪控制的契定。
+pub trait LumaNoiseRemoval {
+    /// 获取 YNR V2 属性。
+    fn get_ynr_attrib(&self) -> XCamResult<YnrAttr>;
+
+    /// 设置 YNR V2 属性。
+    fn set_ynr_attrib<T: Into<YnrAttr>>(&self, attr: T) -> XCamResult<()>;
+
+    /// 获取 YNR 工作模式。
+    fn get_ynr_mode(&self) -> XCamResult<OpMode>;
+
+    /// 设置 YNR 工作模式，仅支持 `Auto` 及 `Manual`。
+    fn set_ynr_mode(&self, mode: OpMode) -> XCamResult<()>;
+
+    /// 获取自动模式下按 ISO 索引的参数表。
+    fn get_ynr_auto(&self) -> XCamResult<Vec<IsoParams<YnrParams>>>;
+
+    /// 设置自动模式下按 ISO 索引的参数表，并切换至自动模式。
+    ///
+    /// # Parameters
+    /// * `table` - 参数表，须包含 [`YNR_ISO_NUM`] 个档位且 ISO 单调递增，
+    ///   各档位噪声曲线的 `luma_points` 须一致。
+    fn set_ynr_auto(&self, table: &[IsoParams<YnrParams>]) -> XCamResult<()>;
+
+    /// 获取手动模式参数。
+    fn get_ynr_manual(&self) -> XCamResult<YnrParams>;
+
+    /// 设置手动模式参数，并切换至手动模式。
+    fn set_ynr_manual(&self, params: &YnrParams) -> XCamResult<()>;
+
+    /// 获取亮度降噪强度，取值范围 [0,1]。
+    fn get_ynr_strength(&self) -> XCamResult<f32>;
+
+    /// 设置亮度降噪强度，取值范围 [0,1]，0.5 为 IQ 默认强度。
+    fn set_ynr_strength(&self, percent: f32) -> XCamResult<()>;
+}
+
+impl LumaNoiseRemoval for Context {
+    fn get_ynr_attrib(&self) -> XCamResult<YnrAttr> {
+        let mut attr = YnrAttr::default();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_aynrV2_GetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+            .map(|_| attr)
+        }
+    }
+
+    fn set_ynr_attrib<T: Into<YnrAttr>>(&self, attr: T) -> XCamResult<()> {
+        let mut attr = attr.into();
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_aynrV2_SetAttrib(
+                self.lock().as_ptr(),
+                &mut attr,
+            ))
+            .ok()
+        }
+    }
+
+    fn get_ynr_mode(&self) -> XCamResult<OpMode> {
+        use ffi::Aynr_OPMode_t::*;
+        self.get_ynr_attrib().map(|attr| match attr.eMode {
+            AYNR_OP_MODE_AUTO => OpMode::Auto,
+            AYNR_OP_MODE_MANUAL => OpMode::Manual,
+            _ => OpMode::Invalid,
+        })
+    }
+
+    fn set_ynr_mode(&self, mode: OpMode) -> XCamResult<()> {
+        let _lock = self.lock();
+        use ffi::Aynr_OPMode_t::*;
+        let mut attr = self.get_ynr_attrib()?;
+        attr.eMode = match mode {
+            OpMode::Auto => AYNR_OP_MODE_AUTO,
+            OpMode::Manual => AYNR_OP_MODE_MANUAL,
+            _ => return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)),
+        };
+        self.set_ynr_attrib(attr)
+    }
+
+    fn get_ynr_auto(&self) -> XCamResult<Vec<IsoParams<YnrParams>>> {
+        let attr = self.get_ynr_attrib()?;
+        let p = &attr.stAuto.stParams;
+        let table = (0..YNR_ISO_NUM)
+            .map(|i| IsoParams {
+                iso: p.iso[i],
+                params: YnrParams::from_select(&p.arYnrParamsISO[i]),
+            })
+            .collect();
+        Ok(table)
+    }
+
+    fn set_ynr_auto(&self, table: &[IsoParams<YnrParams>]) -> XCamResult<()> {
+        let _lock = self.lock();
+        validate_iso_table(table, YNR_ISO_NUM)?;
+        validate_shared(table, |p| p.sigma_curve.luma_points)?;
+        let mut attr = self.get_ynr_attrib()?;
+        attr.eMode = ffi::Aynr_OPMode_t::AYNR_OP_MODE_AUTO;
+        let p = &mut attr.stAuto.stParams;
+        for (i, v) in table.iter().enumerate() {
+            p.iso[i] = v.iso;
+            v.params.apply_to(&mut p.arYnrParamsISO[i]);
+        }
+        self.set_ynr_attrib(attr)
+    }
+
+    fn get_ynr_manual(&self) -> XCamResult<YnrParams> {
+        self.get_ynr_attrib()
+            .map(|attr| YnrParams::from_select(&attr.stManual.stSelect))
+    }
+
+    fn set_ynr_manual(&self, params: &YnrParams) -> XCamResult<()> {
+        let _lock = self.lock();
+        let mut attr = self.get_ynr_attrib()?;
+        attr.eMode = ffi::Aynr_OPMode_t::AYNR_OP_MODE_MANUAL;
+        params.apply_to(&mut attr.stManual.stSelect);
+        self.set_ynr_attrib(attr)
+    }
+
+    fn get_ynr_strength(&self) -> XCamResult<f32> {
+        let mut percent: f32 = 0.0;
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_aynrV2_GetStrength(
+                self.lock().as_ptr(),
+                &mut percent,
+            ))
+            .ok()
+            .map(|_| percent)
+        }
+    }
+
+    fn set_ynr_strength(&self, percent: f32) -> XCamResult<()> {
+        if !(0.0..=1.0).contains(&percent) {
+            return Err(XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM));
+        }
+        unsafe {
+            XCamError::from(ffi::rk_aiq_user_api2_aynrV2_SetStrength(
+                self.lock().as_ptr(),
+                percent,
+            ))
+            .ok()
+        }
+    }
+}
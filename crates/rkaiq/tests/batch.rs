@@ -0,0 +1,149 @@
+//! 批量设置的暂存、单次写回及失败回滚。
+mod mock;
+
+use rkaiq::batch::{BatchError, BatchModule};
+use rkaiq::ffi;
+use rkaiq::prelude::*;
+use rkaiq::types::OpMode;
+
+fn failed() -> XCamError {
+    XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_FAILED)
+}
+
+#[test]
+fn test_batch_staging_error_writes_nothing() {
+    const NAME: &str = "mock-batch-staging";
+    let ctx = mock::context(NAME);
+    let err = ctx
+        .batch(|b| {
+            b.enable_bayernr2d().enable_cnr();
+            b.set_ynr_mode(OpMode::SemiAuto).enable_ynr();
+        })
+        .unwrap_err();
+    assert_eq!(
+        err,
+        BatchError {
+            module: BatchModule::Ynr,
+            error: XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM),
+            applied: vec![],
+            rollback_failures: vec![],
+        }
+    );
+    mock::backend(NAME, |b| {
+        assert_eq!((b.bayernr.sets, b.cnr.sets, b.ynr.sets), (0, 0, 0));
+        assert_eq!(b.bayernr.value.stAuto.bayernr2DEn, 0);
+        assert_eq!(b.cnr.value.stAuto.cnrEn, 0);
+    });
+}
+
+#[test]
+fn test_batch_toggle_rejects_unknown_mode() {
+    const NAME: &str = "mock-batch-unknown-mode";
+    let ctx = mock::context(NAME);
+    mock::backend(NAME, |b| {
+        b.cnr.value.eMode = ffi::Acnr_OPMode_t::ACNR_OP_MODE_INVALID
+    });
+    let err = ctx
+        .batch(|b| {
+            b.enable_bayernr2d().enable_cnr();
+        })
+        .unwrap_err();
+    assert_eq!(err.module, BatchModule::Cnr);
+    assert_eq!(
+        err.error,
+        XCamError::from(ffi::XCamReturn::XCAM_RETURN_ERROR_PARAM)
+    );
+    assert!(err.applied.is_empty());
+    mock::backend(NAME, |b| {
+        assert_eq!((b.bayernr.sets, b.cnr.sets), (0, 0));
+    });
+}
+
+#[test]
+fn test_batch_commits_once_per_module() {
+    const NAME: &str = "mock-batch-commit";
+    let ctx = mock::context(NAME);
+    ctx.batch(|b| {
+        b.enable_bayernr2d().enable_bayernr3d().disable_bayernr3d();
+        b.set_cnr_mode(OpMode::Manual).enable_cnr();
+        b.enable_ynr();
+    })
+    .unwrap();
+    mock::backend(NAME, |b| {
+        assert_eq!((b.bayernr.sets, b.cnr.sets, b.ynr.sets), (1, 1, 1));
+        assert_ne!(b.bayernr.value.stAuto.bayernr2DEn, 0);
+        assert_eq!(b.bayernr.value.stAuto.bayernr3DEn, 0);
+        // 先切换模式时，开关作用于新模式。
+        assert_eq!(b.cnr.value.eMode, ffi::Acnr_OPMode_t::ACNR_OP_MODE_MANUAL);
+        assert_ne!(b.cnr.value.stManual.cnrEn, 0);
+        assert_eq!(b.cnr.value.stAuto.cnrEn, 0);
+        assert_ne!(b.ynr.value.stAuto.ynrEn, 0);
+    });
+
+    // 未涉及的模块不读写。
+    ctx.batch(|b| {
+        b.disable_ynr();
+    })
+    .unwrap();
+    mock::backend(NAME, |b| {
+        assert_eq!((b.bayernr.sets, b.cnr.sets, b.ynr.sets), (1, 1, 2));
+    });
+}
+
+#[test]
+fn test_batch_rollback_on_failure() {
+    const NAME: &str = "mock-batch-rollback";
+    let ctx = mock::context(NAME);
+    mock::backend(NAME, |b| b.cnr.fail_at = Some(1));
+    let err = ctx
+        .batch(|b| {
+            b.enable_bayernr2d().enable_cnr().enable_ynr();
+        })
+        .unwrap_err();
+    assert_eq!(err.module, BatchModule::Cnr);
+    assert_eq!(err.error, failed());
+    assert_eq!(err.applied, vec![BatchModule::BayerNr]);
+    assert!(err.rollback_failures.is_empty());
+    assert_eq!(err.to_string(), "Apply CNR failed: Failed");
+    mock::backend(NAME, |b| {
+        // BayerNR 写回后又恢复为提交前的属性，YNR 未写回。
+        assert_eq!((b.bayernr.sets, b.cnr.sets, b.ynr.sets), (2, 1, 0));
+        assert_eq!(b.bayernr.value.stAuto.bayernr2DEn, 0);
+        assert_eq!(b.cnr.value.stAuto.cnrEn, 0);
+    });
+
+    // 注入的失败仅作用于第一次写回，随后的单项开关正常生效。
+    assert_eq!(ctx.enable_cnr(), Ok(()));
+    mock::backend(NAME, |b| assert_ne!(b.cnr.value.stAuto.cnrEn, 0));
+}
+
+#[test]
+fn test_batch_reports_rollback_failures() {
+    const NAME: &str = "mock-batch-rollback-failure";
+    let ctx = mock::context(NAME);
+    mock::backend(NAME, |b| {
+        b.bayernr.fail_at = Some(2);
+        b.ynr.fail_at = Some(1);
+    });
+    let err = ctx
+        .batch(|b| {
+            b.enable_bayernr3d().disable_cnr().enable_ynr();
+        })
+        .unwrap_err();
+    assert_eq!(err.module, BatchModule::Ynr);
+    assert_eq!(err.applied, vec![BatchModule::BayerNr, BatchModule::Cnr]);
+    assert_eq!(
+        err.rollback_failures,
+        vec![(BatchModule::BayerNr, failed())]
+    );
+    assert_eq!(
+        err.to_string(),
+        "Apply YNR failed: Failed, rollback failed on BayerNR(Failed)"
+    );
+    assert_eq!(XCamError::from(err), failed());
+    mock::backend(NAME, |b| {
+        // 按相反顺序回滚：CNR 恢复成功，BayerNR 恢复失败并保留新值。
+        assert_eq!((b.bayernr.sets, b.cnr.sets, b.ynr.sets), (2, 2, 1));
+        assert_ne!(b.bayernr.value.stAuto.bayernr3DEn, 0);
+    });
+}
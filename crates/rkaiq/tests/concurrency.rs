@@ -0,0 +1,77 @@
+//! 多线程并发调用 Context 方法时不应丢失更新。
+mod mock;
+
+use rkaiq::prelude::*;
+use std::thread;
+
+const ROUNDS: usize = 500;
+
+type Toggle = fn(&Context) -> XCamResult<()>;
+
+#[test]
+fn test_nr_toggles_no_lost_updates() {
+    const NAME: &str = "mock-nr-toggles";
+    let ctx = mock::context(NAME);
+    // 2D/3D 开关位于同一份 BayerNR 属性中，读取-修改-写回未串行化时会相互覆盖。
+    let toggles: [(Toggle, Toggle); 4] = [
+        (Context::enable_bayernr2d, Context::disable_bayernr2d),
+        (Context::enable_bayernr3d, Context::disable_bayernr3d),
+        (Context::enable_cnr, Context::disable_cnr),
+        (Context::enable_ynr, Context::disable_ynr),
+    ];
+    thread::scope(|s| {
+        for (enable, disable) in toggles {
+            let ctx = &ctx;
+            s.spawn(move || {
+                for _ in 0..ROUNDS {
+                    disable(ctx).unwrap();
+                    enable(ctx).unwrap();
+                }
+            });
+        }
+    });
+    mock::backend(NAME, |b| {
+        assert_eq!(b.conflicts(), 0);
+        assert_eq!(b.bayernr.sets, 4 * ROUNDS);
+        assert_eq!(b.cnr.sets, 2 * ROUNDS);
+        assert_eq!(b.ynr.sets, 2 * ROUNDS);
+        assert_ne!(b.bayernr.value.stAuto.bayernr2DEn, 0);
+        assert_ne!(b.bayernr.value.stAuto.bayernr3DEn, 0);
+        assert_ne!(b.cnr.value.stAuto.cnrEn, 0);
+        assert_ne!(b.ynr.value.stAuto.ynrEn, 0);
+    });
+}
+
+#[cfg(any(feature = "v4_0", feature = "v5_0"))]
+#[test]
+fn test_manual_exp_no_lost_updates() {
+    const NAME: &str = "mock-manual-exp";
+    const THREADS: usize = 4;
+    let ctx = mock::context(NAME);
+    thread::scope(|s| {
+        for i in 0..THREADS {
+            let ctx = &ctx;
+            s.spawn(move || {
+                for _ in 0..ROUNDS {
+                    ctx.set_manual_exp(1.0 + i as f32, 0.01).unwrap();
+                }
+            });
+        }
+        // 与曝光并发修改其他模块，验证不同模块之间互不影响。
+        s.spawn(|| {
+            for _ in 0..ROUNDS {
+                ctx.disable_cnr().unwrap();
+                ctx.enable_cnr().unwrap();
+            }
+        });
+    });
+    mock::backend(NAME, |b| {
+        assert_eq!(b.conflicts(), 0);
+        assert_eq!(b.exp.sets, THREADS * ROUNDS);
+        let linear = &b.exp.value.stManual.LinearAE;
+        assert!(linear.ManualGainEn && linear.ManualTimeEn);
+        assert!((1.0..=THREADS as f32).contains(&linear.GainValue));
+        assert_eq!(linear.TimeValue, 0.01);
+        assert_ne!(b.cnr.value.stAuto.cnrEn, 0);
+    });
+}
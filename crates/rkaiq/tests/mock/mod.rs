@@ -0,0 +1,239 @@
+//! 模拟 AIQ 后端
+//!
+//! 以同名的 `#[no_mangle]` 符号覆盖 librkaiq 中的部分 uapi 接口，属性保存在进程内，
+//! 使集成测试无需硬件即可驱动真实的 `Context` 方法。每个上下文以传感器名称区分，
+//! 测试之间使用不同的名称即可互不干扰。
+//!
+//! 每份属性记录写回次数，并检测丢失更新：写回时若该线程读取之后属性已被其他调用写回，
+//! 则计为一次冲突。读取与写回时主动让出 CPU，以放大未加锁时的竞争窗口。
+#![allow(dead_code)]
+
+use rkaiq::ffi;
+use rkaiq::prelude::*;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::thread::{self, ThreadId};
+
+/// 一个代表单个模块属性的类型。
+pub struct Slot<T> {
+    /// 当前属性。
+    pub value: T,
+    /// 写回次数，含注入失败的调用。
+    pub sets: usize,
+    /// 检测到的丢失更新次数。
+    pub conflicts: usize,
+    /// 第几次写回（从 1 开始）返回失败。
+    pub fail_at: Option<usize>,
+    version: u64,
+    reads: HashMap<ThreadId, u64>,
+}
+
+impl<T: Copy> Slot<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            sets: 0,
+            conflicts: 0,
+            fail_at: None,
+            version: 0,
+            reads: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self) -> T {
+        self.reads.insert(thread::current().id(), self.version);
+        self.value
+    }
+
+    fn set(&mut self, value: T) -> ffi::XCamReturn {
+        self.sets += 1;
+        if self.fail_at == Some(self.sets) {
+            return ffi::XCamReturn::XCAM_RETURN_ERROR_FAILED;
+        }
+        if self.reads.get(&thread::current().id()) != Some(&self.version) {
+            self.conflicts += 1;
+        }
+        self.version += 1;
+        self.value = value;
+        ffi::XCamReturn::XCAM_RETURN_NO_ERROR
+    }
+}
+
+/// 一个代表单个上下文模拟状态的类型。
+pub struct Backend {
+    ptr: usize,
+    pub bayernr: Slot<ffi::rk_aiq_bayernr_attrib_v2_t>,
+    pub cnr: Slot<ffi::rk_aiq_cnr_attrib_v1_t>,
+    pub ynr: Slot<ffi::rk_aiq_ynr_attrib_v2_t>,
+    #[cfg(any(feature = "v4_0", feature = "v5_0"))]
+    pub exp: Slot<ffi::Uapi_ExpSwAttrV2_t>,
+}
+
+// SAFETY: 属性均为 C 结构体的副本，仅在全局锁内访问。
+unsafe impl Send for Backend {}
+
+impl Backend {
+    fn new(ptr: usize) -> Self {
+        let bayernr = ffi::rk_aiq_bayernr_attrib_v2_t {
+            eMode: ffi::Abayernr_OPMode_t::ABAYERNR_OP_MODE_AUTO,
+            ..Default::default()
+        };
+        let cnr = ffi::rk_aiq_cnr_attrib_v1_t {
+            eMode: ffi::Acnr_OPMode_t::ACNR_OP_MODE_AUTO,
+            ..Default::default()
+        };
+        let ynr = ffi::rk_aiq_ynr_attrib_v2_t {
+            eMode: ffi::Aynr_OPMode_t::AYNR_OP_MODE_AUTO,
+            ..Default::default()
+        };
+        Self {
+            ptr,
+            bayernr: Slot::new(bayernr),
+            cnr: Slot::new(cnr),
+            ynr: Slot::new(ynr),
+            #[cfg(any(feature = "v4_0", feature = "v5_0"))]
+            exp: Slot::new(ffi::Uapi_ExpSwAttrV2_t::default()),
+        }
+    }
+
+    /// 全部模块检测到的丢失更新次数之和。
+    pub fn conflicts(&self) -> usize {
+        let n = self.bayernr.conflicts + self.cnr.conflicts + self.ynr.conflicts;
+        #[cfg(any(feature = "v4_0", feature = "v5_0"))]
+        let n = n + self.exp.conflicts;
+        n
+    }
+}
+
+fn backends() -> MutexGuard<'static, HashMap<String, Backend>> {
+    static BACKENDS: OnceLock<Mutex<HashMap<String, Backend>>> = OnceLock::new();
+    BACKENDS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 创建一个使用模拟后端的上下文。
+pub fn context(name: &str) -> Context {
+    Context::new(name, "/tmp").unwrap()
+}
+
+/// 访问指定上下文的模拟状态。
+pub fn backend<R>(name: &str, f: impl FnOnce(&mut Backend) -> R) -> R {
+    f(backends().get_mut(name).expect("unknown mock context"))
+}
+
+fn with_ctx<R>(ctx: *const ffi::rk_aiq_sys_ctx_t, f: impl FnOnce(&mut Backend) -> R) -> R {
+    let mut backends = backends();
+    let backend = backends
+        .values_mut()
+        .find(|b| b.ptr == ctx as usize)
+        .expect("unknown mock context");
+    f(backend)
+}
+
+unsafe fn get_attrib<T: Copy>(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: *mut T,
+    slot: impl FnOnce(&mut Backend) -> &mut Slot<T>,
+) -> ffi::XCamReturn {
+    *attr = with_ctx(ctx, |b| slot(b).get());
+    thread::yield_now();
+    ffi::XCamReturn::XCAM_RETURN_NO_ERROR
+}
+
+fn set_attrib<T: Copy>(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: T,
+    slot: impl FnOnce(&mut Backend) -> &mut Slot<T>,
+) -> ffi::XCamReturn {
+    thread::yield_now();
+    with_ctx(ctx, |b| slot(b).set(attr))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_uapi2_sysctl_init(
+    sns_ent_name: *const c_char,
+    _iq_file_dir: *const c_char,
+    _err_cb: ffi::rk_aiq_error_cb,
+    _metas_cb: ffi::rk_aiq_metas_cb,
+) -> *mut ffi::rk_aiq_sys_ctx_t {
+    let name = CStr::from_ptr(sns_ent_name).to_string_lossy().into_owned();
+    let ptr = Box::into_raw(Box::new(0u8)) as usize;
+    backends().insert(name, Backend::new(ptr));
+    ptr as *mut ffi::rk_aiq_sys_ctx_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_uapi2_sysctl_deinit(ctx: *mut ffi::rk_aiq_sys_ctx_t) {
+    backends().retain(|_, b| b.ptr != ctx as usize);
+    drop(Box::from_raw(ctx as *mut u8));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_user_api2_abayernrV2_GetAttrib(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: *mut ffi::rk_aiq_bayernr_attrib_v2_t,
+) -> ffi::XCamReturn {
+    get_attrib(ctx, attr, |b| &mut b.bayernr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_user_api2_abayernrV2_SetAttrib(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: *mut ffi::rk_aiq_bayernr_attrib_v2_t,
+) -> ffi::XCamReturn {
+    set_attrib(ctx, *attr, |b| &mut b.bayernr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_user_api2_acnrV1_GetAttrib(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: *mut ffi::rk_aiq_cnr_attrib_v1_t,
+) -> ffi::XCamReturn {
+    get_attrib(ctx, attr, |b| &mut b.cnr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_user_api2_acnrV1_SetAttrib(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: *mut ffi::rk_aiq_cnr_attrib_v1_t,
+) -> ffi::XCamReturn {
+    set_attrib(ctx, *attr, |b| &mut b.cnr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_user_api2_aynrV2_GetAttrib(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: *mut ffi::rk_aiq_ynr_attrib_v2_t,
+) -> ffi::XCamReturn {
+    get_attrib(ctx, attr, |b| &mut b.ynr)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_user_api2_aynrV2_SetAttrib(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: *mut ffi::rk_aiq_ynr_attrib_v2_t,
+) -> ffi::XCamReturn {
+    set_attrib(ctx, *attr, |b| &mut b.ynr)
+}
+
+#[cfg(any(feature = "v4_0", feature = "v5_0"))]
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_user_api2_ae_getExpSwAttr(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: *mut ffi::Uapi_ExpSwAttrV2_t,
+) -> ffi::XCamReturn {
+    get_attrib(ctx, attr, |b| &mut b.exp)
+}
+
+#[cfg(any(feature = "v4_0", feature = "v5_0"))]
+#[no_mangle]
+pub unsafe extern "C" fn rk_aiq_user_api2_ae_setExpSwAttr(
+    ctx: *const ffi::rk_aiq_sys_ctx_t,
+    attr: ffi::Uapi_ExpSwAttrV2_t,
+) -> ffi::XCamReturn {
+    set_attrib(ctx, attr, |b| &mut b.exp)
+}